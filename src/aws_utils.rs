@@ -2,8 +2,15 @@
 //!
 //! This module provides utility functions for interacting with AWS services:
 //! - Loading and configuring the AWS SDK
-//! - Listing available S3 buckets
+//! - Listing available S3 buckets, retrying transient failures, and choosing the one
+//!   to upload to
 //! - Determining the region for a specific S3 bucket
+//! - Computing object keys and confirming freshly uploaded objects are readable
+//! - Checking the KMS key used to encrypt uploads (`aws.kms_key_id`)
+//! - Creating presigned links to uploaded objects
+//! - Inspecting objects given as `s3://bucket/key` input
+//! - Resolving `secretsmanager://` webhook endpoints from AWS Secrets Manager
+//! - Retrying throttled Bedrock and Transcribe calls with exponential backoff
 //!
 //! These functions abstract away the details of AWS API interactions and provide
 //! a simpler interface for the main application to use.
@@ -14,21 +21,76 @@
 //! - AWS credentials file (~/.aws/credentials)
 //! - IAM roles for EC2 or ECS
 //!
+//! A named profile can be selected with `--profile <NAME>`, which takes precedence over
+//! the `AWS_PROFILE` environment variable, which in turn beats the default profile.
+//!
 //! ## Usage
 //! These utilities are used throughout the application to interact with AWS services,
 //! particularly for S3 operations and regional configuration.
 
-use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Context, Result};
 use aws_config::meta::region::RegionProviderChain;
 use aws_config::{Region, SdkConfig};
 use aws_sdk_s3::config::StalledStreamProtectionConfig;
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::list_buckets::ListBucketsError;
+use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::Client;
+use tokio::time::{sleep, Duration};
+
+/// Number of HeadObject attempts before giving up on a freshly uploaded object
+const OBJECT_VISIBLE_ATTEMPTS: u32 = 5;
+
+/// Number of ListBuckets attempts before a transient failure stops the run
+const LIST_BUCKETS_ATTEMPTS: u32 = 3;
+
+/// S3 error codes worth retrying besides 5xx responses
+const TRANSIENT_ERROR_CODES: &[&str] = &["SlowDown", "Throttling", "ThrottlingException", "RequestTimeout"];
+
+/// Bedrock and Transcribe error codes worth retrying besides 5xx responses
+const THROTTLING_ERROR_CODES: &[&str] = &[
+    "ThrottlingException",
+    "TooManyRequestsException",
+    "LimitExceededException",
+    "ServiceUnavailableException",
+    "ModelNotReadyException",
+];
+
+/// Delay before the first retry of a throttled call; it doubles with every retry
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between two attempts of a throttled call
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Retries of a throttled Bedrock or Transcribe call when none are configured
+pub const DEFAULT_MAX_RETRIES: u32 = 5;
+
+/// URL scheme of audio files that are already in S3
+const S3_SCHEME: &str = "s3://";
+
+/// Response header naming the region of a bucket, also sent with redirects and errors
+const BUCKET_REGION_HEADER: &str = "x-amz-bucket-region";
+
+/// URL scheme marking a value that should be read from AWS Secrets Manager
+const SECRETS_MANAGER_SCHEME: &str = "secretsmanager://";
+
+/// Secret values already fetched during this run, keyed by secret name
+static SECRET_CACHE: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// AWS profile selected with `--profile`, used where no SDK configuration is passed in
+static PROFILE_NAME: Mutex<Option<String>> = Mutex::new(None);
 
 /// Loads and configures the AWS SDK with appropriate settings
 ///
 /// # Arguments
 ///
 /// * `region` - Optional AWS region to use; if None, uses the default provider chain or falls back to us-east-1
+/// * `profile` - Optional named profile from the AWS config and credentials files
 ///
 /// # Returns
 ///
@@ -36,9 +98,15 @@ use aws_sdk_s3::Client;
 ///
 /// Configures the AWS SDK using environment variables and credentials files.
 /// Sets the specified region or uses the default provider chain.
+/// A given profile overrides `AWS_PROFILE`; without one, `AWS_PROFILE` or the default
+/// profile is used. The profile is also remembered for `resolve_endpoint()`.
 /// Disables stalled stream protection to resolve issues with large S3 file uploads.
-pub async fn load_config(region: Option<Region>) -> SdkConfig {
+pub async fn load_config(region: Option<Region>, profile: Option<&str>) -> SdkConfig {
     let mut config = aws_config::from_env();
+    if let Some(profile) = profile {
+        config = config.profile_name(profile);
+        *PROFILE_NAME.lock().unwrap() = Some(profile.to_string());
+    }
     match region {
         Some(region) => config = config.region(region),
         None => {
@@ -56,6 +124,105 @@ pub async fn load_config(region: Option<Region>) -> SdkConfig {
     config.load().await
 }
 
+/// Copies an SDK configuration for another region
+///
+/// # Arguments
+///
+/// * `config` - The configuration loaded by `load_config()`
+/// * `region` - The region to use instead
+///
+/// # Returns
+///
+/// The configuration with the new region, keeping its credentials (and so the profile)
+pub fn with_region(config: &SdkConfig, region: Region) -> SdkConfig {
+    config.to_builder().region(region).build()
+}
+
+/// Error returned by `list_buckets()` when the credentials may not list buckets
+#[derive(Debug, Clone, Copy)]
+pub struct ListBucketsDeniedError;
+
+impl fmt::Display for ListBucketsDeniedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Insufficient permissions to list buckets; set aws.s3_bucket_name to use a specific bucket"
+        )
+    }
+}
+
+impl std::error::Error for ListBucketsDeniedError {}
+
+/// How the S3 bucket for the upload is chosen (see `choose_bucket()`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BucketChoice {
+    /// Use this bucket; `verified` is false when the bucket could not be checked because
+    /// the credentials may not list buckets
+    Use { name: String, verified: bool },
+    /// Let the user pick one of these buckets; `not_found` holds the configured bucket
+    /// when it was not among them
+    Ask { buckets: Vec<String>, not_found: Option<String> },
+}
+
+/// Decides which S3 bucket to upload to
+///
+/// # Arguments
+///
+/// * `s3_bucket_name` - The configured `aws.s3_bucket_name`, possibly empty
+/// * `buckets` - The result of `list_buckets()`
+/// * `interactive` - Whether the user may be asked to pick a bucket
+///
+/// # Returns
+///
+/// A Result containing the bucket to use or the buckets to choose from, or an error if
+/// no bucket can be used
+///
+/// A configured bucket is used when it is listed, or unchecked when the credentials may
+/// not list buckets. Otherwise the listed buckets are offered, unless `interactive` is
+/// false (`--non-interactive`): then a missing or unknown configured bucket is an error.
+/// Nothing is printed here; the caller reports the choice.
+pub fn choose_bucket(s3_bucket_name: &str, buckets: &Result<Vec<String>>, interactive: bool) -> Result<BucketChoice> {
+    let list_denied = matches!(buckets, Err(err) if err.is::<ListBucketsDeniedError>());
+
+    if !s3_bucket_name.is_empty() {
+        if list_denied {
+            // Without s3:ListAllMyBuckets the bucket can't be checked; uploading will tell
+            return Ok(BucketChoice::Use {
+                name: s3_bucket_name.to_string(),
+                verified: false,
+            });
+        }
+        if buckets
+            .as_ref()
+            .is_ok_and(|buckets| buckets.iter().any(|bucket| bucket == s3_bucket_name))
+        {
+            return Ok(BucketChoice::Use {
+                name: s3_bucket_name.to_string(),
+                verified: true,
+            });
+        }
+        if !interactive && buckets.is_ok() {
+            bail!("\n❌ The configured S3 bucket '{}' was not found.", s3_bucket_name);
+        }
+    } else if !interactive {
+        bail!("\n❌ --non-interactive needs aws.s3_bucket_name in config.toml to choose the S3 bucket");
+    }
+
+    match buckets {
+        Ok(bucket_names) if bucket_names.is_empty() => {
+            bail!("\nNo S3 buckets found. Please create an S3 bucket first.");
+        }
+        Ok(bucket_names) => Ok(BucketChoice::Ask {
+            buckets: bucket_names.clone(),
+            not_found: (!s3_bucket_name.is_empty()).then(|| s3_bucket_name.to_string()),
+        }),
+        Err(_) if list_denied => {
+            bail!("\n❌ {}", ListBucketsDeniedError);
+        }
+        Err(err) => bail!("\nError getting bucket list: {}", err),
+    }
+}
+
 /// Lists all S3 buckets available to the authenticated user
 ///
 /// # Arguments
@@ -67,9 +234,37 @@ pub async fn load_config(region: Option<Region>) -> SdkConfig {
 /// A Result containing a vector of bucket names or an error
 ///
 /// Makes an API call to S3 to list all buckets, extracts the bucket names
-/// from the response, and returns them as a vector of strings.
+/// from the response, and returns them as a vector of strings. Fails with
+/// `ListBucketsDeniedError` when the credentials lack `s3:ListAllMyBuckets`.
+/// Transient failures (timeouts, connection errors, throttling and 5xx responses) are
+/// retried up to `LIST_BUCKETS_ATTEMPTS` times with a growing delay.
 pub async fn list_buckets(client: &Client) -> Result<Vec<String>> {
-    let resp = client.list_buckets().send().await?;
+    let mut delay = Duration::from_millis(500);
+    let mut attempt = 1;
+
+    let resp = loop {
+        match client.list_buckets().send().await {
+            Ok(resp) => break resp,
+            Err(err) if err.as_service_error().and_then(|err| err.code()) == Some("AccessDenied") => {
+                log::debug!("ListBuckets denied: {}", DisplayErrorContext(&err));
+                return Err(ListBucketsDeniedError.into());
+            }
+            Err(err) if is_transient(&err) && attempt < LIST_BUCKETS_ATTEMPTS => {
+                log::debug!("ListBuckets failed (attempt {}): {}", attempt, DisplayErrorContext(&err));
+                sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) if is_transient(&err) => {
+                return Err(anyhow!(
+                    "❌ Could not list S3 buckets after {} attempts: {}. Check your network connection, or set aws.s3_bucket_name to skip listing buckets.",
+                    attempt,
+                    DisplayErrorContext(&err)
+                ));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    };
     let buckets = resp.buckets();
 
     let bucket_names: Vec<String> = buckets
@@ -80,6 +275,114 @@ pub async fn list_buckets(client: &Client) -> Result<Vec<String>> {
     Ok(bucket_names)
 }
 
+/// Whether a failed ListBuckets call may succeed when tried again
+fn is_transient(err: &SdkError<ListBucketsError>) -> bool {
+    match err {
+        SdkError::TimeoutError(_) | SdkError::ResponseError(_) => true,
+        SdkError::DispatchFailure(failure) => failure.is_io() || failure.is_timeout(),
+        SdkError::ServiceError(service_error) => {
+            service_error.raw().status().is_server_error()
+                || service_error.err().code().is_some_and(|code| TRANSIENT_ERROR_CODES.contains(&code))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a failed Bedrock or Transcribe call was throttled or hit a transient error
+///
+/// # Arguments
+///
+/// * `err` - The error of the call
+///
+/// # Returns
+///
+/// True for throttling (`ThrottlingException`, `TooManyRequestsException`,
+/// `LimitExceededException`, ...), 5xx responses, timeouts and connection errors
+pub fn is_throttled<E: ProvideErrorMetadata>(err: &SdkError<E>) -> bool {
+    match err {
+        SdkError::TimeoutError(_) | SdkError::ResponseError(_) => true,
+        SdkError::DispatchFailure(failure) => failure.is_io() || failure.is_timeout(),
+        SdkError::ServiceError(service_error) => {
+            service_error.raw().status().is_server_error()
+                || service_error.err().code().is_some_and(|code| THROTTLING_ERROR_CODES.contains(&code))
+        }
+        _ => false,
+    }
+}
+
+/// The wait before a retry of a throttled call
+///
+/// # Arguments
+///
+/// * `retry` - Number of the retry, starting at 1
+/// * `jitter` - Random value between 0 and 1
+///
+/// # Returns
+///
+/// `RETRY_BASE_DELAY` doubled for every earlier retry, at most `RETRY_MAX_DELAY`, of
+/// which the jitter keeps between half and all, so concurrent callers don't retry in step
+pub fn backoff_delay(retry: u32, jitter: f64) -> Duration {
+    let ceiling = RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY);
+    ceiling.mul_f64(0.5 + 0.5 * jitter.clamp(0.0, 1.0))
+}
+
+/// Calls an AWS API, retrying with exponential backoff while it is throttled
+///
+/// # Arguments
+///
+/// * `operation` - Name of the call, used in log messages (e.g. "InvokeModel")
+/// * `max_retries` - Most retries after the first attempt (`model.max_retries`)
+/// * `on_retry` - Called with the number of the next attempt before waiting for it
+/// * `call` - Sends the request
+///
+/// # Returns
+///
+/// The response, or the error of the last attempt
+///
+/// Only errors `is_throttled()` accepts are retried; any other error is returned at
+/// once. The wait between attempts comes from `backoff_delay()`.
+pub async fn retry_throttled<T, E, F, Fut>(
+    operation: &str,
+    max_retries: u32,
+    mut on_retry: impl FnMut(u32),
+    mut call: F,
+) -> Result<T, SdkError<E>>
+where
+    E: ProvideErrorMetadata + std::error::Error + 'static,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E>>>,
+{
+    let mut retry = 0;
+    loop {
+        match call().await {
+            Err(err) if retry < max_retries && is_throttled(&err) => {
+                retry += 1;
+                let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0;
+                let delay = backoff_delay(retry, jitter);
+                log::warn!(
+                    "{} throttled or failed transiently; retrying in {:.1}s (attempt {} of {}): {}",
+                    operation,
+                    delay.as_secs_f64(),
+                    retry + 1,
+                    max_retries + 1,
+                    DisplayErrorContext(&err)
+                );
+                on_retry(retry + 1);
+                sleep(delay).await;
+            }
+            Err(err) => {
+                if retry > 0 && is_throttled(&err) {
+                    log::warn!("{} still failing after {} retries", operation, retry);
+                }
+                return Err(err);
+            }
+            Ok(output) => return Ok(output),
+        }
+    }
+}
+
 /// Determines the AWS region for a specific S3 bucket
 ///
 /// # Arguments
@@ -110,4 +413,326 @@ pub async fn bucket_region(client: &Client, bucket_name: &str) -> Result<Region>
     } else {
         Ok(Region::new(location_constraint.as_str().to_owned()))
     }
-}
\ No newline at end of file
+}
+
+/// Waits until a freshly uploaded S3 object is readable
+///
+/// # Arguments
+///
+/// * `client` - AWS S3 client instance
+/// * `bucket_name` - Name of the S3 bucket holding the object
+/// * `key` - Key of the uploaded object
+///
+/// # Returns
+///
+/// A Result indicating the object is readable or an error after the last attempt
+///
+/// Polls `HeadObject` a few times with a short, growing delay, so Transcribe isn't
+/// started before the object is visible (which fails the job with "does not exist").
+pub async fn wait_for_object(client: &Client, bucket_name: &str, key: &str) -> Result<()> {
+    let mut delay = Duration::from_millis(500);
+
+    for attempt in 1..=OBJECT_VISIBLE_ATTEMPTS {
+        match client.head_object().bucket(bucket_name).key(key).send().await {
+            Ok(_) => return Ok(()),
+            Err(err) if attempt < OBJECT_VISIBLE_ATTEMPTS => {
+                log::debug!("s3://{}/{} not readable yet (attempt {}): {}", bucket_name, key, attempt, err);
+                sleep(delay).await;
+                delay *= 2;
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Uploaded object s3://{}/{} is not readable", bucket_name, key)
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a presigned GET URL for an S3 object
+///
+/// # Arguments
+///
+/// * `client` - AWS S3 client for the bucket's region
+/// * `bucket_name` - Name of the S3 bucket holding the object
+/// * `key` - Key of the object
+/// * `expires_in` - How long the link stays valid (at most 7 days)
+///
+/// # Returns
+///
+/// A Result containing the URL or an error if the object is missing, can't be read
+/// with the current credentials, or the expiry isn't valid
+///
+/// Presigning itself happens locally and succeeds even without permission to read the
+/// object, so `HeadObject` (which needs the same `s3:GetObject` permission) is called
+/// first to avoid handing out a link that only returns "Access Denied".
+pub async fn presigned_object_url(
+    client: &Client,
+    bucket_name: &str,
+    key: &str,
+    expires_in: Duration,
+) -> Result<String> {
+    client
+        .head_object()
+        .bucket(bucket_name)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| format!("Cannot read s3://{}/{}", bucket_name, key))?;
+
+    let presigning_config = PresigningConfig::expires_in(expires_in).context("Invalid link expiry")?;
+    let request = client
+        .get_object()
+        .bucket(bucket_name)
+        .key(key)
+        .presigned(presigning_config)
+        .await
+        .with_context(|| format!("Could not presign s3://{}/{}", bucket_name, key))?;
+
+    Ok(request.uri().to_string())
+}
+
+/// An existing S3 object given as input
+#[derive(Debug, Clone)]
+pub struct S3Object {
+    /// Region of the bucket
+    pub region: Region,
+    /// Size of the object in bytes
+    pub size: i64,
+}
+
+/// Splits an `s3://bucket/key` URI into bucket and key
+///
+/// # Arguments
+///
+/// * `uri` - The input path or URI
+///
+/// # Returns
+///
+/// The bucket and key, or None if `uri` isn't an S3 URI with both parts
+pub fn parse_s3_uri(uri: &str) -> Option<(String, String)> {
+    let (bucket, key) = uri.strip_prefix(S3_SCHEME)?.split_once('/')?;
+    if bucket.is_empty() || key.is_empty() {
+        return None;
+    }
+    Some((bucket.to_string(), key.to_string()))
+}
+
+/// Looks up the size and bucket region of an existing S3 object
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration (any region)
+/// * `bucket_name` - Name of the S3 bucket holding the object
+/// * `key` - Key of the object
+///
+/// # Returns
+///
+/// A Result containing the object's region and size, or an error if it is missing or
+/// can't be read with the current credentials
+///
+/// Needs only `s3:GetObject` on the object, not `s3:GetBucketLocation`, so objects in
+/// buckets owned by someone else work too. A single `HeadObject` answers both questions
+/// when the bucket is in the configured region; otherwise S3 names the bucket's region
+/// in its redirect and the request is repeated there once.
+pub async fn head_s3_object(config: &SdkConfig, bucket_name: &str, key: &str) -> Result<S3Object> {
+    let client = Client::new(config);
+    let redirected_region = match client.head_object().bucket(bucket_name).key(key).send().await {
+        Ok(head) => {
+            let region = config.region().cloned().unwrap_or_else(|| Region::new("us-east-1"));
+            return Ok(S3Object {
+                region,
+                size: head.content_length().unwrap_or_default(),
+            });
+        }
+        Err(err) => match err
+            .raw_response()
+            .and_then(|response| response.headers().get(BUCKET_REGION_HEADER))
+            .filter(|region| Some(*region) != config.region().map(Region::as_ref))
+        {
+            Some(region) => Region::new(region.to_string()),
+            None => return Err(err).with_context(|| format!("Cannot read s3://{}/{}", bucket_name, key)),
+        },
+    };
+
+    let regional_config = with_region(config, redirected_region.clone());
+    let head = Client::new(&regional_config)
+        .head_object()
+        .bucket(bucket_name)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| format!("Cannot read s3://{}/{}", bucket_name, key))?;
+
+    Ok(S3Object {
+        region: redirected_region,
+        size: head.content_length().unwrap_or_default(),
+    })
+}
+
+/// Computes the S3 object key an input file is uploaded under
+///
+/// # Arguments
+///
+/// * `file_name` - File name of the input audio file (e.g. "meeting.wav")
+/// * `prefix` - Optional folder the object is uploaded under (e.g. "distill/2024-06-01")
+/// * `strip_extension` - Leave the file extension out of the key
+/// * `suffix` - Optional text appended to the file stem (e.g. "-2024")
+///
+/// # Returns
+///
+/// The object key, e.g. "meeting.wav", "meeting", "meeting-2024.wav" or
+/// "distill/2024-06-01/meeting.wav"
+///
+/// The upload, the Transcribe media URI and the delete step all use this key, so they
+/// always reference the same object. Leading and trailing slashes of the prefix are
+/// ignored, so "distill/" and "/distill" give the same key; an empty prefix uploads to
+/// the bucket root.
+pub fn s3_object_key(file_name: &str, prefix: Option<&str>, strip_extension: bool, suffix: Option<&str>) -> String {
+    let path = std::path::Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file_name.to_string());
+    let suffix = suffix.unwrap_or_default();
+
+    let key = match path.extension() {
+        Some(extension) if !strip_extension => {
+            format!("{}{}.{}", stem, suffix, extension.to_string_lossy())
+        }
+        _ => format!("{}{}", stem, suffix),
+    };
+
+    match prefix.map(|prefix| prefix.trim_matches('/')).filter(|prefix| !prefix.is_empty()) {
+        Some(prefix) => format!("{}/{}", prefix, key),
+        None => key,
+    }
+}
+
+/// Checks that a KMS key reference has a form S3 accepts for SSE-KMS
+///
+/// # Arguments
+///
+/// * `kms_key_id` - The `aws.kms_key_id` setting
+///
+/// # Returns
+///
+/// A Result indicating the reference looks valid, or an error naming the accepted forms
+///
+/// Accepts a key id ("1234abcd-12ab-34cd-56ef-1234567890ab"), a multi-region key id
+/// ("mrk-..."), an alias ("alias/distill") or the ARN of a key or alias. Whether the key
+/// exists and may be used is only known when S3 tries to use it.
+pub fn check_kms_key_id(kms_key_id: &str) -> Result<()> {
+    const KEY_ID: &str = r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}|mrk-[0-9a-fA-F]{32}";
+    const ALIAS: &str = r"alias/[a-zA-Z0-9/_-]+";
+    let pattern = format!(
+        r"^(?:{key}|{alias}|arn:aws[a-z-]*:kms:[a-z0-9-]+:\d{{12}}:(?:key/(?:{key})|{alias}))$",
+        key = KEY_ID,
+        alias = ALIAS
+    );
+
+    if regex::Regex::new(&pattern)?.is_match(kms_key_id.trim()) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "❌ aws.kms_key_id '{}' is not a KMS key id, alias (alias/...) or key ARN (arn:aws:kms:<region>:<account>:key/<id>)",
+            kms_key_id
+        ))
+    }
+}
+
+/// Splits a Secrets Manager reference into the secret id and the optional JSON key
+///
+/// # Arguments
+///
+/// * `reference` - The part of a `secretsmanager://` endpoint after the scheme
+///
+/// # Returns
+///
+/// The secret name or ARN, and the JSON key after it, if any
+///
+/// Secret names can't contain colons, so the first colon starts the JSON key. A secret
+/// ARN (`arn:<partition>:secretsmanager:<region>:<account>:secret:<name>`) keeps its own
+/// colons: only a colon after the ARN's seventh field starts the JSON key.
+pub fn parse_secret_reference(reference: &str) -> (&str, Option<&str>) {
+    let split = if reference.starts_with("arn:") {
+        reference.match_indices(':').nth(6).map(|(index, _)| index)
+    } else {
+        reference.find(':')
+    };
+    match split {
+        Some(index) => (&reference[..index], Some(&reference[index + 1..])),
+        None => (reference, None),
+    }
+}
+
+/// Resolves a webhook endpoint that may reference AWS Secrets Manager
+///
+/// # Arguments
+///
+/// * `endpoint` - Either a plain URL or a `secretsmanager://secret-name-or-arn[:json-key]` reference
+///
+/// # Returns
+///
+/// A Result containing the endpoint URL to post to or an error
+///
+/// Plain URLs are returned unchanged. For Secrets Manager references, the secret string
+/// is fetched once per run and cached; when a `json-key` is given, the secret is parsed
+/// as a JSON object and the value under that key is used.
+pub async fn resolve_endpoint(endpoint: &str) -> Result<String> {
+    let Some(reference) = endpoint.strip_prefix(SECRETS_MANAGER_SCHEME) else {
+        return Ok(endpoint.to_string());
+    };
+
+    let (secret_name, json_key) = parse_secret_reference(reference);
+    if secret_name.is_empty() {
+        return Err(anyhow!("Secrets Manager reference is missing a secret name"));
+    }
+
+    let cached = SECRET_CACHE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|cache| cache.get(secret_name).cloned());
+
+    let secret = match cached {
+        Some(secret) => secret,
+        None => {
+            let profile = PROFILE_NAME.lock().unwrap().clone();
+            let config = load_config(None, profile.as_deref()).await;
+            let client = aws_sdk_secretsmanager::Client::new(&config);
+            let resp = client
+                .get_secret_value()
+                .secret_id(secret_name)
+                .send()
+                .await
+                .with_context(|| format!("Failed to read secret '{}' from Secrets Manager", secret_name))?;
+            let secret = resp
+                .secret_string()
+                .with_context(|| format!("Secret '{}' has no string value", secret_name))?
+                .to_string();
+
+            SECRET_CACHE
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(secret_name.to_string(), secret.clone());
+            secret
+        }
+    };
+
+    match json_key {
+        None => Ok(secret.trim().to_string()),
+        Some(key) => {
+            let value: serde_json::Value = serde_json::from_str(&secret)
+                .with_context(|| format!("Secret '{}' is not a JSON object", secret_name))?;
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+                .with_context(|| format!("Secret '{}' has no string key '{}'", secret_name, key))
+        }
+    }
+}
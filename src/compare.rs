@@ -0,0 +1,130 @@
+//! # Compare Module
+//!
+//! This module compares a new summary with an earlier one (`--compare-previous <PATH>`),
+//! to spot when a recurring meeting changed significantly or the model's output drifts:
+//! - A similarity score from 0 to 100%, based on the words the summaries share in order
+//! - A line diff listing the lines that were removed and added
+//!
+//! Blank lines and differences in spacing are ignored, so reformatted summaries don't
+//! show up as changes.
+
+/// A line that differs between the previous and the new summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// Only in the previous summary
+    Removed(String),
+    /// Only in the new summary
+    Added(String),
+}
+
+/// Result of `compare()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Share of words the summaries have in common, in order, from 0.0 to 1.0
+    pub similarity: f64,
+    /// Removed and added lines, in document order
+    pub changes: Vec<DiffLine>,
+}
+
+/// Compares a new summary with a previous one
+///
+/// # Arguments
+///
+/// * `previous` - The earlier summary
+/// * `current` - The new summary
+///
+/// # Returns
+///
+/// The similarity and the changed lines
+///
+/// The similarity is `2 * common / (previous + current)` over the words of both
+/// summaries, where `common` is the length of their longest common subsequence. Two
+/// empty summaries are identical.
+pub fn compare(previous: &str, current: &str) -> Comparison {
+    let previous_words: Vec<&str> = previous.split_whitespace().collect();
+    let current_words: Vec<&str> = current.split_whitespace().collect();
+    let total = previous_words.len() + current_words.len();
+    let similarity = if total == 0 {
+        1.0
+    } else {
+        2.0 * common_length(&previous_words, &current_words) as f64 / total as f64
+    };
+
+    Comparison {
+        similarity,
+        changes: diff_lines(&lines(previous), &lines(current)),
+    }
+}
+
+/// Non-blank lines of a summary, with runs of whitespace collapsed to one space
+fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Length of the longest common subsequence, keeping only one row of the table
+fn common_length<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut row = vec![0usize; b.len() + 1];
+    for item in a {
+        let mut diagonal = 0;
+        for (j, other) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if item == other { diagonal + 1 } else { above.max(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Lines removed from `a` and added in `b`, from the longest common subsequence
+fn diff_lines(a: &[String], b: &[String]) -> Vec<DiffLine> {
+    // table[i][j] is the common length of a[i..] and b[j..]
+    let mut table = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && table[i + 1][j] >= table[i][j + 1]) {
+            changes.push(DiffLine::Removed(a[i].clone()));
+            i += 1;
+        } else {
+            changes.push(DiffLine::Added(b[j].clone()));
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// Removes what Distill adds around a summary in Markdown output
+///
+/// # Arguments
+///
+/// * `contents` - Contents of a saved summary file
+///
+/// # Returns
+///
+/// The contents without a leading YAML frontmatter block and `# Summary` heading, so a
+/// `.md` output can be compared with a plain summary
+pub fn strip_output_header(contents: &str) -> &str {
+    let mut body = contents.trim_start();
+    if let Some(rest) = body.strip_prefix("---\n") {
+        if let Some(end) = rest.find("\n---\n") {
+            body = rest[end + 5..].trim_start();
+        }
+    }
+    body.strip_prefix("# Summary\n").unwrap_or(body)
+}
@@ -0,0 +1,210 @@
+//! # Estimate Module
+//!
+//! This module estimates what a run will cost before anything is sent to AWS
+//! (`--estimate-only`):
+//! - Reads the audio duration from the file itself (MP3, MP4/M4A, WAV, FLAC, Ogg)
+//! - Prices the transcription per minute of audio
+//! - Estimates the Bedrock input tokens from the duration and the prompt, and prices
+//!   them together with the configured `model.max_tokens` as an upper bound for the output
+//!
+//! The same duration stops a run early when the audio is longer than `--max-duration-mins`.
+//!
+//! ## Configuration
+//! Prices are read from the `[pricing]` section of config.toml. The defaults are the
+//! us-east-1 list prices for Amazon Transcribe (standard batch) and Claude 3 Sonnet.
+//!
+//! The token count is a rough estimate: conversation runs at about 150 words per minute,
+//! and a word is about 1.3 tokens.
+
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::output;
+use crate::settings::Settings;
+
+/// Estimated transcript tokens per minute of conversation (~150 words at ~1.3 tokens each)
+const TOKENS_PER_MINUTE: f64 = 200.0;
+
+/// Transcribe bills at least this many seconds per job
+const TRANSCRIBE_MINIMUM_SECS: f64 = 15.0;
+
+/// Output token budget assumed when `model.max_tokens` is not set
+const DEFAULT_OUTPUT_TOKENS: i64 = 1000;
+
+/// Cost breakdown of a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostEstimate {
+    /// Length of the audio
+    pub duration: Duration,
+    /// Transcription cost in USD
+    pub transcribe_cost: f64,
+    /// Estimated prompt tokens (transcript plus prompt template)
+    pub input_tokens: u64,
+    /// Output tokens assumed for the summary (an upper bound)
+    pub output_tokens: u64,
+    /// Bedrock cost for the input tokens in USD
+    pub input_cost: f64,
+    /// Bedrock cost for the output tokens in USD
+    pub output_cost: f64,
+}
+
+impl CostEstimate {
+    /// Total estimated cost in USD
+    pub fn total(&self) -> f64 {
+        self.transcribe_cost + self.input_cost + self.output_cost
+    }
+}
+
+/// Reads the duration of an audio file
+///
+/// # Arguments
+///
+/// * `path` - Path to the audio file
+///
+/// # Returns
+///
+/// A Result containing the duration or an error if the format isn't supported
+///
+/// Uses the duration from the container headers when present. Otherwise (e.g. MP3 files
+/// without a Xing header) the packets are read, without decoding, and their durations summed.
+pub fn audio_duration(path: &Path) -> Result<Duration> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .with_context(|| format!("Could not read the audio format of {}", path.display()))?;
+    let mut format = probed.format;
+
+    let track = format
+        .default_track()
+        .ok_or_else(|| anyhow!("{} has no audio track", path.display()))?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let time_base = params
+        .time_base
+        .or_else(|| params.sample_rate.map(|rate| symphonia::core::units::TimeBase::new(1, rate)))
+        .ok_or_else(|| anyhow!("Could not determine the duration of {}", path.display()))?;
+
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+            while let Ok(packet) = format.next_packet() {
+                if packet.track_id() == track_id {
+                    frames += packet.dur();
+                }
+            }
+            frames
+        }
+    };
+
+    let time = time_base.calc_time(frames);
+    Ok(Duration::from_secs_f64(time.seconds as f64 + time.frac))
+}
+
+/// Estimates the cost of transcribing and summarizing audio of a given length
+///
+/// # Arguments
+///
+/// * `duration` - Length of the audio
+/// * `settings` - Application settings containing the prices, prompt and model settings
+///
+/// # Returns
+///
+/// The cost breakdown
+pub fn estimate_cost(duration: Duration, settings: &Settings) -> CostEstimate {
+    let pricing = &settings.pricing;
+    let minutes = duration.as_secs_f64().max(TRANSCRIBE_MINIMUM_SECS) / 60.0;
+
+    let prompt_tokens = (settings.prompt.template.len() + settings.anthropic.system.len()) as f64 / 4.0;
+    let input_tokens = (duration.as_secs_f64() / 60.0 * TOKENS_PER_MINUTE + prompt_tokens).ceil() as u64;
+    let output_tokens = settings.model.max_tokens.unwrap_or(DEFAULT_OUTPUT_TOKENS).max(0) as u64;
+
+    CostEstimate {
+        duration,
+        transcribe_cost: minutes * pricing.transcribe_per_minute,
+        input_tokens,
+        output_tokens,
+        input_cost: input_tokens as f64 / 1000.0 * pricing.bedrock_input_per_1k_tokens,
+        output_cost: output_tokens as f64 / 1000.0 * pricing.bedrock_output_per_1k_tokens,
+    }
+}
+
+/// Prints a cost breakdown
+///
+/// # Arguments
+///
+/// * `file_name` - Name of the audio file the estimate is for
+/// * `estimate` - The estimate to print
+pub fn print_estimate(file_name: &str, estimate: &CostEstimate) {
+    let secs = estimate.duration.as_secs();
+    println!("💰 Cost estimate for {}", file_name);
+    println!("   Audio duration:       {:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    println!("   Transcribe:           ${:.4}", estimate.transcribe_cost);
+    println!(
+        "   Bedrock input:        ${:.4} (~{} tokens)",
+        estimate.input_cost, estimate.input_tokens
+    );
+    println!(
+        "   Bedrock output:       ${:.4} (up to {} tokens)",
+        estimate.output_cost, estimate.output_tokens
+    );
+    println!("   Total:                ${:.4}", estimate.total());
+    println!("   Prices come from the [pricing] section of config.toml and may not match your region or model.");
+}
+
+/// Stops the run if the audio is longer than `--max-duration-mins`
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the audio file
+/// * `max_mins` - Longest allowed duration in minutes
+/// * `force` - Only warn when the audio is too long
+///
+/// # Returns
+///
+/// A Result indicating the audio may be processed or an error
+///
+/// Files whose duration can't be read get a warning and are processed.
+pub fn check_max_duration(file_path: &Path, max_mins: u64, force: bool) -> Result<()> {
+    let duration = match audio_duration(file_path) {
+        Ok(duration) => duration,
+        Err(err) => {
+            output::report(format!(
+                "⚠️ Could not read the duration of {} ({:#}); skipping the --max-duration-mins check.",
+                file_path.display(),
+                err
+            ));
+            return Ok(());
+        }
+    };
+
+    if duration <= Duration::from_secs(max_mins * 60) {
+        return Ok(());
+    }
+    let secs = duration.as_secs();
+    let length = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if force {
+        output::report(format!("⚠️ {} is {} long, over --max-duration-mins {}; continuing because of --force.", file_path.display(), length, max_mins));
+        return Ok(());
+    }
+    bail!(
+        "✋ {} is {} long, over the --max-duration-mins limit of {} minutes. Nothing was uploaded; use --force to process it anyway.",
+        file_path.display(),
+        length,
+        max_mins
+    )
+}
@@ -0,0 +1,147 @@
+//! # Index Module
+//!
+//! This module keeps a local, searchable archive of past runs in a SQLite database:
+//! - `--index <DB_PATH>` stores one row per run (time, source file, language, model,
+//!   transcript and summary)
+//! - `distill-cli search <QUERY> --index <DB_PATH>` finds runs whose summary or
+//!   transcript contains the query
+//!
+//! The database and its table are created on first use. SQLite is bundled, so nothing
+//! needs to be installed.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::output::RunMetadata;
+
+/// Characters of the summary shown per search result
+const SNIPPET_CHARS: usize = 160;
+
+/// A run found by `search()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    /// Row id of the run
+    pub id: i64,
+    /// When the run started (RFC 3339)
+    pub created: String,
+    /// Input audio file of the run
+    pub source_file: String,
+    /// Whether the query matched the summary (otherwise only the transcript matched)
+    pub in_summary: bool,
+    /// Start of the summary, for display
+    pub snippet: String,
+}
+
+/// Opens (and if needed creates) an index database
+///
+/// # Arguments
+///
+/// * `path` - Path to the SQLite database file
+///
+/// # Returns
+///
+/// A Result containing the connection or an error
+pub fn open(path: &Path) -> Result<Connection> {
+    let connection = Connection::open(path)
+        .with_context(|| format!("❌ Could not open index {}", path.display()))?;
+    connection
+        .execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY,
+                created TEXT NOT NULL,
+                source_file TEXT NOT NULL,
+                language_code TEXT NOT NULL,
+                model_id TEXT NOT NULL,
+                transcript TEXT NOT NULL,
+                summary TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS runs_created ON runs (created);",
+        )
+        .with_context(|| format!("❌ {} is not a Distill index", path.display()))?;
+    Ok(connection)
+}
+
+/// Stores a run in the index
+///
+/// # Arguments
+///
+/// * `connection` - Connection returned by `open()`
+/// * `metadata` - Metadata of the run
+/// * `transcript` - The transcript
+/// * `summary` - The summary
+///
+/// # Returns
+///
+/// A Result containing the row id of the run or an error
+pub fn insert_run(connection: &Connection, metadata: &RunMetadata, transcript: &str, summary: &str) -> Result<i64> {
+    connection
+        .execute(
+            "INSERT INTO runs (created, source_file, language_code, model_id, transcript, summary)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                metadata.created,
+                metadata.source_file,
+                metadata.language_code,
+                metadata.model_id,
+                transcript,
+                summary
+            ],
+        )
+        .context("❌ Could not store the run in the index")?;
+    Ok(connection.last_insert_rowid())
+}
+
+/// Searches the stored summaries and transcripts
+///
+/// # Arguments
+///
+/// * `connection` - Connection returned by `open()`
+/// * `query` - Text to look for (case-insensitive for ASCII letters)
+/// * `limit` - Maximum number of results
+///
+/// # Returns
+///
+/// A Result containing the matching runs, newest first, or an error
+///
+/// Matches substrings with `LIKE`; `%` and `_` in the query are taken literally.
+pub fn search(connection: &Connection, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+    let pattern = format!(
+        "%{}%",
+        query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    );
+
+    let mut statement = connection.prepare(
+        "SELECT id, created, source_file, summary LIKE ?1 ESCAPE '\\', summary
+         FROM runs
+         WHERE summary LIKE ?1 ESCAPE '\\' OR transcript LIKE ?1 ESCAPE '\\'
+         ORDER BY created DESC, id DESC
+         LIMIT ?2",
+    )?;
+    let hits = statement
+        .query_map(params![pattern, limit as i64], |row| {
+            let summary: String = row.get(4)?;
+            Ok(SearchHit {
+                id: row.get(0)?,
+                created: row.get(1)?,
+                source_file: row.get(2)?,
+                in_summary: row.get(3)?,
+                snippet: snippet(&summary),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("❌ Could not search the index")?;
+
+    Ok(hits)
+}
+
+/// Shortens a summary to one line for search results
+fn snippet(summary: &str) -> String {
+    let line = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > SNIPPET_CHARS {
+        format!("{}…", line.chars().take(SNIPPET_CHARS).collect::<String>())
+    } else {
+        line
+    }
+}
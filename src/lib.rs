@@ -0,0 +1,37 @@
+//! # Distill
+//!
+//! Library interface of Distill CLI: transcribe and summarize audio files using
+//! Amazon Transcribe and Amazon Bedrock from your own Rust programs.
+//!
+//! The `pipeline` module runs the whole flow for one file and returns a structured
+//! result, while the other modules expose the individual steps.
+//!
+//! ## Modules
+//! The library is organized into the following modules:
+//! - `aws_utils`: Handles AWS configuration, S3 bucket operations, and region detection
+//! - `compare`: Compares a summary with a previous one (similarity and line diff)
+//! - `transcribe`: Manages the audio transcription process using Amazon Transcribe
+//! - `summarize`: Handles text summarization using Amazon Bedrock
+//! - `output`: Provides functions for different output formats and notifications
+//! - `settings`: Describes config.toml as typed settings and checks it strictly
+//! - `sentiment`: Classifies the overall sentiment of a transcript
+//! - `estimate`: Estimates the Transcribe and Bedrock cost of a run
+//! - `index`: Stores runs in a local SQLite database and searches them
+//! - `pipeline`: Runs upload, transcription and summarization for a single file and delivers the result
+//! - `redact`: Replaces sensitive terms in the transcript before it reaches Bedrock
+//! - `segment`: Splits long recordings for parallel transcription and stitches the results
+
+pub mod aws_utils;
+pub mod compare;
+pub mod estimate;
+pub mod index;
+pub mod output;
+pub mod pipeline;
+pub mod redact;
+pub mod segment;
+pub mod sentiment;
+pub mod settings;
+pub mod summarize;
+pub mod transcribe;
+
+pub use pipeline::{run, PipelineOptions, PipelineResult};
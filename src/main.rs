@@ -2,49 +2,70 @@
 //!
 //! A tool for transcribing and summarizing audio files using AWS services.
 //!
-//! This is the main entry point for the Distill CLI application. The application flow is:
+//! This is the main entry point for the Distill CLI application, a thin command-line
+//! wrapper around the `distill_cli` library. The application flow is:
 //! 1. Parse command-line arguments
 //! 2. Load AWS configuration and application settings
 //! 3. Select or validate the S3 bucket for file storage
-//! 4. Upload the audio file to S3 with server-side encryption (AES-256)
-//! 5. Transcribe the audio using Amazon Transcribe
-//! 6. Summarize the transcription using Amazon Bedrock
-//! 7. Process the output based on the selected output type
-//! 8. Optionally delete the S3 object
-//! 9. Optionally save the full transcript
+//! 4. Run the pipeline (upload, transcribe, summarize, optionally delete the S3 object)
+//! 5. Process the output based on the selected output type
+//! 6. Optionally save the full transcript
 //!
-//! ## Modules
-//! The application is organized into the following modules:
-//! - `aws_utils`: Handles AWS configuration, S3 bucket operations, and region detection
-//! - `transcribe`: Manages the audio transcription process using Amazon Transcribe
-//! - `summarize`: Handles text summarization using Amazon Bedrock
-//! - `output`: Provides functions for different output formats and notifications
-
-mod aws_utils;
-mod output;
-mod summarize;
-mod transcribe;
+//! With several inputs (repeated `-i`, a directory, a glob pattern or `-` for paths on
+//! stdin), steps 3 to 6 run for each file (`process_one`), and a report of the successes
+//! and failures is printed at the end. The output type, Teams title and webhooks are
+//! chosen once, before the first file.
+//!
+//! The modules (`aws_utils`, `transcribe`, `summarize`, `output`, `pipeline`) live in
+//! the library crate, see `lib.rs`.
 
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
-use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::Client;
-use clap::Parser;
-use config::{Config, File as ConfigFile};
-use dialoguer::{theme::ColorfulTheme, Input, MultiSelect, Select};
-use spinoff::{Spinner, spinners, Color};
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use distill_cli::pipeline::{
+    self, ConfirmSend, DeliveryOptions, LowConfidenceError, LowLanguageConfidenceError, PipelineOptions,
+    ProgressNotification,
+};
+use distill_cli::redact::Redactor;
+use distill_cli::settings::{self, Settings};
+use distill_cli::aws_utils::BucketChoice;
+use distill_cli::{aws_utils, estimate, index, output, summarize, transcribe};
+use spinoff::{Spinner, spinners};
+
+/// Application settings file, relative to the current directory
+const DEFAULT_CONFIG_FILE: &str = "./config.toml";
+
+/// Exit code used when transcription succeeded but summarization failed
+///
+/// Lets scripts distinguish "transcribed but not summarized" from a complete failure (exit code 1).
+const EXIT_SUMMARY_FAILED: i32 = 3;
 
-#[derive(Debug, Parser)]
+/// Exit code used when the transcription confidence is below `--min-confidence-fail`, or
+/// the identified language's confidence is below `--min-language-confidence`
+///
+/// The run stops before Bedrock is called, so no summary is produced.
+const EXIT_LOW_CONFIDENCE: i32 = 4;
+
+#[derive(Debug, Clone, Parser)]
 #[clap(
     about = "Distill CLI can summarize an audio file (e.g., a meeting) using Amazon Transcribe and Amazon Bedrock.\n\nNotes:\n- S3 objects are deleted by default!\n- Use --save-transcript to keep the full transcript.",
-    after_help = "For supported languages, consult: https://docs.aws.amazon.com/transcribe/latest/dg/supported-languages.html"
+    after_help = "For supported languages, run `distill-cli list-languages` or consult: https://docs.aws.amazon.com/transcribe/latest/dg/supported-languages.html",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Opt {
-    #[clap(short, long)]
-    input_audio_file: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(short, long, action = ArgAction::Append, required_unless_present_any = ["from_transcribe_json", "from_summary"], help = "Audio file to process, the s3://bucket/key URI of one already in S3, a directory or quoted glob pattern of audio files, or - to read paths from stdin (one per line); repeat to process several inputs one after the other")]
+    input_audio_file: Vec<String>,
 
     #[clap(
         short,
@@ -58,14 +79,323 @@ struct Opt {
     #[clap(short, long, default_value = "summarized_output")]
     summary_file_name: String,
 
-    #[clap(short, long, default_value = "en-US")]
+    #[clap(short, long, default_value = "en-US", help = "Language code of the audio, or \"auto\" to let Transcribe identify the language")]
     language_code: String,
 
-    #[clap(short, long, default_value = "Y")]
+    #[clap(short, long, default_value = "Y", help = "Delete the audio from S3 after the run (Y/N); defaults to N for s3:// inputs")]
     delete_s3_object: String,
+
+    #[clap(long, help = "Add a presigned link to the recording in S3 to the summary (requires --delete-s3-object N)")]
+    recording_link: bool,
     
     #[clap(short = 't', long, help = "Save the full transcript to a .trans file")]
     save_transcript: bool,
+
+    #[clap(long, help = "Save the start, end and confidence of every word to a .words.json file")]
+    save_word_timings: bool,
+
+    #[clap(long, value_enum, value_name = "FORMAT", default_value = "raw", help = "Line breaks in the saved transcript and the text summarized: one line per speaker turn (raw), paragraphs at long pauses, or one line per sentence")]
+    transcript_format: TranscriptFormat,
+
+    #[clap(long, help = "Collapse repeated words/phrases and strip configured filler words before summarizing")]
+    dedupe_transcript: bool,
+
+    #[clap(long, help = "Fix obvious transcription errors (misheard words, run-ons) with an extra Bedrock call before summarizing")]
+    clean_transcript: bool,
+
+    #[clap(long, requires = "save_transcript", help = "Save the cleaned transcript instead of the raw one")]
+    save_cleaned: bool,
+
+    #[clap(long, value_enum, value_name = "MODE", default_value = "transcript", help = "What to deliver when summarization fails: the transcript in the selected output, the transcript in the terminal, or nothing")]
+    fallback_on_bedrock_error: BedrockFallback,
+
+    #[clap(long, value_name = "CMD", help = "Pipe the summary to this shell command's stdin, with the run metadata in DISTILL_* environment variables")]
+    output_command: Option<String>,
+
+    #[clap(long, requires = "output_command", help = "Don't fail the run when --output-command exits with a non-zero status")]
+    ignore_output_command_errors: bool,
+
+    #[clap(long, value_name = "PATH", help = "Write a JSON (or .csv) manifest of every file produced by the run")]
+    manifest: Option<PathBuf>,
+
+    #[clap(long, help = "Show full webhook URLs in logs and error messages instead of redacting their secret tokens")]
+    show_secrets: bool,
+
+    #[clap(long, value_name = "PATH", help = "PEM bundle of additional root certificates trusted for webhook requests")]
+    ca_bundle: Option<PathBuf>,
+
+    #[clap(long, value_name = "N", help = "Seconds a Slack or Teams webhook request may take before it fails, 0 to wait indefinitely (overrides notify.timeout_secs, default 30)")]
+    webhook_timeout_secs: Option<u64>,
+
+    #[clap(long, value_name = "TEXT", help = "Title of the Slack message (header) and Teams card, instead of prompting for the Teams title")]
+    notify_title: Option<String>,
+
+    #[clap(long, help = "Disable TLS certificate verification for webhook requests (testing only!)")]
+    danger_accept_invalid_certs: bool,
+
+    #[clap(long, help = "Ask the model to rate the summary's completeness from 1 to 5")]
+    self_rate: bool,
+
+    #[clap(long, help = "Save run metadata (source, language, model, rating) to a .meta.json file")]
+    save_metadata: bool,
+
+    #[clap(long, value_name = "PATH", action = ArgAction::Append, help = "Text file of reference material (e.g. a glossary or earlier decisions) included in the prompt so the model can resolve acronyms and context; repeat for several files")]
+    reference: Vec<PathBuf>,
+
+    #[clap(long, help = "With several input files, include condensed summaries of the earlier files in the prompt for each next one, so a series of related recordings is summarized consistently")]
+    carry_context: bool,
+
+    #[clap(long, value_name = "PATH", help = "Compare the new summary with a previous summary file and print a similarity score and the changed lines")]
+    compare_previous: Option<PathBuf>,
+
+    #[clap(long, help = "Prepend YAML frontmatter (title, date, source, language, model) to Markdown output")]
+    frontmatter: bool,
+
+    #[clap(long, help = "Start Markdown and Word output with a table of contents of the summary's ## headings")]
+    toc: bool,
+
+    #[clap(long, help = "Transcribe each audio channel separately (for multi-track recordings) instead of using speaker diarization")]
+    by_channel: bool,
+
+    #[clap(long, value_name = "N", conflicts_with = "by_channel", value_parser = clap::value_parser!(i32).range(2..=30), help = "Most speakers speaker diarization tells apart (2-30, overrides transcribe.max_speakers; default 10)")]
+    speakers: Option<i32>,
+
+    #[clap(long, value_name = "N", conflicts_with = "from_transcribe_json", help = "Experimental: split the audio into N segments with ffmpeg and transcribe them in parallel")]
+    parallel_transcribe: Option<usize>,
+
+    #[clap(long, value_name = "PATH", help = "Summarize a saved Amazon Transcribe result JSON file instead of uploading and transcribing audio")]
+    from_transcribe_json: Option<PathBuf>,
+
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "STAGES", help = "Run only these consecutive stages: upload, transcribe, summarize, output (comma-separated, default: all)")]
+    stages: Vec<Stage>,
+
+    #[clap(long, value_name = "PATH", conflicts_with = "from_transcribe_json", help = "Deliver a saved summary (with --stages output)")]
+    from_summary: Option<PathBuf>,
+
+    #[clap(long, value_name = "LIST", value_delimiter = ',', help = "Structure the summary into exactly these sections, in order (e.g. \"Overview,Risks,Next Steps\"); overrides prompt.sections")]
+    sections: Vec<String>,
+
+    #[clap(long, value_name = "NAME", help = "Summarize in the voice of a persona from the [personas] table in config.toml")]
+    persona: Option<String>,
+
+    #[clap(long, value_name = "N", help = "Limit each bulleted list (e.g. action items) in the summary to N items")]
+    max_items: Option<usize>,
+
+    #[clap(long, value_name = "N", help = "Rewrite the summary (up to 2 times) until it has at least N words")]
+    min_words: Option<usize>,
+
+    #[clap(long, value_name = "N", help = "Rewrite the summary (up to 2 times) until it has at most N words")]
+    max_words: Option<usize>,
+
+    #[clap(long, help = "Detect topic chapters with timestamps and a short summary each, and add them to the output")]
+    chapters: bool,
+
+    #[clap(long, help = "Add a short summary of each speaker's contributions to the output (one extra Bedrock call per speaker)")]
+    per_speaker_summary: bool,
+
+    #[clap(long, value_name = "FLOAT", help = "Abort without summarizing when the average word confidence from Transcribe is below this value (0.0-1.0)")]
+    min_confidence_fail: Option<f64>,
+
+    #[clap(long, value_name = "FLOAT", help = "With --language-code auto, stop before summarizing (or ask, when run interactively) if the language identification confidence is below this value (0.0-1.0)")]
+    min_language_confidence: Option<f64>,
+
+    #[clap(long, conflicts_with = "from_transcribe_json", help = "With --language-code auto, transcribe again in transcribe.fallback_language (once) if the summary fails or is nearly empty")]
+    retry_with_fallback_language: bool,
+
+    #[clap(long, value_name = "PATH", help = "CSV (filename,language_code) or JSON file mapping input files to language codes; unmapped files use --language-code")]
+    language_map: Option<PathBuf>,
+
+    #[clap(long, value_name = "PATH.zip", help = "Collect all files produced during the run into a single zip archive")]
+    bundle: Option<PathBuf>,
+
+    #[clap(long, help = "Reuse the saved summary progress for this transcript, and save progress so an interrupted run can resume")]
+    resume_summary: bool,
+
+    #[clap(long, conflicts_with = "resume_summary", help = "Discard any saved summary progress for this transcript and summarize from scratch")]
+    no_resume: bool,
+
+    #[clap(long, value_name = "LIST", value_delimiter = ',', help = "Comma-separated audio file extensions picked up from a directory input (overrides input.extensions; default: the formats Transcribe supports)")]
+    extensions: Option<Vec<String>>,
+
+    #[clap(long, value_name = "SUFFIX", help = "Text appended to the file stem in the S3 object key (e.g. -2024 turns meeting.wav into meeting-2024.wav)")]
+    s3_key_suffix: Option<String>,
+
+    #[clap(long, value_name = "PREFIX", help = "Folder in the bucket the audio is uploaded under, e.g. distill/2024-06-01 (overrides aws.s3_key_prefix)")]
+    s3_prefix: Option<String>,
+
+    #[clap(long, help = "Classify the overall sentiment of the transcript and add it to the output and metadata")]
+    sentiment: bool,
+
+    #[clap(long, conflicts_with = "from_transcribe_json", help = "Print an estimate of the Transcribe and Bedrock cost for the audio file and exit without calling AWS")]
+    estimate_only: bool,
+
+    #[clap(long, value_name = "N", help = "Stop before uploading if the audio is longer than N minutes (unless --force)")]
+    max_duration_mins: Option<u64>,
+
+    #[clap(long, requires = "max_duration_mins", help = "Process the audio even if it is longer than --max-duration-mins")]
+    force: bool,
+
+    #[clap(long, value_name = "PATH", default_value = DEFAULT_CONFIG_FILE, help = "Path of the config file")]
+    config: PathBuf,
+
+    #[clap(long, value_name = "TS", help = "Post the Slack summary as a reply in the thread with this timestamp (needs slack.bot_token)")]
+    thread_ts: Option<String>,
+
+    #[clap(long, value_name = "NAME", help = "Overlay config.<NAME>.toml (e.g. dev, staging, prod) on top of config.toml")]
+    env: Option<String>,
+
+    #[clap(long, value_name = "NAME", help = "AWS profile to use from ~/.aws/config and ~/.aws/credentials (overrides AWS_PROFILE)")]
+    profile: Option<String>,
+
+    #[clap(long, help = "Don't print the startup banner (welcome, file, language, environment, profile, output type, model and output file name)")]
+    no_banner: bool,
+
+    #[clap(long, value_name = "NAME", help = "Apply the [profiles.<NAME>] settings (prompt, model, output type, ...) from config.toml")]
+    profile_name: Option<String>,
+
+    #[clap(long, value_name = "PATH", help = "Write every Bedrock request (model id, messages, system prompt, parameters) as JSON to this file right before it is sent; includes the full transcript")]
+    dump_request: Option<PathBuf>,
+
+    #[clap(long, help = "Send a tiny Bedrock request every few minutes while waiting for the transcription, so the summarization call starts without warm-up delay (costs a few tokens)")]
+    keep_warm: bool,
+
+    #[clap(long, help = "Write the transcript verbatim to stdout and send the summary, spinner and status messages to stderr")]
+    transcript_to_stdout: bool,
+
+    #[clap(long, help = "Fail on unknown or misspelled config.toml keys and values of the wrong type")]
+    strict_config: bool,
+
+    #[clap(long, requires = "save_transcript", help = "Apply the [redaction] terms to the .trans file as well (it is saved unredacted by default)")]
+    redact_transcript_file: bool,
+
+    #[clap(long, value_name = "DB_PATH", help = "Store the run (source, language, model, transcript, summary) in this SQLite database, searchable with the search subcommand")]
+    index: Option<PathBuf>,
+
+    #[clap(long, help = "Show the summary and ask for confirmation (and the Teams card title) before sending it to Slack or Teams")]
+    preview: bool,
+
+    #[clap(long, help = "Send without the --preview confirmation; required with --preview when there is no terminal")]
+    yes: bool,
+
+    #[clap(long, help = "Re-read every written file and fail the run if one is empty or invalid (JSON must parse, Word files must open, text must be UTF-8)")]
+    verify_output: bool,
+
+    #[clap(long, help = "Never prompt (for cron jobs and scripts): require aws.s3_bucket_name, send to all configured webhooks, use the default Teams card title and skip --preview")]
+    non_interactive: bool,
+
+    #[clap(long, help = "Don't save a Slack or Teams summary that reached no destination to notify.fallback_file")]
+    no_fallback_file: bool,
+
+    #[clap(long, conflicts_with = "estimate_only", help = "Resolve the bucket, print the S3 object and model id and check the selected webhooks, then exit before uploading")]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+enum Command {
+    /// Print the language codes supported by Amazon Transcribe
+    ListLanguages,
+    /// Search the summaries and transcripts stored with --index
+    Search {
+        /// Text to look for in summaries and transcripts
+        query: String,
+
+        #[clap(long, value_name = "DB_PATH", help = "SQLite database written by --index")]
+        index: PathBuf,
+
+        #[clap(long, default_value_t = 20, help = "Maximum number of results")]
+        limit: usize,
+    },
+    /// Delete finished Amazon Transcribe jobs left behind by earlier runs
+    CleanupJobs {
+        #[clap(long, default_value = transcribe::JOB_NAME_PREFIX, help = "Only delete jobs whose name starts with this prefix")]
+        prefix: String,
+
+        #[clap(long, value_name = "DURATION", value_parser = parse_age, help = "Only delete jobs created at least this long ago (e.g. 90m, 12h, 7d, 2w)")]
+        older_than: Duration,
+
+        #[clap(long, help = "List the jobs that would be deleted without deleting them")]
+        dry_run: bool,
+
+        #[clap(short, long, help = "Delete without asking for confirmation")]
+        yes: bool,
+    },
+}
+
+/// Parses an age such as "90m", "12h", "7d" or "2w"
+fn parse_age(value: &str) -> std::result::Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("expected a number followed by s, m, h, d or w, not \"{}\"", value))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(format!("unknown unit \"{}\"; use s, m, h, d or w", unit)),
+    };
+    amount
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("\"{}\" is too long", value))
+}
+
+/// What to deliver when the audio was transcribed but summarization failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum BedrockFallback {
+    /// Deliver the labeled transcript to the selected output
+    Transcript,
+    /// Print the labeled transcript to the terminal, whatever the output type
+    Terminal,
+    /// Deliver nothing
+    None,
+}
+
+impl From<BedrockFallback> for pipeline::BedrockFallback {
+    fn from(fallback: BedrockFallback) -> Self {
+        match fallback {
+            BedrockFallback::Transcript => Self::Transcript,
+            BedrockFallback::Terminal => Self::Terminal,
+            BedrockFallback::None => Self::None,
+        }
+    }
+}
+
+/// How the transcript is broken into lines, selectable with `--transcript-format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum TranscriptFormat {
+    /// One line per speaker turn, as Transcribe returns it
+    Raw,
+    /// A new paragraph at every speaker turn and long pause
+    Paragraphs,
+    /// One line per sentence
+    Sentences,
+}
+
+impl From<TranscriptFormat> for transcribe::TranscriptFormat {
+    fn from(format: TranscriptFormat) -> Self {
+        match format {
+            TranscriptFormat::Raw => Self::Raw,
+            TranscriptFormat::Paragraphs => Self::Paragraphs,
+            TranscriptFormat::Sentences => Self::Sentences,
+        }
+    }
+}
+
+/// A stage of a run, selectable with `--stages`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+enum Stage {
+    /// Upload the audio file to S3
+    Upload,
+    /// Transcribe the uploaded audio
+    Transcribe,
+    /// Summarize the transcription
+    Summarize,
+    /// Deliver the summary to the selected output
+    Output,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -78,30 +408,274 @@ enum OutputType {
     SlackSplit,
     Teams,
     TeamsSplit,
+    Vtt,
+    Srt,
+    Ssml,
 }
 
+impl From<OutputType> for output::OutputType {
+    fn from(output_type: OutputType) -> Self {
+        match output_type {
+            OutputType::Terminal => Self::Terminal,
+            OutputType::Text => Self::Text,
+            OutputType::Word => Self::Word,
+            OutputType::Markdown => Self::Markdown,
+            OutputType::Slack => Self::Slack,
+            OutputType::SlackSplit => Self::SlackSplit,
+            OutputType::Teams => Self::Teams,
+            OutputType::TeamsSplit => Self::TeamsSplit,
+            OutputType::Vtt => Self::Vtt,
+            OutputType::Srt => Self::Srt,
+            OutputType::Ssml => Self::Ssml,
+        }
+    }
+}
+
+impl From<&Opt> for PipelineOptions {
+    /// Takes the pipeline flags from the command line
+    ///
+    /// What depends on the file or on config.toml (bucket, stages, persona, sections,
+    /// references, ...) is left at the `PipelineOptions::new()` defaults for
+    /// `process_one()` to fill in.
+    fn from(opt: &Opt) -> Self {
+        Self {
+            language_code: opt.language_code.clone(),
+            delete_s3_object: opt.delete_s3_object == "Y",
+            recording_link: opt.recording_link,
+            s3_key_suffix: opt.s3_key_suffix.clone(),
+            s3_key_prefix: opt.s3_prefix.clone(),
+            transcript_format: opt.transcript_format.into(),
+            dedupe_transcript: opt.dedupe_transcript,
+            clean_transcript: opt.clean_transcript,
+            keep_warm: opt.keep_warm,
+            by_channel: opt.by_channel,
+            parallel_transcribe: opt.parallel_transcribe,
+            transcribe_json: opt.from_transcribe_json.clone(),
+            max_items: opt.max_items,
+            min_words: opt.min_words,
+            max_words: opt.max_words,
+            chapters: opt.chapters,
+            per_speaker_summary: opt.per_speaker_summary,
+            self_rate: opt.self_rate,
+            sentiment: opt.sentiment,
+            min_confidence_fail: opt.min_confidence_fail,
+            resume_summary: opt.resume_summary,
+            discard_summary_state: opt.no_resume,
+            min_language_confidence: opt.min_language_confidence,
+            confirm_language: (console::user_attended() && !opt.non_interactive)
+                .then_some(confirm_language as fn(&_) -> bool),
+            max_speakers: opt.speakers,
+            ..PipelineOptions::new(String::new(), String::new())
+        }
+    }
+}
+
+impl From<&Opt> for DeliveryOptions {
+    /// Takes the delivery flags from the command line
+    ///
+    /// The output type, Teams title and webhooks chosen up front, and what depends on
+    /// the file or on config.toml, are left for `process_one()` to fill in.
+    fn from(opt: &Opt) -> Self {
+        Self {
+            summary_file_name: opt.summary_file_name.clone(),
+            bedrock_fallback: opt.fallback_on_bedrock_error.into(),
+            toc: opt.toc,
+            frontmatter: opt.frontmatter,
+            confirm_send: opt.preview.then_some(confirm_send as ConfirmSend),
+            thread_ts: opt.thread_ts.clone(),
+            save_transcript: opt.save_transcript,
+            save_cleaned: opt.save_cleaned,
+            redact_transcript_file: opt.redact_transcript_file,
+            save_word_timings: opt.save_word_timings,
+            save_metadata: opt.save_metadata,
+            index: opt.index.clone(),
+            manifest: opt.manifest.clone(),
+            bundle: opt.bundle.clone(),
+            output_command: opt.output_command.clone(),
+            ignore_output_command_errors: opt.ignore_output_command_errors,
+            ..Default::default()
+        }
+    }
+}
+
+/// Checks the `--stages` selection against the inputs given
+///
+/// # Arguments
+///
+/// * `stages` - The stages from the command line (empty for all)
+/// * `from_transcribe_json` - Whether a saved Transcribe result was given
+/// * `from_summary` - Whether a saved summary was given
+/// * `parallel_transcribe` - Whether `--parallel-transcribe` was given
+///
+/// # Returns
+///
+/// A Result containing the pipeline stages and whether the output stage runs, or an
+/// error explaining what is missing
+///
+/// The stages must be consecutive, and the first one needs its input: the audio file
+/// for `upload` and `transcribe` (already uploaded for the latter), `--from-transcribe-json`
+/// for `summarize` and `--from-summary` for `output`.
+fn resolve_stages(
+    stages: &[Stage],
+    from_transcribe_json: bool,
+    from_summary: bool,
+    parallel_transcribe: bool,
+) -> Result<(pipeline::Stages, bool)> {
+    let mut stages = stages.to_vec();
+    stages.sort();
+    stages.dedup();
+    if stages.is_empty() {
+        stages = if from_summary {
+            vec![Stage::Output]
+        } else if from_transcribe_json {
+            vec![Stage::Summarize, Stage::Output]
+        } else {
+            Stage::value_variants().to_vec()
+        };
+    }
+
+    let all = Stage::value_variants();
+    let first = all.iter().position(|stage| *stage == stages[0]).unwrap_or(0);
+    if stages.iter().zip(&all[first..]).any(|(stage, expected)| stage != expected) {
+        bail!("--stages must be consecutive (e.g. transcribe,summarize), not {:?}", stages);
+    }
+
+    match stages[0] {
+        Stage::Upload | Stage::Transcribe if from_transcribe_json => {
+            bail!("--from-transcribe-json replaces the upload and transcribe stages; start --stages at summarize")
+        }
+        Stage::Summarize if !from_transcribe_json => {
+            bail!("--stages starting at summarize needs --from-transcribe-json <PATH>")
+        }
+        Stage::Output if !from_summary => bail!("--stages output needs --from-summary <PATH>"),
+        _ => {}
+    }
+    if from_summary && stages != [Stage::Output] {
+        bail!("--from-summary can only be used with --stages output");
+    }
+    if parallel_transcribe && stages[0] == Stage::Transcribe {
+        bail!("--parallel-transcribe uploads its own segments, so it needs the upload stage");
+    }
+
+    let pipeline_stages = pipeline::Stages {
+        upload: stages.contains(&Stage::Upload),
+        transcribe: stages.contains(&Stage::Transcribe),
+        summarize: stages.contains(&Stage::Summarize),
+    };
+    Ok((pipeline_stages, stages.contains(&Stage::Output)))
+}
+
+/// Reads the default output type from config.toml
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing `output.default_type`
+///
+/// # Returns
+///
+/// The configured output type, or None (with a warning) if the value isn't a known type
+fn configured_output_type(settings: &Settings) -> Option<OutputType> {
+    let value = settings.output.default_type.as_deref()?;
+    match OutputType::from_str(value, true) {
+        Ok(output_type) => Some(output_type),
+        Err(_) => {
+            let known: Vec<String> = OutputType::value_variants()
+                .iter()
+                .filter_map(|variant| variant.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect();
+            println!(
+                "⚠️ Unknown output.default_type '{}' in config.toml (expected one of: {}). Using terminal.",
+                value,
+                known.join(", ")
+            );
+            None
+        }
+    }
+}
+
+/// Asks whether to continue with an identified language whose confidence is low
+///
+/// # Arguments
+///
+/// * `language` - The language identified by Transcribe
+///
+/// # Returns
+///
+/// `true` to summarize anyway, `false` to stop
+fn confirm_language(language: &transcribe::IdentifiedLanguage) -> bool {
+    let prompt = format!(
+        "⚠️ Transcribe identified {} with only {:.0}% confidence. Continue anyway?",
+        language.code,
+        language.confidence * 100.0
+    );
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .unwrap_or(false)
+}
+
+/// Title of the Teams card when none is entered
+const DEFAULT_TEAMS_CARD_TITLE: &str = "A meeting from today...";
+
 /// Prompts the user to enter a title for the Teams card
 ///
+/// # Arguments
+///
+/// * `non_interactive` - Use the default title without asking (`--non-interactive`)
+///
 /// # Returns
 ///
 /// The title entered by the user or a default value
 ///
 /// Displays a prompt for the user to enter a title, provides a default value,
 /// and handles input errors by falling back to the default value.
-fn get_teams_card_title() -> String {
+fn get_teams_card_title(non_interactive: bool) -> String {
+    if non_interactive {
+        return DEFAULT_TEAMS_CARD_TITLE.to_string();
+    }
     Input::with_theme(&ColorfulTheme::default())
         .with_prompt("📝 Enter a title for the Teams card:")
-        .default("A meeting from today...".to_string())
+        .default(DEFAULT_TEAMS_CARD_TITLE.to_string())
         .interact_text()
-        .unwrap_or_else(|_| "A meeting from today...".to_string())
+        .unwrap_or_else(|_| DEFAULT_TEAMS_CARD_TITLE.to_string())
+}
+
+/// Shows the summary and asks before it is sent to a webhook (`--preview`)
+///
+/// # Arguments
+///
+/// * `summary` - The summary about to be sent
+/// * `service` - Service name shown in the question ("Slack" or "Teams")
+/// * `title` - Card title that may be edited before sending (Teams)
+///
+/// # Returns
+///
+/// A Result containing whether to send the summary or an error
+fn confirm_send(summary: &str, service: &str, title: Option<&mut String>) -> Result<bool> {
+    println!("\n👀 Preview of the summary for {}:\n", service);
+    println!("{}\n", summary);
+    if let Some(title) = title {
+        *title = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("📝 Card title")
+            .with_initial_text(title.clone())
+            .interact_text()?;
+    }
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Send this summary to {}?", service))
+        .default(false)
+        .interact()?;
+    Ok(confirmed)
 }
 
 /// Gets webhooks from settings and prompts for selection if multiple are defined
 ///
 /// # Arguments
 ///
-/// * `settings` - Application configuration containing the webhooks
+/// * `settings` - Application settings containing the webhooks
 /// * `service` - Service name ("teams" or "slack")
+/// * `non_interactive` - Use all webhooks instead of asking (`--non-interactive`)
 ///
 /// # Returns
 ///
@@ -111,21 +685,16 @@ fn get_teams_card_title() -> String {
 /// If only one webhook is configured (either as a legacy webhook_endpoint or as a single entry
 /// in the webhooks array), it will be used automatically without prompting the user.
 /// If multiple webhooks are configured, a multi-select dialog is shown to let the user
-/// choose which webhooks to use.
-fn select_webhooks(settings: &Config, service: &str) -> Result<Vec<usize>> {
+/// choose which webhooks to use, or all of them are used with `non_interactive`.
+fn select_webhooks(settings: &Settings, service: &str, non_interactive: bool) -> Result<Vec<usize>> {
     // Try to get webhooks array first
-    let webhooks_path = format!("{}.webhooks", service);
-    let endpoint_path = format!("{}.webhook_endpoint", service);
+    let service_settings = settings.webhooks_for(service);
     
-    let webhooks = match settings.get_array(&webhooks_path) {
-        Ok(webhooks) => webhooks,
-        Err(_) => {
+    let webhooks = match service_settings.webhooks {
+        Some(webhooks) => webhooks,
+        None => {
             // Check for legacy single webhook
-            let webhook_endpoint = settings
-                .get_string(&endpoint_path)
-                .unwrap_or_default();
-                
-            if !webhook_endpoint.is_empty() {
+            if !service_settings.webhook_endpoint.is_empty() {
                 // If we have a single legacy webhook, return index 0
                 return Ok(vec![0]);
             }
@@ -137,23 +706,15 @@ fn select_webhooks(settings: &Config, service: &str) -> Result<Vec<usize>> {
         return Ok(vec![]);
     }
     
-    // If there's only one webhook, return it without prompting
-    if webhooks.len() == 1 {
-        return Ok(vec![0]);
+    // If there's only one webhook (or no one to ask), return them without prompting
+    if webhooks.len() == 1 || non_interactive {
+        return Ok((0..webhooks.len()).collect());
     }
     
     // For multiple webhooks, show selection dialog
     let webhook_names: Vec<String> = webhooks
         .iter()
-        .map(|w| {
-            // Convert to table and extract name safely
-            match w.clone().into_table() {
-                Ok(table) => table.get("name")
-                    .and_then(|v| v.clone().into_string().ok())
-                    .unwrap_or_else(|| "Unnamed webhook".to_string()),
-                Err(_) => "Invalid webhook".to_string()
-            }
-        })
+        .map(|w| w.name.clone().unwrap_or_else(|| "Unnamed webhook".to_string()))
         .collect();
     
     let prompt = format!("📝 Select {} channels to send the summary to", service);
@@ -169,26 +730,28 @@ fn select_webhooks(settings: &Config, service: &str) -> Result<Vec<usize>> {
 ///
 /// # Arguments
 ///
-/// * `settings` - Application configuration containing the Teams webhooks
+/// * `settings` - Application settings containing the Teams webhooks
+/// * `non_interactive` - Use all webhooks instead of asking
 ///
 /// # Returns
 ///
 /// A vector of indices of the selected webhooks
-fn select_teams_webhooks(settings: &Config) -> Result<Vec<usize>> {
-    select_webhooks(settings, "teams")
+fn select_teams_webhooks(settings: &Settings, non_interactive: bool) -> Result<Vec<usize>> {
+    select_webhooks(settings, "teams", non_interactive)
 }
 
 /// Gets Slack webhooks from settings and prompts for selection if multiple are defined
 ///
 /// # Arguments
 ///
-/// * `settings` - Application configuration containing the Slack webhooks
+/// * `settings` - Application settings containing the Slack webhooks
+/// * `non_interactive` - Use all webhooks instead of asking
 ///
 /// # Returns
 ///
 /// A vector of indices of the selected webhooks
-fn select_slack_webhooks(settings: &Config) -> Result<Vec<usize>> {
-    select_webhooks(settings, "slack")
+fn select_slack_webhooks(settings: &Settings, non_interactive: bool) -> Result<Vec<usize>> {
+    select_webhooks(settings, "slack", non_interactive)
 }
 
 /// Selects or validates an S3 bucket for file storage
@@ -197,6 +760,7 @@ fn select_slack_webhooks(settings: &Config) -> Result<Vec<usize>> {
 ///
 /// * `s3_client` - AWS S3 client instance
 /// * `s3_bucket_name` - Optional preconfigured bucket name from settings
+/// * `non_interactive` - Fail instead of showing the selection menu (`--non-interactive`)
 ///
 /// # Returns
 ///
@@ -204,364 +768,956 @@ fn select_slack_webhooks(settings: &Config) -> Result<Vec<usize>> {
 ///
 /// Checks if the provided bucket name exists in the user's account.
 /// If the bucket exists, uses it; otherwise shows a selection menu.
-/// Returns an error if no valid bucket is found.
-async fn select_bucket(s3_client: &Client, s3_bucket_name: &str) -> Result<String> {
-    let resp = &aws_utils::list_buckets(s3_client).await;
-    let mut bucket_name = String::new();
+/// Returns an error if no valid bucket is found. When the credentials may not
+/// list buckets, a configured bucket is used unchecked and an unconfigured one is
+/// reported as a permissions problem rather than an empty account.
+async fn select_bucket(s3_client: &Client, s3_bucket_name: &str, non_interactive: bool) -> Result<String> {
+    let resp = aws_utils::list_buckets(s3_client).await;
 
-    if !s3_bucket_name.is_empty() {
-        if resp
-            .as_ref()
-            .ok()
-            .and_then(|buckets| buckets.iter().find(|b| b.as_str() == s3_bucket_name))
-            .is_some()
-        {
-            println!("📦 S3 bucket name: {}", s3_bucket_name);
-            bucket_name = s3_bucket_name.to_string();
-        } else {
-            println!(
-                "Error: The configured S3 bucket '{}' was not found.",
-                s3_bucket_name
-            );
+    let bucket_names = match aws_utils::choose_bucket(s3_bucket_name, &resp, !non_interactive)? {
+        BucketChoice::Use { name, verified: true } => {
+            println!("📦 S3 bucket name: {}", name);
+            return Ok(name);
         }
-    }
-
-    if bucket_name.is_empty() {
-        match resp {
-            Ok(bucket_names) => {
-                if bucket_names.is_empty() {
-                    bail!("\nNo S3 buckets found. Please create an S3 bucket first.");
-                }
-                
-                let selection = Select::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Choose a destination S3 bucket for your audio file")
-                    .default(0)
-                    .items(&bucket_names[..])
-                    .interact()?;
-
-                bucket_name = bucket_names[selection].clone();
-            }
-            Err(err) => {
-                println!("Error getting bucket list: {}", err);
-                bail!("\nError getting bucket list: {}", err);
+        BucketChoice::Use { name, verified: false } => {
+            println!("📦 S3 bucket name: {} (not verified: listing buckets is not permitted)", name);
+            return Ok(name);
+        }
+        BucketChoice::Ask { buckets, not_found } => {
+            if let Some(name) = not_found {
+                println!("Error: The configured S3 bucket '{}' was not found.", name);
             }
-        };
+            buckets
+        }
+    };
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Choose a destination S3 bucket for your audio file")
+        .default(0)
+        .items(&bucket_names[..])
+        .interact()?;
+
+    Ok(bucket_names[selection].clone())
+}
+
+/// Infers the output type from the extension of the summary file name
+///
+/// # Arguments
+///
+/// * `summary_file_name` - The `--summary-file-name` value
+///
+/// # Returns
+///
+/// The output type matching the extension, or None if the extension is not recognized
+///
+/// `.md`, `.docx`, `.txt`, `.vtt`, `.srt` and `.ssml` map to Markdown, Word, Text, Vtt,
+/// Srt and Ssml. `.pdf`, `.html` and `.json`
+/// are recognized but have no matching output type yet, so a warning is printed instead.
+fn output_type_from_extension(summary_file_name: &str) -> Option<OutputType> {
+    let extension = Path::new(summary_file_name)
+        .extension()?
+        .to_string_lossy()
+        .to_lowercase();
+
+    match extension.as_str() {
+        "md" => Some(OutputType::Markdown),
+        "docx" => Some(OutputType::Word),
+        "txt" => Some(OutputType::Text),
+        "vtt" => Some(OutputType::Vtt),
+        "srt" => Some(OutputType::Srt),
+        "ssml" => Some(OutputType::Ssml),
+        "pdf" | "html" | "json" => {
+            println!("⚠️ There is no .{} output type, so the output type was not inferred from the file name.", extension);
+            None
+        }
+        _ => None,
     }
+}
+
+/// Removes the extension the writer for the output type will add anyway
+///
+/// # Arguments
+///
+/// * `summary_file_name` - The `--summary-file-name` value
+/// * `output_type` - The output type that will be written
+///
+/// # Returns
+///
+/// The file name without a matching extension, so "notes.md" doesn't become "notes.md.md"
+fn strip_output_extension(summary_file_name: &str, output_type: OutputType) -> String {
+    let Some(extension) = output::OutputType::from(output_type).extension() else {
+        return summary_file_name.to_string();
+    };
 
-    if bucket_name.is_empty() {
-        bail!("\nNo valid S3 bucket found. Please check your AWS configuration.");
+    match summary_file_name.len().checked_sub(extension.len()) {
+        Some(split) if split > 0 && summary_file_name[split..].eq_ignore_ascii_case(extension) => {
+            summary_file_name[..split].to_string()
+        }
+        _ => summary_file_name.to_string(),
     }
+}
 
-    Ok(bucket_name)
+/// Prints the supported Transcribe language codes and their names
+fn list_languages() {
+    println!("Supported language codes:");
+    for (code, name) in transcribe::SUPPORTED_LANGUAGES {
+        println!("  {:<8} {}", code, name);
+    }
 }
 
-/// Loads application settings from the config.toml file
+/// Prints the runs in an index database matching a query
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the SQLite database written by `--index`
+/// * `query` - Text to look for
+/// * `limit` - Maximum number of results
 ///
 /// # Returns
 ///
-/// A Result containing the loaded configuration or an error
+/// A Result indicating success or an error
+fn search_index(db_path: &Path, query: &str, limit: usize) -> Result<()> {
+    if !db_path.exists() {
+        bail!("❌ Index {} does not exist. Create it with --index.", db_path.display());
+    }
+    let connection = index::open(db_path)?;
+    let hits = index::search(&connection, query, limit)?;
+
+    if hits.is_empty() {
+        println!("🔍 No runs match \"{}\".", query);
+        return Ok(());
+    }
+    println!("🔍 {} run(s) match \"{}\":", hits.len(), query);
+    for hit in hits {
+        let matched = if hit.in_summary { "summary" } else { "transcript" };
+        println!("\n#{} {} {} (matched in {})", hit.id, hit.created, hit.source_file, matched);
+        println!("  {}", hit.snippet);
+    }
+    Ok(())
+}
+
+/// Prints what a run would do and checks the selected webhooks, for `--dry-run`
+///
+/// # Arguments
+///
+/// * `s3_uri` - Where the audio would be uploaded or transcribed from, if anywhere
+/// * `settings` - Application settings loaded from config.toml
+/// * `client` - HTTP client used for webhook requests
+/// * `delivery` - Output type and webhooks chosen up front
+///
+/// # Returns
 ///
-/// Attempts to load the config.toml file from the current directory
-/// and parses it into a Config object.
-fn load_settings() -> Result<Config> {
-    Config::builder()
-        .add_source(ConfigFile::with_name("./config.toml"))
-        .build()
-        .context("Failed to load config.toml. Make sure it exists in the current directory.")
+/// A Result indicating every selected webhook answered, or an error naming those that didn't
+async fn dry_run_report(
+    s3_uri: Option<&str>,
+    settings: &Settings,
+    client: &reqwest::Client,
+    delivery: &Delivery,
+) -> Result<()> {
+    println!("🧪 DRY RUN: nothing is uploaded, transcribed, summarized or sent");
+    match s3_uri {
+        Some(s3_uri) => println!("   S3 object: {}", s3_uri),
+        None => println!("   S3 object: none (nothing is uploaded for a saved Transcribe result or summary)"),
+    }
+    println!("   Model: {}", settings.model.model_id);
+    println!("   Output type: {:?}", delivery.output_type);
+
+    let mut checks = Vec::new();
+    if delivery.slack_bot {
+        checks.push(("Slack API".to_string(), output::check_slack_api(client).await));
+    }
+    for (service, webhook_indices) in [("slack", &delivery.slack_webhook_indices), ("teams", &delivery.teams_webhook_indices)] {
+        if !webhook_indices.is_empty() {
+            checks.extend(output::check_webhooks(settings, client, service, webhook_indices).await);
+        }
+    }
+
+    let mut unreachable = Vec::new();
+    for (name, check) in checks {
+        match check {
+            Ok(status) => println!("   ✅ {} is reachable ({})", name, status),
+            Err(err) => {
+                println!("   ❌ {} is not reachable: {}", name, err);
+                unreachable.push(name);
+            }
+        }
+    }
+    if !unreachable.is_empty() {
+        bail!("❌ Webhooks not reachable: {}", unreachable.join(", "));
+    }
+    Ok(())
 }
 
-/// Main entry point for the Distill CLI application
+/// Deletes finished Transcribe jobs matching a prefix and minimum age
+///
+/// # Arguments
+///
+/// * `prefix` - Job name prefix
+/// * `older_than` - Minimum age of the jobs to delete
+/// * `dry_run` - Only list the jobs
+/// * `yes` - Skip the confirmation prompt
+/// * `profile` - AWS profile from `--profile`, if given
 ///
 /// # Returns
 ///
 /// A Result indicating success or an error
 ///
-/// Parses command-line arguments, loads configurations, processes the audio file,
-/// and handles the output based on user preferences.
-#[::tokio::main]
-async fn main() -> Result<()> {
-    env_logger::init();
-    
-    // Reset the spinner stopped flag at the beginning of the application
-    output::reset_spinner_flag();
-    
-    // Parse command-line arguments first
-    let Opt {
-        input_audio_file,
-        output_type,
-        summary_file_name,
-        language_code,
-        delete_s3_object,
-        save_transcript,
-    } = Opt::parse();
-    
-    // Display input file and output type at the beginning
-    println!("🧙 Welcome to Distill CLI");
-    
-    // Extract just the filename without path
-    let file_path = Path::new(&input_audio_file);
-    let file_name = file_path
-        .file_name()
-        .map(|name| name.to_string_lossy().into_owned())
-        .unwrap_or_else(|| input_audio_file.clone());
-    
-    println!("📄 Processing file: {}", file_name);
-    println!("🔄 Output type: {:?}", output_type);
-    
-    // Load AWS config
-    let config = aws_utils::load_config(None).await;
-    
-    // Load application settings from config.toml
-    let settings = load_settings()?;
+/// Only jobs in the region of the current AWS configuration are considered. Running
+/// and queued jobs are never deleted.
+async fn cleanup_jobs(prefix: &str, older_than: Duration, dry_run: bool, yes: bool, profile: Option<&str>) -> Result<()> {
+    if prefix.is_empty() {
+        bail!("❌ --prefix must not be empty; it keeps jobs started by other tools safe");
+    }
 
-    let s3_bucket_name = settings
-        .get_string("aws.s3_bucket_name")
-        .unwrap_or_default();
+    let config = aws_utils::load_config(None, profile).await;
+    let region = config.region().map(|region| region.to_string()).unwrap_or_default();
+    let jobs = transcribe::finished_jobs(&config, prefix, older_than).await?;
+    if jobs.is_empty() {
+        println!("🧹 No finished transcription jobs starting with \"{}\" are that old in {}.", prefix, region);
+        return Ok(());
+    }
 
-    let model_id = settings
-        .get_string("model.model_id")
-        .unwrap_or_default();
+    println!("🧹 {} finished transcription job(s) in {}:", jobs.len(), region);
+    for job in &jobs {
+        println!("  {}  {:<9} {}", job.created.format("%Y-%m-%d %H:%M"), job.status, job.name);
+    }
+    if dry_run {
+        println!("Dry run: nothing was deleted.");
+        return Ok(());
+    }
+    if !yes
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Delete these {} job(s)?", jobs.len()))
+            .default(false)
+            .interact()?
+    {
+        println!("Nothing was deleted.");
+        return Ok(());
+    }
 
-    let s3_client = Client::new(&config);
+    let mut failed = 0;
+    for job in &jobs {
+        if let Err(err) = transcribe::delete_job(&config, &job.name).await {
+            println!("⚠️ {:#}", err);
+            failed += 1;
+        }
+    }
+    println!("🗑️ Deleted {} transcription job(s).", jobs.len() - failed);
+    if failed > 0 {
+        bail!("❌ {} job(s) could not be deleted", failed);
+    }
+    Ok(())
+}
 
-    println!("📦 Using model: {}", model_id);
+/// Where the summaries of a run go, settled once before the first file is processed
+struct Delivery {
+    /// Output type from `--output-type`, the summary file name or `output.default_type`
+    output_type: OutputType,
+    /// Title of the Teams card (Teams output types only)
+    teams_title: String,
+    /// Whether Slack messages go through `slack.bot_token` instead of the webhooks
+    slack_bot: bool,
+    /// Indices of the selected Slack webhooks
+    slack_webhook_indices: Vec<usize>,
+    /// Indices of the selected Teams webhooks
+    teams_webhook_indices: Vec<usize>,
+}
 
-    // Select or validate S3 bucket
-    let bucket_name = select_bucket(&s3_client, &s3_bucket_name).await?;
+/// Resolves the output type and asks for the Teams title and webhooks, once per run
+///
+/// # Arguments
+///
+/// * `opt` - The command-line options
+/// * `settings` - Application settings loaded from config.toml
+/// * `output_type_given` - Whether `--output-type` was given on the command line
+///
+/// # Returns
+///
+/// A Result containing the delivery settings shared by every file, or an error
+fn select_delivery(opt: &Opt, settings: &Settings, output_type_given: bool) -> Result<Delivery> {
+    // An explicit --output-type always wins over the file name's extension, which wins
+    // over output.default_type
+    let output_type = if output_type_given {
+        opt.output_type
+    } else {
+        let configured = configured_output_type(settings).unwrap_or(opt.output_type);
+        if settings.output.infer_type_from_extension {
+            output_type_from_extension(&opt.summary_file_name).unwrap_or(configured)
+        } else {
+            configured
+        }
+    };
 
-    if output_type != OutputType::Teams && output_type != OutputType::TeamsSplit && 
-       output_type != OutputType::Slack && output_type != OutputType::SlackSplit {
-        println!("📦 Current output file name: {}", summary_file_name);
-    }
+    let (_, deliver_output) = resolve_stages(
+        &opt.stages,
+        opt.from_transcribe_json.is_some(),
+        opt.from_summary.is_some(),
+        opt.parallel_transcribe.is_some(),
+    )?;
+    let delivered_type = if deliver_output { output_type } else { OutputType::Terminal };
+    let teams = delivered_type == OutputType::Teams || delivered_type == OutputType::TeamsSplit;
+    let slack = delivered_type == OutputType::Slack || delivered_type == OutputType::SlackSplit;
 
     // Get Teams card title if needed
-    let user_input = if output_type == OutputType::Teams || output_type == OutputType::TeamsSplit {
-        get_teams_card_title()
+    let teams_title = if teams {
+        opt.notify_title
+            .clone()
+            .or_else(|| settings.notify.title.clone())
+            .unwrap_or_else(|| get_teams_card_title(opt.non_interactive))
     } else {
         String::new()
     };
-    
+
+    // With a bot token, Slack messages go through chat.postMessage (the only way to reply
+    // in a thread) instead of the webhooks
+    let slack_bot = settings.slack.bot_token.as_deref().is_some_and(|token| !token.is_empty());
+    if opt.thread_ts.is_some() && !slack_bot {
+        println!("⚠️ --thread-ts needs slack.bot_token in config.toml; posting a new message through the webhook instead.");
+    }
+
     // Select webhooks early if needed
-    let slack_webhook_indices = if output_type == OutputType::Slack || output_type == OutputType::SlackSplit {
-        select_slack_webhooks(&settings)?
+    let slack_webhook_indices = if slack && !slack_bot {
+        select_slack_webhooks(settings, opt.non_interactive)?
     } else {
         vec![]
     };
     
-    let teams_webhook_indices = if output_type == OutputType::Teams || output_type == OutputType::TeamsSplit {
-        select_teams_webhooks(&settings)?
+    let teams_webhook_indices = if teams {
+        select_teams_webhooks(settings, opt.non_interactive)?
     } else {
         vec![]
     };
     
     // Check if we have webhooks selected when needed
-    if (output_type == OutputType::Slack || output_type == OutputType::SlackSplit) && slack_webhook_indices.is_empty() {
+    if slack && slack_webhook_indices.is_empty() && !slack_bot {
         println!("⚠️ No Slack webhooks selected.");
     }
     
-    if (output_type == OutputType::Teams || output_type == OutputType::TeamsSplit) && teams_webhook_indices.is_empty() {
+    if teams && teams_webhook_indices.is_empty() {
         println!("⚠️ No Teams webhooks selected.");
     }
 
-    let mut spinner = Spinner::new(spinners::Dots, "Uploading file to S3...", Color::White);
+    Ok(Delivery {
+        output_type,
+        teams_title,
+        slack_bot,
+        slack_webhook_indices,
+        teams_webhook_indices,
+    })
+}
+
+/// Processes one audio file: runs the pipeline and delivers its output
+///
+/// # Arguments
+///
+/// * `input_audio_file` - The audio file, `s3://` URI, saved Transcribe result or saved summary
+/// * `opt` - The command-line options, with `summary_file_name` set for this file
+/// * `settings` - Application settings loaded from config.toml
+/// * `delivery` - Output type, Teams title and webhooks chosen up front
+/// * `delete_s3_object_given` - Whether `--delete-s3-object` was given on the command line
+/// * `transcript_stdout` - The real stdout, with `--transcript-to-stdout`
+/// * `carried_context` - Summaries of the earlier files of a batch (`--carry-context`);
+///   the summary of this file is added to it
+///
+/// # Returns
+///
+/// A Result containing the exit code for the file (0, `EXIT_SUMMARY_FAILED` or
+/// `EXIT_LOW_CONFIDENCE`), or an error
+async fn process_one(
+    input_audio_file: &str,
+    opt: Opt,
+    settings: &Settings,
+    delivery: &Delivery,
+    delete_s3_object_given: bool,
+    transcript_stdout: Option<&mut File>,
+    mut carried_context: Option<&mut summarize::CarriedContext>,
+) -> Result<i32> {
+    // Every file gets a spinner of its own
+    output::reset_spinner_flag();
 
-    // Load the bucket region and create a new client to use that region
-    let region = aws_utils::bucket_region(&s3_client, &bucket_name).await?;
-    println!();
+    let (mut stages, deliver_output) = resolve_stages(
+        &opt.stages,
+        opt.from_transcribe_json.is_some(),
+        opt.from_summary.is_some(),
+        opt.parallel_transcribe.is_some(),
+    )?;
+
+    // An s3:// input is transcribed in place and, unless asked, never deleted: the
+    // object may well belong to someone else
+    let s3_input = aws_utils::parse_s3_uri(input_audio_file);
+    let delete_s3_object = if s3_input.is_some() && !delete_s3_object_given {
+        false
+    } else {
+        opt.delete_s3_object == "Y"
+    };
+    if s3_input.is_some() {
+        if opt.parallel_transcribe.is_some() {
+            bail!("--parallel-transcribe needs a local audio file, not an s3:// URI");
+        }
+        if opt.estimate_only {
+            bail!("--estimate-only needs a local audio file, not an s3:// URI");
+        }
+        if stages.upload && !stages.transcribe {
+            bail!("{} is already in S3; there is nothing to upload", input_audio_file);
+        }
+        stages.upload = false;
+        if delete_s3_object {
+            println!("⚠️ {} will be deleted from S3 after the run (--delete-s3-object Y).", input_audio_file);
+        }
+    } else if input_audio_file.starts_with("s3://") {
+        bail!("{} is not a valid S3 URI; use s3://bucket/key", input_audio_file);
+    }
+
+    // Per-file language codes take precedence over --language-code
+    let language_code = match &opt.language_map {
+        Some(path) => {
+            let language_map = transcribe::load_language_map(path)?;
+            transcribe::language_for_file(&language_map, input_audio_file, &opt.language_code).to_string()
+        }
+        None => opt.language_code.clone(),
+    };
+
+    // Display input file and output type at the beginning (unless --no-banner)
+    let banner = !opt.no_banner;
+
+    // Extract just the filename without path
+    let file_path = Path::new(input_audio_file);
+    let file_name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| input_audio_file.to_string());
     
-    let region_message = format!("Using bucket region {}", region);
-    let static_region_message: &'static str = Box::leak(region_message.into_boxed_str());
-    spinner.update(spinners::Dots, static_region_message, Some(Color::White));
-
-    let regional_config = aws_utils::load_config(Some(region)).await;
-    let regional_s3_client = Client::new(&regional_config);
-
-    // Handle conversion of relative paths to absolute paths
-    let file_path = Path::new(&input_audio_file);
-
-    let absolute_path = shellexpand::tilde(file_path.to_str().unwrap()).to_string();
-    let absolute_path = Path::new(&absolute_path);
-
-    if !absolute_path.exists() {
-        bail!("\n❌ The path {} does not exist.", absolute_path.display());
-    }
-
-    let canonicalized_path = absolute_path.canonicalize()?;
-    let body = ByteStream::from_path(&canonicalized_path)
-        .await
-        .with_context(|| format!("❌ Error loading file: {}", canonicalized_path.display()))?;
-
-    let _upload_result = regional_s3_client
-        .put_object()
-        .bucket(&bucket_name)
-        .key(&file_name)
-        .body(body)
-        .server_side_encryption(aws_sdk_s3::types::ServerSideEncryption::Aes256)
-        .send()
-        .await
-        .context("❌ Failed to upload to S3")?;
-
-    let s3_uri = format!("s3://{}/{}", bucket_name, file_name);
-
-    println!();
-    spinner.update(spinners::Dots, "Transcribing audio...", Some(Color::White));
-
-    // Transcribe the audio
-    let transcription: String = transcribe::transcribe_audio(
-        &regional_config,
-        file_path,
-        &s3_uri,
-        &mut spinner,
-        &language_code,
-    )
-    .await?;
+    if banner {
+        println!("📄 Processing file: {}", file_name);
+        println!("🌐 Language code: {}", language_code);
+    }
+    output::set_artifact_source(input_audio_file);
+    
+    if let Some(env) = opt.env.as_ref().filter(|_| banner) {
+        println!("🏷️ Environment: {}", env);
+    }
+    if let Some(profile_name) = opt.profile_name.as_ref().filter(|_| banner) {
+        println!("🎛️ Profile: {}", profile_name);
+    }
 
-    // Summarize the transcription
-    spinner.update(spinners::Dots, "Summarizing text...", Some(Color::White));
-    let summarized_text = summarize::summarize_text(&config, &transcription, &mut spinner).await?;
+    // Catch a mistyped KMS key before anything is uploaded
+    let kms_key_id = settings.aws.kms_key_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+    if let Some(kms_key_id) = kms_key_id {
+        aws_utils::check_kms_key_id(kms_key_id)?;
+    }
 
-    // Process output based on selected output type
-    match output_type {
-        OutputType::Word => {
-            output::write_word_file(&summary_file_name.clone(), &summarized_text, &mut spinner)?;
+    // The fallback language comes from config.toml
+    let fallback_language = if opt.retry_with_fallback_language {
+        let fallback_language = settings
+            .transcribe
+            .fallback_language
+            .clone()
+            .filter(|language| !language.trim().is_empty())
+            .context("❌ --retry-with-fallback-language needs transcribe.fallback_language in config.toml")?;
+        if language_code != transcribe::AUTO_LANGUAGE {
+            println!("⚠️ --retry-with-fallback-language only applies with --language-code auto.");
         }
-        OutputType::Text => {
-            output::write_text_file(&summary_file_name.clone(), &summarized_text, &mut spinner)?;
+        Some(fallback_language)
+    } else {
+        None
+    };
+
+    // Resolve the persona before anything is uploaded, so a typo fails fast
+    let persona = opt
+        .persona
+        .as_deref()
+        .map(|name| settings.persona(name).map(str::to_string))
+        .transpose()?;
+
+    // --sections replaces the sections configured in prompt.sections
+    let sections: Vec<String> = if opt.sections.is_empty() { &settings.prompt.sections } else { &opt.sections }
+        .iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    // Read the previous summary up front, so a wrong path fails before the upload
+    let previous_summary = opt
+        .compare_previous
+        .as_ref()
+        .map(|path| {
+            std::fs::read_to_string(path)
+                .map(|summary| (path.clone(), summary))
+                .with_context(|| format!("❌ Failed to read previous summary {}", path.display()))
+        })
+        .transpose()?;
+
+    // Read the reference documents up front as well
+    let references = if opt.reference.is_empty() {
+        None
+    } else {
+        let documents = opt
+            .reference
+            .iter()
+            .map(|path| {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("❌ Failed to read reference document {}", path.display()))?;
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                Ok((name, contents))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let max_chars = settings.prompt.reference_max_chars.unwrap_or(summarize::REFERENCE_MAX_CHARS);
+        let (instruction, truncated) = summarize::reference_instruction(&documents, max_chars);
+        if !truncated.is_empty() {
+            println!(
+                "⚠️ Reference material is limited to {} characters; cut short or left out: {}",
+                max_chars,
+                truncated.join(", ")
+            );
         }
-        OutputType::Terminal => {
-            if !output::SPINNER_STOPPED.load(std::sync::atomic::Ordering::SeqCst) {
-                spinner.success("Done!");
-                output::SPINNER_STOPPED.store(true, std::sync::atomic::Ordering::SeqCst);
-            }
-            println!();
-            println!("Summary:\n{}\n", summarized_text);
-        }
-        OutputType::Markdown => {
-            output::write_markdown_file(&summary_file_name.clone(), &summarized_text, &mut spinner)?;
-        }
-        OutputType::Slack => {
-            if slack_webhook_indices.is_empty() {
-                println!("⚠️ No Slack webhooks selected. Displaying summary in terminal instead.");
-                println!("Summary:\n{}\n", summarized_text);
-            } else {
-                output::send_slack_notification(
-                    &settings,
-                    &mut spinner,
-                    &summarized_text,
-                    &slack_webhook_indices,
-                )
-                .await?;
-            }
+        Some(instruction)
+    };
+
+    // Compile the redaction terms up front, so a bad pattern fails before the upload
+    let redactor = Redactor::new(&settings.redaction)?;
+    if opt.redact_transcript_file && !redactor.is_active() {
+        println!("⚠️ --redact-transcript-file has no effect: no [redaction] terms or patterns are configured.");
+    }
+
+    // Print a cost estimate and stop before any AWS work
+    if opt.estimate_only {
+        let duration = estimate::audio_duration(file_path)?;
+        estimate::print_estimate(&file_name, &estimate::estimate_cost(duration, settings));
+        return Ok(0);
+    }
+
+    // Check the duration cap before anything is uploaded or transcribed
+    if let Some(max_mins) = opt.max_duration_mins {
+        if s3_input.is_some() {
+            println!("⚠️ Cannot read the duration of {}; skipping the --max-duration-mins check.", input_audio_file);
+        } else if opt.from_transcribe_json.is_none() && opt.from_summary.is_none() {
+            estimate::check_max_duration(file_path, max_mins, opt.force)?;
         }
-        OutputType::SlackSplit => {
-            // First write to a file
-            let ext: &str = ".txt";
-            let outfile = summary_file_name.clone() + ext;
-            let output_file_path_txt = Path::new(&outfile);
-            let mut file = File::create(output_file_path_txt)
-                .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
-
-            file.write_all(summarized_text.as_bytes())
-                .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
-
-            println!("\n💾 Summary written to {}", output_file_path_txt.display());
-            
-            // Update spinner for Slack notification
-            if !slack_webhook_indices.is_empty() {
-                if !output::SPINNER_STOPPED.load(std::sync::atomic::Ordering::SeqCst) {
-                    spinner.update(spinners::Dots, "Sending to Slack...", Some(Color::White));
+    }
+
+    // Load AWS config (--profile beats AWS_PROFILE, which beats the default profile)
+    let config = aws_utils::load_config(None, opt.profile.as_deref()).await;
+
+    let output_type = delivery.output_type;
+    let summary_file_name = strip_output_extension(&opt.summary_file_name, output_type);
+    if banner {
+        println!("🔄 Output type: {:?}", output_type);
+    }
+    if matches!(output_type, OutputType::Vtt | OutputType::Srt) && opt.from_summary.is_some() {
+        bail!("❌ Subtitles are made from the transcription, so they can't be written with --from-summary");
+    }
+
+    let s3_client = Client::new(&config);
+
+    if banner {
+        println!("📦 Using model: {}", settings.model.model_id);
+    }
+
+    // Build the HTTP client used for webhook notifications
+    let ca_bundle = opt.ca_bundle.clone().or_else(|| {
+        settings
+            .notify
+            .ca_bundle
+            .as_ref()
+            .map(|path| PathBuf::from(shellexpand::tilde(&path).to_string()))
+    });
+    if opt.danger_accept_invalid_certs {
+        println!("⚠️ WARNING: TLS certificate verification is DISABLED for webhook requests. Never use this in production!");
+    }
+    let webhook_timeout = match opt.webhook_timeout_secs.unwrap_or(settings.notify.timeout_secs) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    let webhook_client =
+        output::build_webhook_client(ca_bundle.as_deref(), opt.danger_accept_invalid_certs, webhook_timeout)?;
+
+    // Select or validate S3 bucket (nothing is uploaded for a saved Transcribe result or summary)
+    let from_saved = opt.from_transcribe_json.is_some() || opt.from_summary.is_some();
+    let bucket_name = if from_saved {
+        String::new()
+    } else if let Some((bucket, _)) = &s3_input {
+        bucket.clone()
+    } else {
+        select_bucket(&s3_client, &settings.aws.s3_bucket_name, opt.non_interactive).await?
+    };
+
+    // Stop before the upload with a report of what would happen
+    if opt.dry_run {
+        let s3_uri = if from_saved {
+            None
+        } else if s3_input.is_some() {
+            Some(input_audio_file.to_string())
+        } else {
+            let s3_key = aws_utils::s3_object_key(
+                &file_name,
+                opt.s3_prefix.as_deref().or(Some(&settings.aws.s3_key_prefix)),
+                settings.aws.key_strip_extension,
+                opt.s3_key_suffix.as_deref(),
+            );
+            Some(format!("s3://{}/{}", bucket_name, s3_key))
+        };
+        dry_run_report(s3_uri.as_deref(), settings, &webhook_client, delivery).await?;
+        return Ok(0);
+    }
+
+    if banner && output_type != OutputType::Teams && output_type != OutputType::TeamsSplit &&
+       output_type != OutputType::Slack && output_type != OutputType::SlackSplit {
+        println!("📦 Current output file name: {}", summary_file_name);
+    }
+
+    let output_type = if deliver_output { output_type } else { OutputType::Terminal };
+    // A streamed summary is printed as it arrives, unless the output has to be redacted
+    // first or the summary is rewritten afterwards (item limit, word band, sections)
+    let rewritten =
+        opt.max_items.is_some() || opt.min_words.is_some() || opt.max_words.is_some() || !sections.is_empty();
+    summarize::set_stream_to_terminal(
+        settings.model.stream
+            && output_type == OutputType::Terminal
+            && !settings.redaction.apply_to_outputs
+            && !rewritten,
+    );
+
+    output::configure_spinner(settings);
+    let spinner_message = if opt.from_transcribe_json.is_some() {
+        "Reading Transcribe result..."
+    } else if opt.from_summary.is_some() {
+        "Reading summary..."
+    } else if s3_input.is_some() {
+        "Looking up the S3 object..."
+    } else if !stages.upload {
+        "Looking for the uploaded file in S3..."
+    } else {
+        "Uploading file to S3..."
+    };
+    let mut spinner = Spinner::new(
+        output::spinner_frames(spinners::Dots),
+        spinner_message,
+        output::spinner_color(),
+    );
+
+    // The rest runs with the spinner going; a failure clears it, so it doesn't keep
+    // spinning over the next file of a batch
+    let result: Result<i32> = async {
+        // Post a one-time "still working" message to the selected webhooks for long transcriptions
+        let progress_after_secs = settings.notify.progress_after_secs;
+        let progress_webhooks = if !delivery.slack_webhook_indices.is_empty() {
+            Some(("slack", delivery.slack_webhook_indices.clone()))
+        } else if !delivery.teams_webhook_indices.is_empty() {
+            Some(("teams", delivery.teams_webhook_indices.clone()))
+        } else {
+            None
+        };
+        let progress_notification = match progress_webhooks {
+            Some((service, webhook_indices)) if progress_after_secs > 0 => Some(ProgressNotification {
+                after: Duration::from_secs(progress_after_secs),
+                client: webhook_client.clone(),
+                service: service.to_string(),
+                webhook_indices,
+            }),
+            _ => None,
+        };
+
+        // The flags come from the command line; what depends on this file or on
+        // config.toml is filled in here
+        let options = PipelineOptions {
+            input_audio_file: input_audio_file.to_string(),
+            bucket_name,
+            language_code,
+            delete_s3_object,
+            key_strip_extension: settings.aws.key_strip_extension,
+            kms_key_id: kms_key_id.map(str::to_string),
+            s3_key_prefix: opt.s3_prefix.clone().or_else(|| Some(settings.aws.s3_key_prefix.clone())),
+            stages,
+            carried_context: carried_context
+                .as_deref()
+                .filter(|context| !context.is_empty())
+                .map(summarize::CarriedContext::render),
+            references,
+            sections,
+            persona,
+            progress_notification,
+            fallback_language,
+            ..PipelineOptions::from(&opt)
+        };
+
+        // Upload only: report where the audio went and stop
+        if stages.upload && !stages.transcribe {
+            let s3_uri = pipeline::upload(&config, &options, &mut spinner).await?;
+            output::finish_spinner(&mut spinner, &format!("Uploaded to {}", s3_uri));
+            println!("➡️ Continue with: --stages transcribe,summarize,output -i {}", input_audio_file);
+            return Ok(0);
+        }
+
+        let result = match &opt.from_summary {
+            // Output only: deliver a summary saved by an earlier run
+            Some(path) => pipeline::read_summary(path, settings, &options)?,
+            None => match pipeline::run(&config, settings, &options, &mut spinner).await {
+                Ok(result) => result,
+                Err(err) if err.is::<LowConfidenceError>() || err.is::<LowLanguageConfidenceError>() => {
+                    output::persist_spinner(&mut spinner, "❌", &err.to_string());
+                    return Ok(EXIT_LOW_CONFIDENCE);
                 }
-                
-                output::send_slack_notification(
-                    &settings,
-                    &mut spinner,
-                    &summarized_text,
-                    &slack_webhook_indices,
-                )
-                .await?;
-            } else {
-                println!("⚠️ No Slack webhooks selected. Summary was only written to file.");
-            }
+                Err(err) => return Err(err),
+            },
+        };
+
+        // The next file of the batch is summarized with this summary in mind
+        if let Some(context) = carried_context.as_deref_mut().filter(|_| !result.summary_failed) {
+            context.push(&file_name, &result.summary);
         }
-        OutputType::Teams => {
-            if teams_webhook_indices.is_empty() {
-                println!("⚠️ No Teams webhooks selected. Displaying summary in terminal instead.");
-                println!("Summary:\n{}\n", summarized_text);
-            } else {
-                output::send_teams_notification(
-                    &settings,
-                    &mut spinner,
-                    &summarized_text,
-                    &user_input,
-                    "Summary sent to Teams!",
-                    &teams_webhook_indices,
-                )
-                .await?;
-            }
+
+        // The Teams title and webhooks were chosen before the first file
+        let delivery_options = DeliveryOptions {
+            summary_file_name,
+            output_type: output_type.into(),
+            deliver_output,
+            stages,
+            teams_title: delivery.teams_title.clone(),
+            notify_title: opt.notify_title.clone().or_else(|| settings.notify.title.clone()),
+            slack_bot: delivery.slack_bot,
+            slack_webhook_indices: delivery.slack_webhook_indices.clone(),
+            teams_webhook_indices: delivery.teams_webhook_indices.clone(),
+            webhook_client,
+            fallback_file: (!opt.no_fallback_file && !settings.notify.fallback_file.trim().is_empty())
+                .then(|| PathBuf::from(shellexpand::tilde(&settings.notify.fallback_file).to_string())),
+            previous_summary,
+            ..DeliveryOptions::from(&opt)
+        };
+        let summary_failed =
+            pipeline::deliver(settings, &delivery_options, result, &redactor, transcript_stdout, &mut spinner).await?;
+        Ok(if summary_failed { EXIT_SUMMARY_FAILED } else { 0 })
+    }
+    .await;
+    if result.is_err() {
+        output::clear_spinner(&mut spinner);
+    }
+    result
+}
+
+/// Processes the audio files of several inputs (repeated `-i`, a directory, a glob pattern
+/// or `-`) one after the other
+///
+/// # Arguments
+///
+/// * `files` - The audio files to process
+/// * `opt` - The command-line options
+/// * `settings` - Application settings loaded from config.toml
+/// * `delivery` - Output type, Teams title and webhooks chosen up front
+/// * `delete_s3_object_given` - Whether `--delete-s3-object` was given on the command line
+/// * `transcript_stdout` - The real stdout, with `--transcript-to-stdout`
+///
+/// # Returns
+///
+/// A Result indicating success, or an error if any file failed
+///
+/// Each summary goes to the summary file name with the input's stem appended. A file
+/// that fails is reported and the batch moves on; the manifest and bundle cover all
+/// files and are written at the end. With `--carry-context`, the summaries of the
+/// earlier files are carried into the prompt of each next file.
+async fn process_batch(
+    files: &[PathBuf],
+    mut opt: Opt,
+    settings: &Settings,
+    delivery: &Delivery,
+    delete_s3_object_given: bool,
+    mut transcript_stdout: Option<&mut File>,
+) -> Result<()> {
+    let manifest = opt.manifest.take();
+    let bundle = opt.bundle.take();
+    let summary_file_names = pipeline::batch_output_names(&opt.summary_file_name, files);
+    let mut carried_context = opt
+        .carry_context
+        .then(|| summarize::CarriedContext::new(summarize::CARRIED_CONTEXT_CHARS));
+
+    let mut failures = Vec::new();
+    for (index, (file, summary_file_name)) in files.iter().zip(summary_file_names).enumerate() {
+        println!("\n📚 File {} of {}", index + 1, files.len());
+        let file = file.display().to_string();
+        let file_opt = Opt { summary_file_name, ..opt.clone() };
+        let outcome = process_one(
+            &file,
+            file_opt,
+            settings,
+            delivery,
+            delete_s3_object_given,
+            transcript_stdout.as_deref_mut(),
+            carried_context.as_mut(),
+        )
+        .await;
+        let failure = match outcome {
+            Ok(0) => continue,
+            Ok(EXIT_SUMMARY_FAILED) => "transcribed, but summarization failed".to_string(),
+            Ok(EXIT_LOW_CONFIDENCE) => "confidence below the threshold".to_string(),
+            Ok(code) => format!("exit code {}", code),
+            Err(err) => format!("{:#}", err),
+        };
+        println!("❌ {}: {}", file, failure);
+        failures.push((file, failure));
+    }
+
+    if let Some(manifest_path) = manifest {
+        output::write_manifest(&manifest_path)?;
+    }
+    if let Some(bundle_path) = bundle {
+        output::write_bundle(&bundle_path)?;
+    }
+
+    println!("\n📊 Batch finished: {} succeeded, {} failed", files.len() - failures.len(), failures.len());
+    for (file, failure) in &failures {
+        println!("  ❌ {}: {}", file, failure);
+    }
+    if !failures.is_empty() {
+        bail!("❌ {} of {} files failed", failures.len(), files.len());
+    }
+    Ok(())
+}
+
+/// Prints the status messages of a run (see `output::set_reporter()`)
+fn print_status(message: &str) {
+    println!("{}", message);
+}
+
+/// Main entry point for the Distill CLI application
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Parses command-line arguments, loads configurations, processes the audio file
+/// (or each file of a directory or glob input), and handles the output based on user
+/// preferences.
+#[::tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    output::set_reporter(print_status);
+
+    // Parse command-line arguments first, remembering whether --output-type was given
+    let matches = Opt::command().get_matches();
+    let output_type_given = matches.value_source("output_type") == Some(ValueSource::CommandLine);
+    let delete_s3_object_given = matches.value_source("delete_s3_object") == Some(ValueSource::CommandLine);
+    let mut opt = Opt::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    match opt.command.take() {
+        Some(Command::ListLanguages) => {
+            list_languages();
+            return Ok(());
         }
-        OutputType::TeamsSplit => {
-            // First write to a file
-            let ext: &str = ".txt";
-            let outfile = summary_file_name.clone() + ext;
-            let output_file_path_txt = Path::new(&outfile);
-            let mut file = File::create(output_file_path_txt)
-                .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
-
-            file.write_all(summarized_text.as_bytes())
-                .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
-
-            println!("\n💾 Summary written to {}", output_file_path_txt.display());
-            
-            // Update spinner for Teams notification
-            if !teams_webhook_indices.is_empty() {
-                if !output::SPINNER_STOPPED.load(std::sync::atomic::Ordering::SeqCst) {
-                    spinner.update(spinners::Dots, "Sending to Teams...", Some(Color::White));
-                }
-                
-                output::send_teams_notification(
-                    &settings,
-                    &mut spinner,
-                    &summarized_text,
-                    &user_input,
-                    "Summary sent to Teams and written to output file!",
-                    &teams_webhook_indices,
-                )
-                .await?;
-            } else {
-                println!("⚠️ No Teams webhooks selected. Summary was only written to file.");
-            }
+        Some(Command::Search { query, index, limit }) => return search_index(&index, &query, limit),
+        Some(Command::CleanupJobs { prefix, older_than, dry_run, yes }) => {
+            return cleanup_jobs(&prefix, older_than, dry_run, yes, opt.profile.as_deref()).await;
         }
+        None => {}
     }
 
-    // After processing, check if the user wants to delete the S3 object
-    if delete_s3_object == "Y" {
-        s3_client
-            .delete_object()
-            .bucket(&bucket_name)
-            .key(&file_name)
-            .send()
-            .await?;
+    // A saved Transcribe result or summary stands in for the audio file
+    let from_saved = opt
+        .from_transcribe_json
+        .as_ref()
+        .or(opt.from_summary.as_ref())
+        .map(|path| path.display().to_string());
+    if opt.input_audio_file.is_empty() && from_saved.is_none() {
+        bail!("--input-audio-file is required");
     }
-    
-    // Save transcript if requested (as the last operation)
-    if save_transcript {
-        let trans_ext = ".trans";
-        let trans_file = summary_file_name.clone() + trans_ext;
-        let trans_path = Path::new(&trans_file);
-        let mut trans_file = File::create(trans_path)
-            .map_err(|e| anyhow::anyhow!("❌ Error creating transcript file: {}", e))?;
-            
-        trans_file.write_all(transcription.as_bytes())
-            .map_err(|e| anyhow::anyhow!("❌ Error writing transcript file: {}", e))?;
-            
-        println!("📝 Full transcript saved to {}", trans_path.display());
-    }
-
-    if !output::SPINNER_STOPPED.load(std::sync::atomic::Ordering::SeqCst) {
-        spinner.success("Done!");
+
+    output::set_show_secrets(opt.show_secrets);
+    summarize::set_dump_request(opt.dump_request.clone());
+    output::set_verify_output(opt.verify_output);
+
+    // From here on stdout is reserved for the transcript
+    let mut transcript_stdout = if opt.transcript_to_stdout {
+        Some(output::redirect_stdout_to_stderr()?)
     } else {
-        println!("Done!");
+        None
+    };
+
+    if let Some(threshold) = opt.min_confidence_fail {
+        if !(0.0..=1.0).contains(&threshold) {
+            bail!("--min-confidence-fail must be between 0.0 and 1.0");
+        }
+    }
+    if let Some(threshold) = opt.min_language_confidence {
+        if !(0.0..=1.0).contains(&threshold) {
+            bail!("--min-language-confidence must be between 0.0 and 1.0");
+        }
+        if opt.language_code != transcribe::AUTO_LANGUAGE {
+            println!("⚠️ --min-language-confidence only applies with --language-code auto.");
+        }
     }
+    // Previewing needs someone to answer; --yes and --non-interactive send without asking
+    opt.yes |= opt.non_interactive;
+    if opt.preview && !opt.yes && !std::io::stdin().is_terminal() {
+        bail!("--preview needs an interactive terminal; add --yes to send without previewing");
+    }
+    opt.preview = opt.preview && !opt.yes;
 
-    Ok(())
-}
\ No newline at end of file
+    if let (Some(min_words), Some(max_words)) = (opt.min_words, opt.max_words) {
+        if min_words > max_words {
+            bail!("--min-words must not be greater than --max-words");
+        }
+    }
+
+    if !opt.no_banner {
+        println!("🧙 Welcome to Distill CLI");
+    }
+
+    // Load application settings from config.toml (or --config)
+    if !opt.config.is_file() {
+        bail!("❌ Config file not found: {}", opt.config.display());
+    }
+    if opt.strict_config {
+        settings::check_strict(&opt.config)?;
+        if let Some(env) = &opt.env {
+            settings::check_strict(&settings::env_config_path(&opt.config, env)?)?;
+        }
+    }
+    let settings = Settings::load(&opt.config.to_string_lossy(), opt.env.as_deref(), opt.profile_name.as_deref())?;
+    // A mistyped model.provider fails before anything is uploaded
+    if settings.model.api != "converse" {
+        summarize::check_provider(&settings.model.provider)?;
+    }
+
+    // Several -i values, a directory, a glob pattern or - make a batch; anything else is
+    // processed as a single file
+    let extensions = opt.extensions.clone().unwrap_or_else(|| settings.input.extensions.clone());
+    let (inputs, batch) = match from_saved {
+        Some(path) if opt.input_audio_file.is_empty() => (vec![path], false),
+        _ => pipeline::expand_inputs(&opt.input_audio_file, &extensions)?,
+    };
+
+    let delivery = select_delivery(&opt, &settings, output_type_given)?;
+
+    if !batch {
+        if opt.carry_context {
+            println!("⚠️ --carry-context only applies when several files are processed.");
+        }
+        let exit_code = process_one(
+            &inputs[0],
+            opt,
+            &settings,
+            &delivery,
+            delete_s3_object_given,
+            transcript_stdout.as_mut(),
+            None,
+        )
+        .await?;
+        if exit_code != 0 {
+            std::process::exit(exit_code);
+        }
+        return Ok(());
+    }
+
+    let files: Vec<PathBuf> = inputs.iter().map(PathBuf::from).collect();
+    println!("📚 Processing {} files", files.len());
+    process_batch(
+        &files,
+        opt,
+        &settings,
+        &delivery,
+        delete_s3_object_given,
+        transcript_stdout.as_mut(),
+    )
+    .await
+}
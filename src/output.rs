@@ -2,6 +2,8 @@
 //!
 //! This module handles all output operations for the Distill CLI application:
 //! - Writing summaries to different file formats (text, Word, Markdown)
+//! - Writing subtitles (WebVTT, SRT) from the word timings of the transcription
+//! - Writing the summary as SSML for text-to-speech (e.g. Amazon Polly)
 //! - Sending notifications to communication platforms (Slack, Teams)
 //!
 //! Each function in this module takes care of a specific output format or notification
@@ -18,28 +20,72 @@
 //! When multiple webhooks are configured, the user can select which ones to use
 //! through a multi-select interface.
 //!
+//! Webhook URLs usually embed secret tokens, so any log line or error message that
+//! mentions an endpoint goes through `redact_url()`, which masks everything after the
+//! first path segment unless `--show-secrets` was passed.
+//!
+//! An `endpoint` may also be a `secretsmanager://secret-name[:json-key]` reference, which
+//! is resolved through `aws_utils::resolve_endpoint()` right before sending.
+//!
+//! ## Artifact Tracking
+//!
+//! Every file written during a run is recorded with `record_artifact()`, together with
+//! the input file it was produced from. `write_manifest()` turns that record into a JSON
+//! or CSV manifest so downstream tooling can collect the produced files reliably, and
+//! `write_bundle()` packs the files themselves into a single zip archive. With
+//! `--verify-output`, each file is re-read and checked (`verify_output_file()`) as it is
+//! recorded, so a corrupt or empty file fails the run.
+//!
 //! ## Spinner Thread Management
 //!
 //! This module manages a global `SPINNER_STOPPED` flag to ensure that spinner threads
 //! are only stopped once. The flag is reset at the beginning of the application with
 //! `reset_spinner_flag()` and checked before any operation that would stop a spinner.
-//! 
-//! All functions that use spinners should:
-//! 1. Check `SPINNER_STOPPED` before stopping a spinner
-//! 2. Set `SPINNER_STOPPED` to true after stopping a spinner
-//! 3. Use `spinner.update()` instead of `spinner.stop_and_persist()` when possible
+//!
+//! The spinner moves through two states: running, then stopped. Once stopped, any
+//! further `update()` would restart a spinner thread and a second stop would panic, so
+//! all transitions go through guarded helpers:
+//! 1. `update_spinner()` changes the message while the spinner is still running
+//! 2. `finish_spinner()` stops it with a success message
+//! 3. `persist_spinner()` stops it with a custom symbol (warnings, failures)
+//! 4. `clear_spinner()` stops it without a trace, e.g. before asking a question, and
+//!    `restart_spinner()` starts a new one afterwards
+//!
+//! Each stopping helper is a no-op once the spinner has stopped and reports whether it
+//! stopped it, so callers never need to check or set `SPINNER_STOPPED` themselves.
+//!
+//! ## Status Messages
+//!
+//! The library doesn't print: status messages go through `report()` to the reporter
+//! set with `set_reporter()` (the CLI prints them), or to the log at info level.
+//! `report_above_spinner()` clears a running spinner first.
 
-use std::fs::File;
+use std::borrow::Cow;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 
 use anyhow::Result;
-use config::Config;
-use docx_rs::{Docx, Paragraph, Run};
-use reqwest::Client as ReqwestClient;
+use crate::settings::{PostProcessSettings, Settings, TeamsIconSettings};
+use futures_util::stream::{self, StreamExt};
+use docx_rs::{Docx, LineSpacing, LineSpacingType, Paragraph, Run, RunFonts};
+use reqwest::{Certificate, Client as ReqwestClient, StatusCode, Url};
+use serde::Serialize;
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use spinoff::spinners::SpinnerFrames;
 use spinoff::{Spinner, spinners, Color};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::transcribe::WordTiming;
+use crate::{aws_utils, summarize};
 
 // Global flag to track whether the spinner has been stopped
 pub static SPINNER_STOPPED: AtomicBool = AtomicBool::new(false);
@@ -54,148 +100,1692 @@ pub static SPINNER_STOPPED: AtomicBool = AtomicBool::new(false);
 /// 
 /// Call this function once at the start of the main function:
 /// 
-/// ```rust
-/// fn main() {
-///     output::reset_spinner_flag();
-///     // Rest of the application...
-/// }
+/// ```rust,ignore
+/// output::reset_spinner_flag();
+/// // Rest of the application...
 /// ```
 pub fn reset_spinner_flag() {
     SPINNER_STOPPED.store(false, Ordering::SeqCst);
 }
 
-/// Writes summary content to a text file
+// Spinner style and color from output.spinner_style / output.spinner_color
+static SPINNER_STYLE: Mutex<Option<SpinnerFrames>> = Mutex::new(None);
+static SPINNER_COLOR: Mutex<Option<Color>> = Mutex::new(None);
+
+/// Applies the spinner style and color from the settings
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing `output.spinner_style` and `output.spinner_color`
+///
+/// Names are matched case-insensitively, ignoring `_` and `-` (e.g. "bouncing_bar" or
+/// "BouncingBar"); colors may also be given as "#rrggbb". Unknown values print a warning
+/// and fall back to Dots / White.
+pub fn configure_spinner(settings: &Settings) {
+    if let Some(style) = &settings.output.spinner_style {
+        let frames = spinner_style_from_name(style);
+        if frames.is_none() {
+            report(format!("⚠️ Unknown output.spinner_style '{}', using Dots.", style));
+        }
+        if let Ok(mut configured) = SPINNER_STYLE.lock() {
+            *configured = frames;
+        }
+    }
+
+    if let Some(color) = &settings.output.spinner_color {
+        let parsed = spinner_color_from_name(color);
+        if parsed.is_none() {
+            report(format!("⚠️ Unknown output.spinner_color '{}', using White.", color));
+        }
+        if let Ok(mut configured) = SPINNER_COLOR.lock() {
+            *configured = parsed;
+        }
+    }
+}
+
+/// Looks up a spinoff spinner by name
+fn spinner_style_from_name(name: &str) -> Option<SpinnerFrames> {
+    let frames = match normalize_name(name).as_str() {
+        "dots" => spinners::Dots.into(),
+        "dots2" => spinners::Dots2.into(),
+        "dots7" => spinners::Dots7.into(),
+        "dots9" => spinners::Dots9.into(),
+        "line" => spinners::Line.into(),
+        "simpledots" => spinners::SimpleDots.into(),
+        "arc" => spinners::Arc.into(),
+        "circlehalves" => spinners::CircleHalves.into(),
+        "bouncingbar" => spinners::BouncingBar.into(),
+        "arrow3" => spinners::Arrow3.into(),
+        "point" => spinners::Point.into(),
+        "moon" => spinners::Moon.into(),
+        _ => return None,
+    };
+    Some(frames)
+}
+
+/// Looks up a spinoff color by name or "#rrggbb" value
+fn spinner_color_from_name(name: &str) -> Option<Color> {
+    if let Some(hex) = name.trim().strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::TrueColor {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+
+    let color = match normalize_name(name).as_str() {
+        "blue" => Color::Blue,
+        "green" => Color::Green,
+        "red" => Color::Red,
+        "yellow" => Color::Yellow,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "black" => Color::Black,
+        "magenta" => Color::Magenta,
+        _ => return None,
+    };
+    Some(color)
+}
+
+/// Lowercases a name and drops `_`, `-` and spaces, so "Bouncing_Bar" matches "bouncingbar"
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns the configured spinner frames, or `default` when no style is configured
+///
+/// # Arguments
+///
+/// * `default` - Spinner used when `output.spinner_style` is unset or unknown
+pub fn spinner_frames(default: impl Into<SpinnerFrames>) -> SpinnerFrames {
+    SPINNER_STYLE
+        .lock()
+        .ok()
+        .and_then(|style| style.clone())
+        .unwrap_or_else(|| default.into())
+}
+
+/// Returns the configured spinner color, White by default
+pub fn spinner_color() -> Color {
+    SPINNER_COLOR
+        .lock()
+        .ok()
+        .and_then(|color| *color)
+        .unwrap_or(Color::White)
+}
+
+/// Updates the spinner message while the spinner is still running
+///
+/// # Arguments
+///
+/// * `spinner` - Progress spinner to update
+/// * `message` - New message to display
+/// * `color` - Color of the spinner
+///
+/// Does nothing once the spinner has been stopped, since updating a stopped
+/// spinner would start a new spinner thread.
+pub fn update_spinner(spinner: &mut Spinner, message: impl Into<Cow<'static, str>>, color: Color) {
+    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
+        spinner.update(spinner_frames(spinners::Dots), message, Some(color));
+    }
+}
+
+/// Stops the spinner with a success message
+///
+/// # Arguments
+///
+/// * `spinner` - Progress spinner to stop
+/// * `message` - Success message to persist
+///
+/// # Returns
+///
+/// Whether the spinner was still running
+///
+/// Does nothing if the spinner has already been stopped.
+pub fn finish_spinner(spinner: &mut Spinner, message: &str) -> bool {
+    let running = !SPINNER_STOPPED.swap(true, Ordering::SeqCst);
+    if running {
+        spinner.success(message);
+    }
+    running
+}
+
+/// Stops the spinner and persists a custom symbol and message
+///
+/// # Arguments
+///
+/// * `spinner` - Progress spinner to stop
+/// * `symbol` - Symbol shown in place of the spinner (e.g. "⚠️", "❌")
+/// * `message` - Message to persist
+///
+/// # Returns
+///
+/// Whether the spinner was still running
+///
+/// Does nothing if the spinner has already been stopped.
+pub fn persist_spinner(spinner: &mut Spinner, symbol: &str, message: &str) -> bool {
+    let running = !SPINNER_STOPPED.swap(true, Ordering::SeqCst);
+    if running {
+        spinner.stop_and_persist(symbol, message);
+    }
+    running
+}
+
+/// Stops the spinner and removes its line
+///
+/// # Arguments
+///
+/// * `spinner` - Progress spinner to stop
+///
+/// # Returns
+///
+/// Whether the spinner was still running, i.e. whether `restart_spinner()` should
+/// bring it back afterwards
+///
+/// Does nothing if the spinner has already been stopped.
+pub fn clear_spinner(spinner: &mut Spinner) -> bool {
+    let running = !SPINNER_STOPPED.swap(true, Ordering::SeqCst);
+    if running {
+        spinner.clear();
+    }
+    running
+}
+
+/// Replaces a stopped spinner with a new running one
+///
+/// # Arguments
+///
+/// * `spinner` - Progress spinner, already stopped (e.g. by `clear_spinner()`)
+/// * `message` - Message of the new spinner
+///
+/// Uses the configured style and color and marks the spinner as running again.
+pub fn restart_spinner(spinner: &mut Spinner, message: impl Into<Cow<'static, str>>) {
+    *spinner = Spinner::new(spinner_frames(spinners::Dots), message, spinner_color());
+    SPINNER_STOPPED.store(false, Ordering::SeqCst);
+}
+
+/// Receives the status messages of a run (see `set_reporter()`)
+pub type Reporter = fn(&str);
+
+// Where status messages go; the log when no reporter is set
+static REPORTER: Mutex<Option<Reporter>> = Mutex::new(None);
+
+/// Sets where the status messages of a run go
+///
+/// # Arguments
+///
+/// * `reporter` - Called with each message, e.g. to print it
+///
+/// Without a reporter, messages are logged at info level.
+pub fn set_reporter(reporter: Reporter) {
+    *REPORTER.lock().unwrap() = Some(reporter);
+}
+
+/// Reports a status message of the run
+///
+/// # Arguments
+///
+/// * `message` - The message; a leading newline moves it off the spinner's line, an
+///   empty message only ends that line
+///
+/// Goes to the reporter set with `set_reporter()`, or to the log without one.
+pub fn report(message: impl AsRef<str>) {
+    let message = message.as_ref();
+    match *REPORTER.lock().unwrap() {
+        Some(reporter) => reporter(message),
+        None if !message.trim().is_empty() => log::info!("{}", message.trim()),
+        None => {}
+    }
+}
+
+/// Reports a status message without drawing over the spinner
+///
+/// # Arguments
+///
+/// * `spinner` - Progress spinner
+/// * `message` - The message (see `report()`)
+///
+/// A running spinner is cleared first and brought back afterwards with "Continuing...".
+pub fn report_above_spinner(spinner: &mut Spinner, message: &str) {
+    let spinning = clear_spinner(spinner);
+    report(message);
+    if spinning {
+        restart_spinner(spinner, "Continuing...");
+    }
+}
+
+/// Where a summary is delivered (see `pipeline::deliver()`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputType {
+    /// Print the summary
+    #[default]
+    Terminal,
+    /// Write a `.txt` file
+    Text,
+    /// Write a `.docx` file
+    Word,
+    /// Write a `.md` file
+    Markdown,
+    /// Post to Slack, or print when no webhook or bot token is available
+    Slack,
+    /// Write a `.txt` file and post to Slack
+    SlackSplit,
+    /// Post an Adaptive Card to Teams, or print when no webhook is selected
+    Teams,
+    /// Write a `.txt` file and post to Teams
+    TeamsSplit,
+    /// Write WebVTT subtitles of the transcript
+    Vtt,
+    /// Write SRT subtitles of the transcript
+    Srt,
+    /// Write an SSML document of the summary
+    Ssml,
+}
+
+impl OutputType {
+    /// The extension of the file written for this output type, if any
+    ///
+    /// # Returns
+    ///
+    /// The extension with its leading dot, or None for the terminal, Slack and Teams
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            OutputType::Markdown => Some(".md"),
+            OutputType::Word => Some(".docx"),
+            OutputType::Text | OutputType::SlackSplit | OutputType::TeamsSplit => Some(".txt"),
+            OutputType::Vtt => Some(".vtt"),
+            OutputType::Srt => Some(".srt"),
+            OutputType::Ssml => Some(".ssml"),
+            OutputType::Terminal | OutputType::Slack | OutputType::Teams => None,
+        }
+    }
+}
+
+/// Metadata describing a single summarization run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunMetadata {
+    /// Input audio file that was processed
+    pub source_file: String,
+    /// Language code used for transcription (the identified language with `--language-code auto`)
+    pub language_code: String,
+    /// Transcribe's confidence in the automatically identified language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_confidence: Option<f64>,
+    /// Bedrock model used for summarization
+    pub model_id: String,
+    /// When the run started (RFC 3339)
+    pub created: String,
+    /// The model's self-assessed summary quality from 1 to 5 (`--self-rate`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_rating: Option<u8>,
+    /// Overall sentiment of the transcript (`--sentiment`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sentiment: Option<String>,
+    /// Presigned link to the uploaded recording (`--recording-link`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_url: Option<String>,
+    /// The summary split into the requested sections (`--sections`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<summarize::SummarySection>,
+    /// Similarity to the summary given with `--compare-previous`, from 0.0 to 1.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity_to_previous: Option<f64>,
+}
+
+/// Sends everything printed to stdout to stderr instead, keeping a handle to the real stdout
+///
+/// # Returns
+///
+/// A Result containing a file writing to the original stdout or an error
+///
+/// Used by `--transcript-to-stdout`: status messages, the spinner and the summary all
+/// go to stderr, while the returned handle carries only the transcript, so distill-cli
+/// can sit in the middle of a Unix pipeline.
+#[cfg(unix)]
+pub fn redirect_stdout_to_stderr() -> Result<File> {
+    use std::os::fd::AsFd;
+
+    std::io::stdout().flush()?;
+    let original_stdout = std::io::stdout().as_fd().try_clone_to_owned()?;
+
+    // SAFETY: dup2 only replaces the process's stdout descriptor with a copy of stderr
+    if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } == -1 {
+        anyhow::bail!("❌ Error redirecting stdout: {}", std::io::Error::last_os_error());
+    }
+
+    Ok(File::from(original_stdout))
+}
+
+/// Sends everything printed to stdout to stderr instead, keeping a handle to the real stdout
+///
+/// Only supported on Unix-like systems.
+#[cfg(not(unix))]
+pub fn redirect_stdout_to_stderr() -> Result<File> {
+    anyhow::bail!("❌ --transcript-to-stdout is only supported on Unix-like systems")
+}
+
+/// Writes the run metadata to a JSON file alongside the summary
 ///
 /// # Arguments
 ///
 /// * `summary_file_name` - Base name for the output file (without extension)
-/// * `summarized_text` - The text content to write to the file
-/// * `spinner` - Progress spinner to update upon completion
+/// * `metadata` - The metadata collected during the run
 ///
 /// # Returns
 ///
 /// A Result indicating success or an error
 ///
-/// Creates a text file with the provided name and .txt extension,
-/// writes the summarized text to the file, and updates the spinner
-/// to indicate completion.
-pub fn write_text_file(summary_file_name: &str, summarized_text: &str, spinner: &mut Spinner) -> Result<()> {
-    let ext = ".txt";
-    let outfile = summary_file_name.to_owned() + ext;
+/// Creates a `.meta.json` file with the provided name.
+pub fn write_metadata_file(summary_file_name: &str, metadata: &RunMetadata) -> Result<()> {
+    let outfile = summary_file_name.to_owned() + ".meta.json";
     let output_file_path = Path::new(&outfile);
-    let mut file = File::create(output_file_path)
-        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
 
-    file.write_all(summarized_text.as_bytes())
-        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
+    fs::write(output_file_path, serde_json::to_string_pretty(metadata)?)
+        .map_err(|e| anyhow::anyhow!("❌ Error writing metadata file: {}", e))?;
+    record_artifact("metadata", output_file_path)?;
 
-    // Simply update the spinner with success message
-    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-        spinner.success("Done!");
-        SPINNER_STOPPED.store(true, Ordering::SeqCst);
+    report(format!("🗂️ Run metadata written to {}", output_file_path.display()));
+
+    Ok(())
+}
+
+/// Pipes the summary to an external command
+///
+/// # Arguments
+///
+/// * `command` - Shell command line (run with `sh -c`, or `cmd /C` on Windows)
+/// * `summary` - The summary, written to the command's stdin
+/// * `metadata` - The run metadata, passed as environment variables
+///
+/// # Returns
+///
+/// A Result containing the command's exit status or an error if it couldn't be started
+///
+/// The command gets `DISTILL_SOURCE_FILE`, `DISTILL_LANGUAGE_CODE`, `DISTILL_MODEL_ID`,
+/// `DISTILL_RUN_ID` and the whole metadata as JSON in `DISTILL_METADATA`. Its stdout and
+/// stderr are passed through.
+pub fn run_output_command(command: &str, summary: &str, metadata: &RunMetadata) -> Result<ExitStatus> {
+    let mut child = shell_command(command)
+        .env("DISTILL_SOURCE_FILE", &metadata.source_file)
+        .env("DISTILL_LANGUAGE_CODE", &metadata.language_code)
+        .env("DISTILL_MODEL_ID", &metadata.model_id)
+        .env("DISTILL_RUN_ID", run_id())
+        .env("DISTILL_METADATA", serde_json::to_string(metadata)?)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("❌ Error starting output command: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that doesn't read its input closes the pipe early; that's not an error
+        if let Err(err) = stdin.write_all(summary.as_bytes()) {
+            if err.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(anyhow::anyhow!("❌ Error writing to output command: {}", err));
+            }
+        }
+    }
+
+    child
+        .wait()
+        .map_err(|e| anyhow::anyhow!("❌ Error waiting for output command: {}", e))
+}
+
+/// Runs the `[output.<format>]` post-processing command on a written output file
+///
+/// # Arguments
+///
+/// * `post_process` - The format's post-processing settings
+/// * `path` - The output file
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Does nothing without a `post_command`. `{file}` in the command is replaced by the
+/// quoted path; without the placeholder the path is appended. The command also gets the
+/// path in `DISTILL_OUTPUT_FILE`. A failing command stops the run unless
+/// `fail_on_error = false`, in which case a warning is printed.
+pub fn post_process_output(post_process: &PostProcessSettings, path: &Path) -> Result<()> {
+    let Some(command) = post_process.post_command.as_deref().filter(|command| !command.trim().is_empty()) else {
+        return Ok(());
+    };
+
+    let quoted = shell_quote(&path.to_string_lossy());
+    let command = if command.contains("{file}") {
+        command.replace("{file}", &quoted)
+    } else {
+        format!("{} {}", command, quoted)
+    };
+
+    let status = shell_command(&command)
+        .env("DISTILL_OUTPUT_FILE", path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("❌ Error starting post-processing command for {}: {}", path.display(), e))?;
+
+    if status.success() {
+        report(format!("🧩 Post-processed {} ({})", path.display(), status));
+    } else if post_process.fail_on_error {
+        anyhow::bail!("❌ Post-processing command for {} failed ({})", path.display(), status);
+    } else {
+        report(format!("⚠️ Post-processing command for {} failed ({}), ignoring.", path.display(), status));
+    }
+    Ok(())
+}
+
+/// Prepares a command line to run through the platform shell
+fn shell_command(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Quotes a path for the platform shell
+fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value)
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// A file produced during a run
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    /// What the file contains (e.g. "summary", "transcript")
+    pub kind: String,
+    /// Where the file was written
+    pub path: PathBuf,
+    /// The input file the artifact was produced from
+    pub source: String,
+}
+
+// Global flag to disable webhook URL redaction in logs and error messages
+static SHOW_SECRETS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables redaction of webhook URLs in logs and error messages
+///
+/// # Arguments
+///
+/// * `show` - When true, URLs are shown in full (for deep debugging only)
+pub fn set_show_secrets(show: bool) {
+    SHOW_SECRETS.store(show, Ordering::SeqCst);
+}
+
+/// Masks the secret portion of a webhook URL for safe logging
+///
+/// # Arguments
+///
+/// * `url` - The webhook URL to redact
+///
+/// # Returns
+///
+/// The URL with everything after the first path segment replaced by `****`
+/// (e.g. `https://hooks.slack.com/services/****`), or the full URL when
+/// `--show-secrets` is enabled. Unparseable values are masked entirely.
+pub fn redact_url(url: &str) -> String {
+    if SHOW_SECRETS.load(Ordering::SeqCst) {
+        return url.to_string();
+    }
+
+    match Url::parse(url) {
+        Ok(parsed) => {
+            let host = parsed.host_str().unwrap_or_default();
+            let port = parsed.port().map(|p| format!(":{}", p)).unwrap_or_default();
+            let first_segment = parsed
+                .path_segments()
+                .and_then(|mut segments| segments.next())
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| format!("{}/", segment))
+                .unwrap_or_default();
+            format!("{}://{}{}/{}****", parsed.scheme(), host, port, first_segment)
+        }
+        Err(_) => "****".to_string(),
+    }
+}
+
+/// Builds the HTTP client used for webhook requests
+///
+/// # Arguments
+///
+/// * `ca_bundle` - Optional PEM file with additional root certificates (e.g. a private CA)
+/// * `accept_invalid_certs` - Disables TLS certificate verification entirely (testing only)
+/// * `timeout` - How long a webhook request may take in total, or None to wait indefinitely
+///
+/// # Returns
+///
+/// A Result containing the configured client or an error
+///
+/// Every certificate in the bundle is added as a trusted root on top of the system roots,
+/// so webhook relays behind a private PKI can be reached. The timeout only applies to
+/// this client, not to AWS requests.
+pub fn build_webhook_client(
+    ca_bundle: Option<&Path>,
+    accept_invalid_certs: bool,
+    timeout: Option<Duration>,
+) -> Result<ReqwestClient> {
+    let mut builder = ReqwestClient::builder();
+
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    if let Some(ca_bundle) = ca_bundle {
+        let pem = fs::read(ca_bundle)
+            .map_err(|e| anyhow::anyhow!("❌ Error reading CA bundle {}: {}", ca_bundle.display(), e))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| anyhow::anyhow!("❌ Invalid CA bundle {}: {}", ca_bundle.display(), e))?;
+        if certificates.is_empty() {
+            anyhow::bail!("❌ No certificates found in CA bundle {}", ca_bundle.display());
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+        .build()
+        .map_err(|e| anyhow::anyhow!("❌ Error creating HTTP client: {}", e))
+}
+
+/// Formats a webhook request error without leaking the endpoint's secret token
+fn describe_request_error(err: reqwest::Error, endpoint: &str) -> String {
+    format!("{} ({})", err.without_url(), redact_url(endpoint))
+}
+
+// Unique key for this run, sent with every webhook post
+static RUN_ID: OnceLock<String> = OnceLock::new();
+
+// Idempotency keys already delivered successfully, per endpoint
+static DELIVERED: Mutex<Option<HashSet<(String, String)>>> = Mutex::new(None);
+
+/// Returns the unique id of this run
+///
+/// # Returns
+///
+/// A 32 character hex string, generated on first use
+pub fn run_id() -> &'static str {
+    RUN_ID.get_or_init(|| Uuid::new_v4().simple().to_string())
+}
+
+/// Builds the idempotency key for a webhook payload
+///
+/// The key is the run id plus a hash of the input file being processed and the payload,
+/// so a message re-sent for the same file gets the same key, while the parts of a split
+/// summary, or identical summaries of two files in a batch, get different keys.
+fn idempotency_key(payload: &serde_json::Value) -> String {
+    let source = ARTIFACT_SOURCE.lock().map(|source| source.clone()).unwrap_or_default();
+    let digest = Sha256::digest(format!("{}\n{}", source, payload));
+    let hash: String = digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}-{}", run_id(), hash)
+}
+
+/// Posts a JSON payload to a webhook with an `Idempotency-Key` header
+///
+/// # Arguments
+///
+/// * `client` - HTTP client used for the webhook request
+/// * `endpoint` - Resolved webhook URL
+/// * `payload` - The JSON message body
+///
+/// # Returns
+///
+/// A Result containing the response status or the request error
+///
+/// A relay in front of the webhook can use the `Idempotency-Key` header to drop duplicate
+/// posts. Since Slack and Teams ignore the header, a payload that was already delivered to
+/// the same endpoint for the same input file is not sent again; `200 OK` is returned instead.
+async fn post_webhook(
+    client: &ReqwestClient,
+    endpoint: &str,
+    payload: &serde_json::Value,
+) -> Result<StatusCode, reqwest::Error> {
+    let key = idempotency_key(payload);
+    let delivery = (endpoint.to_string(), key.clone());
+
+    if DELIVERED
+        .lock()
+        .map(|delivered| delivered.as_ref().is_some_and(|set| set.contains(&delivery)))
+        .unwrap_or(false)
+    {
+        log::info!("Skipping duplicate post to {} (idempotency key {})", redact_url(endpoint), key);
+        return Ok(StatusCode::OK);
+    }
+
+    let status = client
+        .post(endpoint)
+        .header("Content-Type", "application/json")
+        .header("Idempotency-Key", &key)
+        .json(payload)
+        .send()
+        .await?
+        .status();
+
+    if status.is_success() {
+        if let Ok(mut delivered) = DELIVERED.lock() {
+            delivered.get_or_insert_with(HashSet::new).insert(delivery);
+        }
+    }
+
+    Ok(status)
+}
+
+// Global record of the artifacts produced during the run
+static ARTIFACTS: Mutex<Vec<Artifact>> = Mutex::new(Vec::new());
+
+// Whether written files are re-read and checked (`--verify-output`)
+static VERIFY_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+// Input file that newly recorded artifacts are attributed to
+static ARTIFACT_SOURCE: Mutex<String> = Mutex::new(String::new());
+
+/// Sets the input file that subsequently recorded artifacts are attributed to
+///
+/// # Arguments
+///
+/// * `source` - Path of the input file currently being processed
+pub fn set_artifact_source(source: &str) {
+    *ARTIFACT_SOURCE.lock().unwrap() = source.to_string();
+}
+
+/// Records a file produced during the run
+///
+/// # Arguments
+///
+/// * `kind` - What the file contains (e.g. "summary", "transcript")
+/// * `path` - Where the file was written
+///
+/// # Returns
+///
+/// A Result indicating success, or an error if `--verify-output` is set and the file
+/// fails `verify_output_file()`
+pub fn record_artifact(kind: &str, path: &Path) -> Result<()> {
+    if VERIFY_OUTPUT.load(Ordering::SeqCst) {
+        verify_output_file(path)?;
+    }
+
+    let source = ARTIFACT_SOURCE.lock().unwrap().clone();
+    ARTIFACTS.lock().unwrap().push(Artifact {
+        kind: kind.to_string(),
+        path: path.to_path_buf(),
+        source,
+    });
+    Ok(())
+}
+
+/// Enables re-reading and checking every file right after it is written (`--verify-output`)
+///
+/// # Arguments
+///
+/// * `verify` - When true, `record_artifact()`, `write_manifest()` and `write_bundle()`
+///   verify the files they are given
+pub fn set_verify_output(verify: bool) {
+    VERIFY_OUTPUT.store(verify, Ordering::SeqCst);
+}
+
+/// Re-reads a written file and checks that it is valid
+///
+/// # Arguments
+///
+/// * `path` - The file to check
+///
+/// # Returns
+///
+/// A Result indicating the file is valid, or an error describing what is wrong
+///
+/// Every file must be non-empty. JSON files must parse, Word documents and zip archives
+/// must open as zip archives (a Word document with a `word/document.xml` part), and
+/// text formats (summaries, transcripts, subtitles, SSML, CSV) must be valid UTF-8.
+pub fn verify_output_file(path: &Path) -> Result<()> {
+    let contents = fs::read(path)
+        .map_err(|e| anyhow::anyhow!("❌ Verification failed: cannot read {}: {}", path.display(), e))?;
+    if contents.is_empty() {
+        anyhow::bail!("❌ Verification failed: {} is empty", path.display());
+    }
+
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "json" => {
+            serde_json::from_slice::<serde_json::Value>(&contents).map_err(|e| {
+                anyhow::anyhow!("❌ Verification failed: {} is not valid JSON: {}", path.display(), e)
+            })?;
+        }
+        "docx" | "zip" => {
+            let mut archive = ZipArchive::new(std::io::Cursor::new(&contents)).map_err(|e| {
+                anyhow::anyhow!("❌ Verification failed: {} is not a valid archive: {}", path.display(), e)
+            })?;
+            if extension == "docx" && archive.by_name("word/document.xml").is_err() {
+                anyhow::bail!("❌ Verification failed: {} has no document part", path.display());
+            }
+        }
+        "txt" | "md" | "trans" | "vtt" | "srt" | "ssml" | "csv" => {
+            std::str::from_utf8(&contents).map_err(|e| {
+                anyhow::anyhow!("❌ Verification failed: {} is not valid UTF-8: {}", path.display(), e)
+            })?;
+        }
+        _ => {}
+    }
+
+    log::debug!("Verified {} ({} bytes)", path.display(), contents.len());
+    Ok(())
+}
+
+/// Returns the artifacts recorded so far
+pub fn artifacts() -> Vec<Artifact> {
+    ARTIFACTS.lock().unwrap().clone()
+}
+
+/// Writes a manifest of every artifact produced during the run
+///
+/// # Arguments
+///
+/// * `manifest_path` - Where to write the manifest; a `.csv` extension produces CSV, anything else JSON
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Each entry lists the artifact kind, its path (canonicalized when possible),
+/// its size in bytes, and the input file it was produced from.
+pub fn write_manifest(manifest_path: &Path) -> Result<()> {
+    let entries: Vec<(Artifact, PathBuf, u64)> = artifacts()
+        .into_iter()
+        .map(|artifact| {
+            let path = artifact.path.canonicalize().unwrap_or_else(|_| artifact.path.clone());
+            let size = fs::metadata(&artifact.path).map(|m| m.len()).unwrap_or_default();
+            (artifact, path, size)
+        })
+        .collect();
+
+    let is_csv = manifest_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+    let content = if is_csv {
+        let mut csv = String::from("kind,path,size,source\n");
+        for (artifact, path, size) in &entries {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&artifact.kind),
+                csv_field(&path.display().to_string()),
+                size,
+                csv_field(&artifact.source)
+            ));
+        }
+        csv
+    } else {
+        let artifacts: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(artifact, path, size)| {
+                json!({
+                    "kind": artifact.kind,
+                    "path": path.display().to_string(),
+                    "size": size,
+                    "source": artifact.source,
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&json!({
+            "generated": chrono::Local::now().to_rfc3339(),
+            "artifacts": artifacts,
+        }))?
+    };
+
+    fs::write(manifest_path, content)
+        .map_err(|e| anyhow::anyhow!("❌ Error writing manifest: {}", e))?;
+    if VERIFY_OUTPUT.load(Ordering::SeqCst) {
+        verify_output_file(manifest_path)?;
+    }
+
+    report(format!("🧾 Manifest written to {}", manifest_path.display()));
+
+    Ok(())
+}
+
+/// Collects every artifact produced during the run into a single zip archive
+///
+/// # Arguments
+///
+/// * `bundle_path` - Where to write the zip archive
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Each artifact is stored as `<kind>/<file name>` (e.g. `summary/meeting.docx`,
+/// `transcript/meeting.trans`), and a `manifest.json` at the root of the archive lists
+/// the stored entries with their kind, size and source input file.
+pub fn write_bundle(bundle_path: &Path) -> Result<()> {
+    let file = File::create(bundle_path)
+        .map_err(|e| anyhow::anyhow!("❌ Error creating bundle {}: {}", bundle_path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut entries = Vec::new();
+    for artifact in artifacts() {
+        let file_name = artifact
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| artifact.kind.clone());
+
+        // Keep names unique when several artifacts of one kind share a file name
+        let mut entry_name = format!("{}/{}", artifact.kind, file_name);
+        let mut duplicate = 1;
+        while entries.iter().any(|(name, _, _)| *name == entry_name) {
+            duplicate += 1;
+            entry_name = format!("{}/{}-{}", artifact.kind, duplicate, file_name);
+        }
+
+        let contents = fs::read(&artifact.path)
+            .map_err(|e| anyhow::anyhow!("❌ Error reading {}: {}", artifact.path.display(), e))?;
+        zip.start_file(entry_name.as_str(), options)?;
+        zip.write_all(&contents)?;
+        entries.push((entry_name, artifact, contents.len()));
+    }
+
+    let manifest: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|(name, artifact, size)| {
+            json!({
+                "kind": artifact.kind,
+                "path": name,
+                "size": size,
+                "source": artifact.source,
+            })
+        })
+        .collect();
+    zip.start_file("manifest.json", options)?;
+    zip.write_all(
+        serde_json::to_string_pretty(&json!({
+            "generated": chrono::Local::now().to_rfc3339(),
+            "artifacts": manifest,
+        }))?
+        .as_bytes(),
+    )?;
+    zip.finish()?;
+    if VERIFY_OUTPUT.load(Ordering::SeqCst) {
+        verify_output_file(bundle_path)?;
+    }
+
+    report(format!("📦 Bundle with {} files written to {}", entries.len(), bundle_path.display()));
+
+    Ok(())
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote, or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes summary content to a text file
+///
+/// # Arguments
+///
+/// * `summary_file_name` - Base name for the output file (without extension)
+/// * `summarized_text` - The text content to write to the file
+/// * `spinner` - Progress spinner to update upon completion
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Creates a text file with the provided name and .txt extension,
+/// writes the summarized text to the file, and updates the spinner
+/// to indicate completion.
+pub fn write_text_file(summary_file_name: &str, summarized_text: &str, spinner: &mut Spinner) -> Result<()> {
+    let ext = ".txt";
+    let outfile = summary_file_name.to_owned() + ext;
+    let output_file_path = Path::new(&outfile);
+    let mut file = File::create(output_file_path)
+        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
+
+    file.write_all(summarized_text.as_bytes())
+        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
+
+    record_artifact("summary", output_file_path)?;
+
+    // Simply update the spinner with success message
+    finish_spinner(spinner, "Done!");
+    
+    report(format!("💾 Summary written to {}", output_file_path.display()));
+    
+    Ok(())
+}
+
+/// Longest span of audio covered by one subtitle cue, in seconds
+pub const CAPTION_CUE_SECS: f64 = 7.0;
+
+/// A subtitle cue: the text shown between two points in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionCue {
+    /// When the cue appears, in seconds
+    pub start: f64,
+    /// When the cue disappears, in seconds
+    pub end: f64,
+    /// The words spoken in that time
+    pub text: String,
+}
+
+/// Groups timed words into subtitle cues
+///
+/// # Arguments
+///
+/// * `words` - Word timings from `transcribe::word_timings()`
+///
+/// # Returns
+///
+/// The cues in order
+///
+/// A new cue starts when the next word would make the cue longer than
+/// `CAPTION_CUE_SECS`, or after a word ending a sentence once the cue covers at least
+/// half of that, so cues tend to break at sentence boundaries.
+pub fn caption_cues(words: &[WordTiming]) -> Vec<CaptionCue> {
+    let mut cues: Vec<CaptionCue> = Vec::new();
+    let mut current: Option<CaptionCue> = None;
+
+    for word in words {
+        if let Some(cue) = current.as_mut() {
+            let sentence_ended = cue.text.ends_with(['.', '?', '!']) && cue.end - cue.start >= CAPTION_CUE_SECS / 2.0;
+            if word.end - cue.start > CAPTION_CUE_SECS || sentence_ended {
+                cues.extend(current.take());
+            }
+        }
+        match current.as_mut() {
+            Some(cue) => {
+                cue.end = word.end;
+                cue.text.push(' ');
+                cue.text.push_str(&word.word);
+            }
+            None => {
+                current = Some(CaptionCue { start: word.start, end: word.end, text: word.word.clone() })
+            }
+        }
+    }
+    cues.extend(current);
+
+    cues
+}
+
+/// Formats a time offset in seconds as a subtitle timestamp
+///
+/// # Arguments
+///
+/// * `seconds` - Offset from the beginning of the audio
+/// * `separator` - Separator before the milliseconds (`.` for WebVTT, `,` for SRT)
+///
+/// # Returns
+///
+/// The timestamp as `HH:MM:SS.mmm` (or `HH:MM:SS,mmm`)
+pub fn caption_timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        (millis % 3_600_000) / 60_000,
+        (millis % 60_000) / 1000,
+        separator,
+        millis % 1000
+    )
+}
+
+/// Renders subtitle cues as a WebVTT document
+///
+/// # Arguments
+///
+/// * `cues` - The cues from `caption_cues()`
+///
+/// # Returns
+///
+/// The `WEBVTT` header followed by one block per cue
+pub fn vtt_contents(cues: &[CaptionCue]) -> String {
+    let mut contents = String::from("WEBVTT\n");
+    for cue in cues {
+        contents.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            caption_timestamp(cue.start, '.'),
+            caption_timestamp(cue.end, '.'),
+            cue.text
+        ));
+    }
+    contents
+}
+
+/// Renders subtitle cues as a SubRip (SRT) document
+///
+/// # Arguments
+///
+/// * `cues` - The cues from `caption_cues()`
+///
+/// # Returns
+///
+/// One numbered block per cue, separated by blank lines
+pub fn srt_contents(cues: &[CaptionCue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                caption_timestamp(cue.start, ','),
+                caption_timestamp(cue.end, ','),
+                cue.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Writes subtitles to a WebVTT file
+///
+/// # Arguments
+///
+/// * `summary_file_name` - Base name for the output file (without extension)
+/// * `words` - Word timings from `transcribe::word_timings()`
+/// * `spinner` - Progress spinner to update upon completion
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Groups the words into cues of about `CAPTION_CUE_SECS` seconds and writes them to
+/// a .vtt file.
+pub fn write_vtt_file(summary_file_name: &str, words: &[WordTiming], spinner: &mut Spinner) -> Result<()> {
+    write_subtitle_file(summary_file_name, ".vtt", &vtt_contents(&caption_cues(words)), spinner)
+}
+
+/// Writes subtitles to a SubRip (SRT) file
+///
+/// # Arguments
+///
+/// * `summary_file_name` - Base name for the output file (without extension)
+/// * `words` - Word timings from `transcribe::word_timings()`
+/// * `spinner` - Progress spinner to update upon completion
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Groups the words into cues of about `CAPTION_CUE_SECS` seconds and writes them to
+/// a .srt file.
+pub fn write_srt_file(summary_file_name: &str, words: &[WordTiming], spinner: &mut Spinner) -> Result<()> {
+    write_subtitle_file(summary_file_name, ".srt", &srt_contents(&caption_cues(words)), spinner)
+}
+
+/// Writes rendered subtitles to `<summary_file_name><ext>`
+fn write_subtitle_file(summary_file_name: &str, ext: &str, contents: &str, spinner: &mut Spinner) -> Result<()> {
+    let outfile = summary_file_name.to_owned() + ext;
+    let output_file_path = Path::new(&outfile);
+    fs::write(output_file_path, contents)
+        .map_err(|e| anyhow::anyhow!("❌ Error writing subtitle file: {}", e))?;
+
+    record_artifact("subtitles", output_file_path)?;
+
+    finish_spinner(spinner, "Done!");
+
+    report(format!("💾 Subtitles written to {}", output_file_path.display()));
+
+    Ok(())
+}
+
+/// Pause after a heading in SSML output
+const SSML_HEADING_BREAK: &str = "700ms";
+
+/// Pause after a list item in SSML output
+const SSML_ITEM_BREAK: &str = "300ms";
+
+/// Converts a Markdown summary into SSML for text-to-speech
+///
+/// # Arguments
+///
+/// * `summarized_text` - The summary, usually Markdown
+///
+/// # Returns
+///
+/// A `<speak>` document with one element per non-blank line
+///
+/// Headings are read with strong emphasis followed by a longer pause, list items become
+/// sentences followed by a short pause and other lines become paragraphs. `**bold**` text
+/// is emphasized, the remaining Markdown markers are dropped and `&`, `<`, `>`, `"` and
+/// `'` are escaped.
+pub fn summary_to_ssml(summarized_text: &str) -> String {
+    let mut ssml = String::from("<speak>\n");
+
+    for line in summarized_text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.starts_with("```") || line.chars().all(|c| matches!(c, '-' | '*' | '_' | ' ')) {
+            continue;
+        }
+        if line.starts_with('#') {
+            let heading = ssml_text(line.trim_start_matches('#').trim_end_matches('#'));
+            ssml.push_str(&format!(
+                "<p><emphasis level=\"strong\">{}</emphasis></p><break time=\"{}\"/>\n",
+                heading, SSML_HEADING_BREAK
+            ));
+        } else if let Some(item) = list_item_text(line) {
+            ssml.push_str(&format!("<s>{}</s><break time=\"{}\"/>\n", ssml_text(item), SSML_ITEM_BREAK));
+        } else {
+            ssml.push_str(&format!("<p>{}</p>\n", ssml_text(line)));
+        }
+    }
+
+    ssml.push_str("</speak>\n");
+    ssml
+}
+
+/// The text of a bulleted (`-`, `*`, `+`) or numbered (`1.`, `1)`) list item
+fn list_item_text(line: &str) -> Option<&str> {
+    if let Some(item) = line.strip_prefix(['-', '*', '+']).filter(|item| item.starts_with(' ')) {
+        return Some(item.trim());
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    line[digits..]
+        .strip_prefix(['.', ')'])
+        .filter(|item| digits > 0 && item.starts_with(' '))
+        .map(str::trim)
+}
+
+/// Escapes a line for SSML, emphasizing `**bold**` text and dropping other Markdown markers
+fn ssml_text(text: &str) -> String {
+    let escaped = text
+        .trim()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;");
+
+    let mut ssml = String::new();
+    for (index, part) in escaped.split("**").enumerate() {
+        let part = part.replace(['*', '`'], "");
+        if index % 2 == 1 && !part.trim().is_empty() {
+            ssml.push_str(&format!("<emphasis>{}</emphasis>", part));
+        } else {
+            ssml.push_str(&part);
+        }
+    }
+    ssml
+}
+
+/// Writes the summary as an SSML document
+///
+/// # Arguments
+///
+/// * `summary_file_name` - Base name for the output file (without extension)
+/// * `summarized_text` - The summary to convert
+/// * `spinner` - Progress spinner to update upon completion
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Converts the summary with `summary_to_ssml()` and writes it to a .ssml file, ready to
+/// be passed to Amazon Polly with `--text-type ssml`.
+pub fn write_ssml_file(summary_file_name: &str, summarized_text: &str, spinner: &mut Spinner) -> Result<()> {
+    let outfile = summary_file_name.to_owned() + ".ssml";
+    let output_file_path = Path::new(&outfile);
+    fs::write(output_file_path, summary_to_ssml(summarized_text))
+        .map_err(|e| anyhow::anyhow!("❌ Error writing SSML file: {}", e))?;
+
+    record_artifact("summary", output_file_path)?;
+
+    finish_spinner(spinner, "Done!");
+
+    report(format!("💾 Summary written to {}", output_file_path.display()));
+
+    Ok(())
+}
+
+/// Writes summary content to a Microsoft Word document
+///
+/// # Arguments
+///
+/// * `summary_file_name` - Base name for the output file (without extension)
+/// * `summarized_text` - The text content to write to the file
+/// * `settings` - Application settings containing the optional `output.word_*` formatting
+/// * `toc` - Whether to start the document with a numbered list of the summary's `##` headings
+/// * `spinner` - Progress spinner to update upon completion
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Creates a Word document (.docx) with the provided name, formats the content
+/// using docx-rs library, and adds each line of the summarized text as a paragraph.
+/// `output.word_font`, `output.word_font_size` (points) and `output.word_line_spacing`
+/// (a multiple of single spacing) are applied when set; otherwise docx-rs defaults are used.
+pub fn write_word_file(
+    summary_file_name: &str,
+    summarized_text: &str,
+    settings: &Settings,
+    toc: bool,
+    spinner: &mut Spinner,
+) -> Result<()> {
+    let ext = ".docx";
+    let outfile = summary_file_name.to_owned() + ext;
+    let output_file_path = Path::new(&outfile);
+    let file = File::create(output_file_path)
+        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
+
+    let font = settings.output.word_font.as_ref();
+    // Word measures font sizes in half-points and "auto" line spacing in 240ths of a line
+    let font_size = settings
+        .output
+        .word_font_size
+        .map(|points| (points * 2.0).round() as usize);
+    let line_spacing = settings
+        .output
+        .word_line_spacing
+        .map(|lines| (lines * 240.0).round() as i32);
+
+    // The table of contents is a plain numbered list, so it needs no field update in Word
+    let mut toc_lines = Vec::new();
+    if toc {
+        let headings = summary_headings(summarized_text);
+        if !headings.is_empty() {
+            toc_lines.push("Contents".to_string());
+            toc_lines.extend(headings.iter().enumerate().map(|(i, heading)| format!("{}. {}", i + 1, heading)));
+            toc_lines.push(String::new());
+        }
+    }
+
+    // Creating a new document and adding one paragraph per line
+    let mut doc = Docx::new();
+    for line in toc_lines.iter().map(String::as_str).chain(summarized_text.lines()).chain(["", ""]) {
+        let mut run = Run::new().add_text(line);
+        if let Some(font) = font {
+            run = run.fonts(RunFonts::new().ascii(font).hi_ansi(font).east_asia(font).cs(font));
+        }
+        if let Some(font_size) = font_size {
+            run = run.size(font_size);
+        }
+
+        let mut paragraph = Paragraph::new().add_run(run);
+        if let Some(line_spacing) = line_spacing {
+            paragraph = paragraph.line_spacing(
+                LineSpacing::new().line_rule(LineSpacingType::Auto).line(line_spacing),
+            );
+        }
+        doc = doc.add_paragraph(paragraph);
+    }
+
+    // Building and saving the document
+    doc.build()
+        .pack(file)
+        .map_err(|e| anyhow::anyhow!("❌ Error writing Word document: {}", e))?;
+
+    record_artifact("summary", output_file_path)?;
+
+    finish_spinner(spinner, "Done!");
+    
+    report(format!("💾 Summary written to {}", output_file_path.display()));
+    
+    Ok(())
+}
+
+/// Writes summary content to a Markdown file
+///
+/// # Arguments
+///
+/// * `summary_file_name` - Base name for the output file (without extension)
+/// * `summarized_text` - The text content to write to the file
+/// * `frontmatter` - Run metadata to prepend as YAML frontmatter, if requested
+/// * `toc` - Whether to add a table of contents linking to the summary's `##` headings
+/// * `spinner` - Progress spinner to update upon completion
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Creates a Markdown file with the provided name and .md extension,
+/// formats the content with Markdown syntax, and adds a header. When run
+/// metadata is given, the file starts with a YAML frontmatter block so it
+/// can be published directly by static site generators such as Hugo or Jekyll.
+pub fn write_markdown_file(
+    summary_file_name: &str,
+    summarized_text: &str,
+    frontmatter: Option<&RunMetadata>,
+    toc: bool,
+    spinner: &mut Spinner,
+) -> Result<()> {
+    let ext = ".md";
+    let outfile = summary_file_name.to_owned() + ext;
+    let output_file_path = Path::new(&outfile);
+    let mut file = File::create(output_file_path)
+        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
+
+    let mut markdown_content = frontmatter.map(markdown_frontmatter).unwrap_or_default();
+    markdown_content.push_str("# Summary\n\n");
+    if toc {
+        markdown_content.push_str(&markdown_toc(summarized_text).unwrap_or_default());
+    }
+    markdown_content.push_str(summarized_text);
+
+    file.write_all(markdown_content.as_bytes())
+        .map_err(|e| anyhow::anyhow!("❌ Error writing Markdown file: {}", e))?;
+
+    record_artifact("summary", output_file_path)?;
+
+    finish_spinner(spinner, "Done!");
+    
+    report(format!("💾 Summary written to {}", output_file_path.display()));
+    
+    Ok(())
+}
+
+/// Builds a YAML frontmatter block from the run metadata
+///
+/// # Arguments
+///
+/// * `metadata` - The metadata collected during the run
+///
+/// # Returns
+///
+/// The frontmatter block, including the `---` delimiters and a trailing blank line
+///
+/// The title is derived from the input file name and the date is the run's
+/// ISO 8601 start time.
+fn markdown_frontmatter(metadata: &RunMetadata) -> String {
+    let title = Path::new(&metadata.source_file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| metadata.source_file.clone());
+
+    let fields = [
+        ("title", title.as_str()),
+        ("date", metadata.created.as_str()),
+        ("source", metadata.source_file.as_str()),
+        ("language", metadata.language_code.as_str()),
+        ("model", metadata.model_id.as_str()),
+    ];
+
+    let mut frontmatter = String::from("---\n");
+    for (key, value) in fields {
+        frontmatter.push_str(&format!(
+            "{}: \"{}\"\n",
+            key,
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        ));
+    }
+    frontmatter.push_str("---\n\n");
+
+    frontmatter
+}
+
+/// Finds the `##` headings of a Markdown summary
+///
+/// # Arguments
+///
+/// * `summarized_text` - The summary
+///
+/// # Returns
+///
+/// The heading texts in order, without the `##` marker, closing `#`s or bold markers
+///
+/// Lines inside fenced code blocks are ignored, as are deeper (`###`) headings.
+pub fn summary_headings(summarized_text: &str) -> Vec<String> {
+    let mut in_code_block = false;
+    let mut headings = Vec::new();
+    for line in summarized_text.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        if let Some(heading) = line.strip_prefix("## ") {
+            let heading = heading.trim().trim_end_matches('#').trim().trim_matches('*').trim();
+            if !heading.is_empty() {
+                headings.push(heading.to_string());
+            }
+        }
+    }
+    headings
+}
+
+/// Builds the anchor GitHub and most Markdown renderers give a heading
+///
+/// # Arguments
+///
+/// * `heading` - Text of the heading
+///
+/// # Returns
+///
+/// The heading lowercased, with spaces turned into `-` and other punctuation dropped
+pub fn heading_anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Builds a Markdown table of contents for a summary
+///
+/// # Arguments
+///
+/// * `summarized_text` - The summary
+///
+/// # Returns
+///
+/// A "## Contents" section with a numbered link to each `##` heading, followed by a
+/// blank line, or None when the summary has no headings
+///
+/// Repeated headings get the `-1`, `-2`, ... suffixes renderers add to their anchors,
+/// counting the "# Summary" and "## Contents" headings the Markdown file starts with.
+pub fn markdown_toc(summarized_text: &str) -> Option<String> {
+    let headings = summary_headings(summarized_text);
+    if headings.is_empty() {
+        return None;
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::from([("summary".to_string(), 1), ("contents".to_string(), 1)]);
+    let mut toc = String::from("## Contents\n\n");
+    for (i, heading) in headings.iter().enumerate() {
+        let anchor = heading_anchor(heading);
+        let count = seen.entry(anchor.clone()).or_insert(0);
+        let anchor = if *count == 0 { anchor } else { format!("{}-{}", anchor, count) };
+        *count += 1;
+        toc.push_str(&format!("{}. [{}](#{})\n", i + 1, heading, anchor));
+    }
+    toc.push('\n');
+
+    Some(toc)
+}
+
+/// Longest text Slack accepts in a header block
+const SLACK_HEADER_MAX_CHARS: usize = 150;
+
+/// Builds the Slack webhook payload for a summary
+///
+/// # Arguments
+///
+/// * `summarized_text` - The summary
+/// * `title` - Optional message title
+///
+/// # Returns
+///
+/// The JSON payload. Without a title, `content` starts with the fixed "A summarization
+/// job just completed:" line. With a title, `content` starts with the title instead, and
+/// the title is also sent as a `title` variable (for workflow webhooks) and as a header
+/// block (for incoming webhooks).
+fn slack_payload(summarized_text: &str, title: Option<&str>) -> serde_json::Value {
+    match title {
+        Some(title) => {
+            let header: String = title.chars().take(SLACK_HEADER_MAX_CHARS).collect();
+            json!({
+                "content": format!("{}\n\n{}", title, summarized_text),
+                "title": title,
+                "blocks": [
+                    {
+                        "type": "header",
+                        "text": { "type": "plain_text", "text": header }
+                    }
+                ]
+            })
+        }
+        None => json!({
+            "content": format!("A summarization job just completed:\n\n{}", summarized_text)
+        }),
     }
-    
-    println!("💾 Summary written to {}", output_file_path.display());
-    
-    Ok(())
 }
 
-/// Writes summary content to a Microsoft Word document
+/// Slack Web API method used with `slack.bot_token`
+const SLACK_POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+
+/// Builds the `chat.postMessage` payload for a summary
 ///
 /// # Arguments
 ///
-/// * `summary_file_name` - Base name for the output file (without extension)
-/// * `summarized_text` - The text content to write to the file
-/// * `spinner` - Progress spinner to update upon completion
+/// * `channel` - Channel ID to post to
+/// * `summarized_text` - The summary
+/// * `title` - Optional message title, shown in bold on the first line
+/// * `thread_ts` - Timestamp of the thread to reply in, if any
 ///
 /// # Returns
 ///
-/// A Result indicating success or an error
-///
-/// Creates a Word document (.docx) with the provided name, formats the content
-/// using docx-rs library, and adds the summarized text as paragraphs in the document.
-pub fn write_word_file(summary_file_name: &str, summarized_text: &str, spinner: &mut Spinner) -> Result<()> {
-    let ext = ".docx";
-    let outfile = summary_file_name.to_owned() + ext;
-    let output_file_path = Path::new(&outfile);
-    let file = File::create(output_file_path)
-        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
-
-    // Creating a new document and adding paragraphs
-    let doc = Docx::new()
-        .add_paragraph(Paragraph::new().add_run(Run::new().add_text(summarized_text)))
-        .add_paragraph(Paragraph::new().add_run(Run::new().add_text("\n\n")));
-
-    // Building and saving the document
-    doc.build()
-        .pack(file)
-        .map_err(|e| anyhow::anyhow!("❌ Error writing Word document: {}", e))?;
-
-    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-        spinner.success("Done!");
-        SPINNER_STOPPED.store(true, Ordering::SeqCst);
+/// The JSON payload. Without a title, the message starts with the same "A summarization
+/// job just completed:" line as webhook messages.
+pub fn slack_bot_payload(
+    channel: &str,
+    summarized_text: &str,
+    title: Option<&str>,
+    thread_ts: Option<&str>,
+) -> serde_json::Value {
+    let text = match title {
+        Some(title) => format!("*{}*\n\n{}", title, summarized_text),
+        None => format!("A summarization job just completed:\n\n{}", summarized_text),
+    };
+    let mut payload = json!({ "channel": channel, "text": text });
+    if let Some(thread_ts) = thread_ts {
+        payload["thread_ts"] = json!(thread_ts);
     }
-    
-    println!("💾 Summary written to {}", output_file_path.display());
-    
-    Ok(())
+    payload
 }
 
-/// Writes summary content to a Markdown file
+/// Posts a summary with a Slack bot token, optionally as a thread reply
 ///
 /// # Arguments
 ///
-/// * `summary_file_name` - Base name for the output file (without extension)
-/// * `summarized_text` - The text content to write to the file
-/// * `spinner` - Progress spinner to update upon completion
+/// * `settings` - Application settings containing `slack.bot_token` and `slack.channel`
+/// * `client` - HTTP client used for the request
+/// * `spinner` - Progress spinner to update during the process
+/// * `summarized_text` - The text content to send to Slack
+/// * `title` - Optional message title (`--notify-title` / `notify.title`)
+/// * `thread_ts` - Timestamp of the thread to reply in (`--thread-ts`)
 ///
 /// # Returns
 ///
-/// A Result indicating success or an error
+/// A Result containing 1 if the message was posted and 0 if not, or an error
 ///
-/// Creates a Markdown file with the provided name and .md extension,
-/// formats the content with Markdown syntax, and adds a header.
-pub fn write_markdown_file(summary_file_name: &str, summarized_text: &str, spinner: &mut Spinner) -> Result<()> {
-    let ext = ".md";
-    let outfile = summary_file_name.to_owned() + ext;
-    let output_file_path = Path::new(&outfile);
-    let mut file = File::create(output_file_path)
-        .map_err(|e| anyhow::anyhow!("❌ Error creating file: {}", e))?;
+/// Incoming webhooks can't reply in threads, so this uses `chat.postMessage`. Slack
+/// answers failures with `"ok": false` and an error code (e.g. `channel_not_found`,
+/// `not_in_channel`), which is printed like a failed webhook request.
+pub async fn send_slack_bot_message(
+    settings: &Settings,
+    client: &ReqwestClient,
+    spinner: &mut Spinner,
+    summarized_text: &str,
+    title: Option<&str>,
+    thread_ts: Option<&str>,
+) -> Result<usize> {
+    let Some(channel) = settings.slack.channel.as_deref().filter(|channel| !channel.is_empty()) else {
+        persist_spinner(spinner, "⚠️", "slack.channel is not configured. Skipping Slack notification.");
+        report(format!("Summary:\n{}\n", summarized_text));
+        return Ok(0);
+    };
+    let token = match aws_utils::resolve_endpoint(settings.slack.bot_token.as_deref().unwrap_or_default()).await {
+        Ok(token) => token,
+        Err(err) => {
+            report(format!("❌ Error resolving the Slack bot token: {:#}", err));
+            persist_spinner(spinner, "❌", "Failed to send summary to Slack!");
+            return Ok(0);
+        }
+    };
 
-    let summary_md = format!("# Summary\n\n{}", summarized_text);
-    let markdown_content = format!("{}", summary_md);
+    update_spinner(spinner, "Sending to Slack", spinner_color());
+    let payload = slack_bot_payload(channel, summarized_text, title, thread_ts);
+    let response = client
+        .post(SLACK_POST_MESSAGE_URL)
+        .bearer_auth(token)
+        .json(&payload)
+        .send()
+        .await;
 
-    file.write_all(markdown_content.as_bytes())
-        .map_err(|e| anyhow::anyhow!("❌ Error writing Markdown file: {}", e))?;
+    let result = match response {
+        Ok(response) if response.status().is_success() => match response.json::<serde_json::Value>().await {
+            Ok(body) if body["ok"].as_bool() == Some(true) => Ok(()),
+            Ok(body) => Err(body["error"].as_str().unwrap_or("unknown error").to_string()),
+            Err(err) => Err(err.to_string()),
+        },
+        Ok(response) => Err(response.status().to_string()),
+        Err(err) => Err(describe_request_error(err, SLACK_POST_MESSAGE_URL)),
+    };
 
-    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-        spinner.success("Done!");
-        SPINNER_STOPPED.store(true, Ordering::SeqCst);
+    match result {
+        Ok(()) if thread_ts.is_some() => {
+            finish_spinner(spinner, "Summary posted in the Slack thread!");
+        }
+        Ok(()) => {
+            finish_spinner(spinner, "Summary sent to Slack!");
+        }
+        Err(err) => {
+            report(format!("❌ Error sending summary to Slack: {}", err));
+            persist_spinner(spinner, "❌", "Failed to send summary to Slack!");
+            return Ok(0);
+        }
     }
-    
-    println!("💾 Summary written to {}", output_file_path.display());
-    
-    Ok(())
+    Ok(1)
 }
 
 /// Sends a summary notification to one or more Slack webhooks
 ///
 /// # Arguments
 ///
-/// * `settings` - Application configuration containing the Slack webhook URLs
+/// * `settings` - Application settings containing the Slack webhook URLs
+/// * `client` - HTTP client used for the webhook requests
 /// * `spinner` - Progress spinner to update during the process
 /// * `summarized_text` - The text content to send to Slack
+/// * `title` - Optional message title (`--notify-title` / `notify.title`)
 /// * `webhook_indices` - Indices of the selected webhooks to use
 ///
 /// # Returns
 ///
-/// A Result indicating success or an error
+/// A Result containing the number of webhooks the summary was delivered to, or an error
 ///
 /// # Spinner Management
 ///
-/// This function checks the `SPINNER_STOPPED` flag before stopping the spinner
-/// and updates the flag after stopping it. It uses `spinner.update()` for progress
-/// updates and only stops the spinner at the end of processing.
+/// Progress is reported with `update_spinner()`, and the spinner is only stopped
+/// (via `finish_spinner()` / `persist_spinner()`) at the end of processing.
 ///
 /// # Webhook Processing
 ///
@@ -203,230 +1793,532 @@ pub fn write_markdown_file(summary_file_name: &str, summarized_text: &str, spinn
 /// and sends the message to each selected Slack webhook endpoint. Supports both legacy
 /// single webhook configuration and multiple webhook configuration.
 pub async fn send_slack_notification(
-    settings: &Config,
+    settings: &Settings,
+    client: &ReqwestClient,
     spinner: &mut Spinner,
     summarized_text: &str,
+    title: Option<&str>,
     webhook_indices: &[usize],
-) -> Result<()> {
-    let client = ReqwestClient::new();
-
+) -> Result<usize> {
     // Get webhooks from config
-    let webhooks = match settings.get_array("slack.webhooks") {
-        Ok(webhooks) => webhooks,
-        Err(_) => {
+    let webhooks = match &settings.slack.webhooks {
+        Some(webhooks) => webhooks,
+        None => {
             // Try legacy single webhook endpoint for backward compatibility
-            let slack_webhook_endpoint = settings
-                .get_string("slack.webhook_endpoint")
-                .unwrap_or_default();
+            let slack_webhook_endpoint = settings.slack.webhook_endpoint.clone();
                 
             if slack_webhook_endpoint.is_empty() {
-                if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                    spinner.stop_and_persist(
-                        "⚠️",
-                        "Slack webhook endpoint is not configured. Skipping Slack notification.",
-                    );
-                    SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                }
-                println!("Summary:\n{}\n", summarized_text);
-                return Ok(());
+                persist_spinner(spinner, "⚠️", "Slack webhook endpoint is not configured. Skipping Slack notification.");
+                report(format!("Summary:\n{}\n", summarized_text));
+                return Ok(0);
             }
+
+            let slack_webhook_endpoint = match aws_utils::resolve_endpoint(&slack_webhook_endpoint).await {
+                Ok(endpoint) => endpoint,
+                Err(err) => {
+                    report(format!("❌ Error resolving Slack webhook endpoint: {:#}", err));
+                    persist_spinner(spinner, "❌", "Failed to send summary to Slack!");
+                    return Ok(0);
+                }
+            };
             
             // For legacy single webhook, just use it directly
             let message = "Sending to Slack";
-            spinner.update(spinners::Dots, message, Some(Color::White));
+            update_spinner(spinner, message, spinner_color());
             
-            let content = format!("A summarization job just completed:\n\n{}", summarized_text);
-            let payload = json!({
-                "content": content
-            });
+            let payload = slack_payload(summarized_text, title);
             
-            let result = client
-                .post(&slack_webhook_endpoint)
-                .header("Content-Type", "application/json")
-                .json(&payload)
-                .send()
-                .await;
+            log::debug!("Posting summary to Slack webhook {}", redact_url(&slack_webhook_endpoint));
+            let result = post_webhook(client, &slack_webhook_endpoint, &payload).await;
                 
             match result {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                            spinner.success("Summary sent to Slack!");
-                            SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                        }
+                Ok(status) => {
+                    if status.is_success() {
+                        finish_spinner(spinner, "Summary sent to Slack!");
+                        return Ok(1);
                     } else {
-                        let status = response.status();
-                        println!("❌ Error sending summary to Slack: {}", status);
-                        if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                            spinner.stop_and_persist("❌", "Failed to send summary to Slack!");
-                            SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                        }
+                        report(format!("❌ Error sending summary to Slack: {}", status));
+                        persist_spinner(spinner, "❌", "Failed to send summary to Slack!");
                     }
                 }
                 Err(err) => {
-                    let err_msg = err.to_string();
-                    println!("❌ Error sending summary to Slack: {}", err_msg);
-                    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                        spinner.stop_and_persist("❌", "Failed to send summary to Slack!");
-                        SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                    }
+                    let err_msg = describe_request_error(err, &slack_webhook_endpoint);
+                    report(format!("❌ Error sending summary to Slack: {}", err_msg));
+                    persist_spinner(spinner, "❌", "Failed to send summary to Slack!");
                 }
             }
             
-            return Ok(());
+            return Ok(0);
         }
     };
     
     if webhooks.is_empty() || webhook_indices.is_empty() {
-        if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-            spinner.stop_and_persist(
-                "⚠️",
-                "No Slack webhooks selected. Skipping Slack notification.",
-            );
-            SPINNER_STOPPED.store(true, Ordering::SeqCst);
-        }
-        println!("Summary:\n{}\n", summarized_text);
-        return Ok(());
+        persist_spinner(spinner, "⚠️", "No Slack webhooks selected. Skipping Slack notification.");
+        report(format!("Summary:\n{}\n", summarized_text));
+        return Ok(0);
     }
     
     // Create the message payload
-    let content = format!("A summarization job just completed:\n\n{}", summarized_text);
-    let payload = json!({
-        "content": content
-    });
-    
-    // Update the main spinner instead of stopping it
-    let processing_msg = format!("Processing {} Slack webhooks...", webhook_indices.len());
-    let static_processing_msg: &'static str = Box::leak(processing_msg.into_boxed_str());
-    spinner.update(spinners::Dots, static_processing_msg, Some(Color::White));
-    
-    // Send to each selected webhook
-    let mut success_count = 0;
-    let mut failure_count = 0;
-    
-    for &index in webhook_indices {
-        if index >= webhooks.len() {
-            continue;
-        }
-        
-        // Extract webhook details safely
-        let webhook = &webhooks[index];
-        
-        // Use into_table() to get a table view of the config value
-        let webhook_table = match webhook.clone().into_table() {
-            Ok(table) => table,
-            Err(_) => continue,
-        };
-        
-        // Get name and endpoint from the table
-        let webhook_name = webhook_table.get("name")
-            .and_then(|v| v.clone().into_string().ok())
-            .unwrap_or_else(|| format!("Webhook {}", index + 1));
-            
-        let endpoint = match webhook_table.get("endpoint").and_then(|v| v.clone().into_string().ok()) {
-            Some(ep) => ep,
-            None => continue,
-        };
-        
+    let payload = slack_payload(summarized_text, title);
+
+    // Post to the selected webhooks concurrently; the spinner only shows the overall result
+    let selected = selected_webhooks(settings, "slack", webhook_indices);
+    let total = selected.len();
+    update_spinner(spinner, format!("Sending to {} Slack webhooks...", total), spinner_color());
+    let failures = broadcast(client, "Slack", selected, &payload, settings.notify.concurrency).await;
+    for (_, name, err) in &failures {
+        report(format!("❌ Error sending to Slack ({}): {}", name, err));
+    }
+
+    // Update the spinner with the final result
+    let success_count = total - failures.len();
+    if failures.is_empty() && success_count > 0 {
+        let message = format!("Summary sent to {}/{} Slack webhooks", success_count, total);
+        finish_spinner(spinner, &message);
+    } else if success_count > 0 {
+        let message = format!("Sent to {}/{} Slack webhooks", success_count, total);
+        persist_spinner(spinner, "⚠️", &message);
+    } else {
+        persist_spinner(spinner, "❌", "Failed to send summary to any Slack webhooks!");
+    }
+
+    Ok(success_count)
+}
+
+/// Saves a summary that reached no Slack or Teams destination to the fallback file
+///
+/// # Arguments
+///
+/// * `path` - The fallback file (`notify.fallback_file`)
+/// * `service` - Service the summary was meant for ("Slack" or "Teams")
+/// * `summarized_text` - The summary
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// Summaries are appended under a heading naming the input file and the time, so an
+/// earlier undelivered summary (from a previous file of a batch, or a previous run) is
+/// never overwritten.
+pub fn write_fallback_summary(path: &Path, service: &str, summarized_text: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("❌ Error creating directory {}: {}", parent.display(), e))?;
+    }
+
+    let source = ARTIFACT_SOURCE.lock().unwrap().clone();
+    let date = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("❌ Error opening fallback file {}: {}", path.display(), e))?;
+    writeln!(file, "## {} (not sent to {}, {})\n\n{}\n", source, service, date, summarized_text.trim_end())
+        .map_err(|e| anyhow::anyhow!("❌ Error writing fallback file {}: {}", path.display(), e))?;
+    record_artifact("fallback_summary", path)?;
+
+    report(format!("💾 The summary reached no {} destination; it was saved to {}", service, path.display()));
+    Ok(())
+}
+
+/// Looks up the name and endpoint of the selected webhooks for a service
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing the webhooks
+/// * `service` - Service name ("teams" or "slack")
+/// * `webhook_indices` - Indices of the selected webhooks
+///
+/// # Returns
+///
+/// The (name, endpoint) pairs of the selected webhooks, or the legacy
+/// `webhook_endpoint` when no `webhooks` array is configured
+fn selected_webhooks(settings: &Settings, service: &str, webhook_indices: &[usize]) -> Vec<(String, String)> {
+    let service_settings = settings.webhooks_for(service);
+    let Some(webhooks) = service_settings.webhooks else {
+        let endpoint = service_settings.webhook_endpoint;
         if endpoint.is_empty() {
-            continue;
+            return Vec::new();
         }
-        
-        // Update the main spinner with the current webhook
-        let message = format!("Sending to Slack ({})", webhook_name);
-        let static_message: &'static str = Box::leak(message.into_boxed_str());
-        spinner.update(spinners::Dots, static_message, Some(Color::White));
-        
-        let result = client
-            .post(&endpoint)
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await;
-            
-        match result {
-            Ok(response) => {
-                if response.status().is_success() {
-                    success_count += 1;
-                    // Update spinner with success message
-                    let success_msg = format!("Successfully sent to Slack ({})", webhook_name);
-                    let static_success_msg: &'static str = Box::leak(success_msg.into_boxed_str());
-                    spinner.update(spinners::Dots, static_success_msg, Some(Color::Green));
-                } else {
-                    let status = response.status();
-                    // Update spinner with error message
-                    let error_msg = format!("❌ Error sending to Slack ({}): {}", webhook_name, status);
-                    let static_error_msg: &'static str = Box::leak(error_msg.into_boxed_str());
-                    spinner.update(spinners::Dots, static_error_msg, Some(Color::Red));
-                    failure_count += 1;
+        return vec![(service.to_string(), endpoint)];
+    };
+
+    webhook_indices
+        .iter()
+        .filter_map(|&index| {
+            let webhook = webhooks.get(index)?;
+            let name = webhook
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Webhook {}", index + 1));
+            Some((name, webhook.endpoint.clone())).filter(|(_, endpoint)| !endpoint.is_empty())
+        })
+        .collect()
+}
+
+/// Posts a short status message to the selected Slack or Teams webhooks
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing the webhooks
+/// * `client` - HTTP client used for the webhook requests
+/// * `service` - Service name ("teams" or "slack")
+/// * `webhook_indices` - Indices of the selected webhooks
+/// * `message` - The status message to post
+///
+/// # Returns
+///
+/// The number of webhooks the message was delivered to
+///
+/// Unlike the summary notifications this doesn't touch the spinner, so it can be sent
+/// while another step is in progress. Failures are logged and otherwise ignored.
+pub async fn send_status_message(
+    settings: &Settings,
+    client: &ReqwestClient,
+    service: &str,
+    webhook_indices: &[usize],
+    message: &str,
+) -> usize {
+    let payload = if service == "teams" {
+        json!({
+            "type": "message",
+            "attachments": [
+                {
+                    "contentType": "application/vnd.microsoft.card.adaptive",
+                    "contentUrl": null,
+                    "content": {
+                        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                        "type": "AdaptiveCard",
+                        "version": "1.5",
+                        "body": [
+                            {
+                                "type": "TextBlock",
+                                "wrap": true,
+                                "text": message
+                            }
+                        ]
+                    }
                 }
+            ]
+        })
+    } else {
+        json!({
+            "content": message
+        })
+    };
+
+    let selected = selected_webhooks(settings, service, webhook_indices);
+    let total = selected.len();
+    let failures = broadcast(client, service, selected, &payload, settings.notify.concurrency).await;
+    for (_, name, err) in &failures {
+        log::warn!("{} webhook '{}' failed: {}", service, name, err);
+    }
+
+    total - failures.len()
+}
+
+/// Checks that the selected Slack or Teams webhooks can be reached, for --dry-run
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing the webhooks
+/// * `client` - HTTP client used for the webhook requests
+/// * `service` - Service name ("teams" or "slack")
+/// * `webhook_indices` - Indices of the selected webhooks
+///
+/// # Returns
+///
+/// The name of each selected webhook with the HTTP status it answered with, or the
+/// reason it could not be reached
+///
+/// Each endpoint gets a HEAD request, so nothing is posted to the channel. Any HTTP
+/// response counts as reachable: webhooks usually answer HEAD with 405 Method Not Allowed.
+pub async fn check_webhooks(
+    settings: &Settings,
+    client: &ReqwestClient,
+    service: &str,
+    webhook_indices: &[usize],
+) -> Vec<(String, Result<StatusCode, String>)> {
+    let webhooks = selected_webhooks(settings, service, webhook_indices);
+    stream::iter(webhooks)
+        .map(|(name, endpoint)| async move {
+            let endpoint = match aws_utils::resolve_endpoint(&endpoint).await {
+                Ok(endpoint) => endpoint,
+                Err(err) => return (name, Err(format!("could not resolve the endpoint: {:#}", err))),
+            };
+            (name, check_endpoint(client, &endpoint).await)
+        })
+        .buffered(settings.notify.concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Checks that the Slack Web API used in bot-token mode can be reached, for --dry-run
+///
+/// # Arguments
+///
+/// * `client` - HTTP client used for the webhook requests
+///
+/// # Returns
+///
+/// The HTTP status `chat.postMessage` answered a HEAD request with, or the reason it
+/// could not be reached
+pub async fn check_slack_api(client: &ReqwestClient) -> Result<StatusCode, String> {
+    check_endpoint(client, SLACK_POST_MESSAGE_URL).await
+}
+
+/// Sends a HEAD request to an endpoint
+async fn check_endpoint(client: &ReqwestClient, endpoint: &str) -> Result<StatusCode, String> {
+    log::debug!("Checking {}", redact_url(endpoint));
+    client
+        .head(endpoint)
+        .send()
+        .await
+        .map(|response| response.status())
+        .map_err(|err| describe_request_error(err, endpoint))
+}
+
+/// Posts a payload to several webhooks, at most `concurrency` at a time
+///
+/// # Arguments
+///
+/// * `client` - HTTP client used for the webhook requests
+/// * `service` - Service name, used in log messages
+/// * `webhooks` - (name, endpoint) pairs from `selected_webhooks()`
+/// * `payload` - The JSON payload
+/// * `concurrency` - Maximum number of requests in flight (`notify.concurrency`, at least 1)
+///
+/// # Returns
+///
+/// The position in `webhooks`, name and error of each webhook the payload could not be
+/// delivered to, in the order of `webhooks`
+///
+/// Endpoints are resolved (see `aws_utils::resolve_endpoint()`) right before posting.
+/// Nothing is printed, so callers can report one summary instead of racing updates.
+async fn broadcast(
+    client: &ReqwestClient,
+    service: &str,
+    webhooks: Vec<(String, String)>,
+    payload: &serde_json::Value,
+    concurrency: usize,
+) -> Vec<(usize, String, String)> {
+    let mut failures: Vec<(usize, String, String)> = stream::iter(webhooks.into_iter().enumerate())
+        .map(|(position, (name, endpoint))| async move {
+            let endpoint = match aws_utils::resolve_endpoint(&endpoint).await {
+                Ok(endpoint) => endpoint,
+                Err(err) => return Some((position, name, format!("could not resolve the endpoint: {:#}", err))),
+            };
+
+            log::debug!("Posting to {} webhook '{}' at {}", service, name, redact_url(&endpoint));
+            match post_webhook(client, &endpoint, payload).await {
+                Ok(status) if status.is_success() => None,
+                Ok(status) => Some((position, name, status.to_string())),
+                Err(err) => Some((position, name, describe_request_error(err, &endpoint))),
             }
-            Err(err) => {
-                let err_msg = err.to_string();
-                // Update spinner with error message
-                let error_msg = format!("❌ Error sending to Slack ({}): {}", webhook_name, err_msg);
-                let static_error_msg: &'static str = Box::leak(error_msg.into_boxed_str());
-                spinner.update(spinners::Dots, static_error_msg, Some(Color::Red));
-                failure_count += 1;
-            }
-        }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await;
+
+    failures.sort_by_key(|(position, _, _)| *position);
+    failures
+}
+
+/// Builds the facts of the Teams card FactSet
+///
+/// # Arguments
+///
+/// * `names` - Facts to show, in order ("date", "source_file", "language", "model")
+/// * `date` - Formatted date of the run
+/// * `metadata` - Run metadata providing the other values
+///
+/// # Returns
+///
+/// The FactSet `facts` entries; unknown names are skipped with a warning
+fn teams_facts(names: &[String], date: &str, metadata: &RunMetadata) -> Vec<serde_json::Value> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let (title, value) = match name.as_str() {
+                "date" => ("Date", date.to_string()),
+                "source_file" => ("Source file", metadata.source_file.clone()),
+                "language" => ("Language", metadata.language_code.clone()),
+                "model" => ("Model", metadata.model_id.clone()),
+                _ => {
+                    report(format!("⚠️ Unknown Teams fact '{}', skipping it.", name));
+                    return None;
+                }
+            };
+            Some(json!({ "title": title, "value": value }))
+        })
+        .collect()
+}
+
+/// Largest Teams card payload in bytes; Teams rejects cards above about 28 KB
+const TEAMS_CARD_MAX_BYTES: usize = 28_000;
+
+/// Builds the Teams adaptive card payloads for a summary
+///
+/// # Arguments
+///
+/// * `title` - Title of the card
+/// * `header_items` - Date header and/or FactSet shown between the title and the summary
+/// * `icon` - Icon shown next to the title
+/// * `summarized_text` - The summary
+/// * `max_bytes` - Largest serialized payload Teams accepts
+///
+/// # Returns
+///
+/// A single card when the summary fits. Otherwise the summary is split at line breaks
+/// (then spaces) across as many cards as needed, titled "<title> (1/N)", "(2/N)", ...;
+/// only the first card has the header items. Nothing of the summary is left out.
+pub fn teams_card_payloads(
+    title: &str,
+    header_items: &[serde_json::Value],
+    icon: &TeamsIconSettings,
+    summarized_text: &str,
+    max_bytes: usize,
+) -> Vec<serde_json::Value> {
+    let card = |title: &str, header_items: &[serde_json::Value], text: &str| {
+        // Card body: title row, metadata, then the summary
+        let mut body = vec![json!({
+            "type": "ColumnSet",
+            "columns": [
+                {
+                    "type": "Column",
+                    "items": [
+                        {
+                            "type": "Icon",
+                            "name": icon.name,
+                            "size": icon.size,
+                            "style": icon.style,
+                            "color": icon.color
+                        },
+                    ],
+                    "width": "auto"
+                },
+                {
+                    "type": "Column",
+                    "spacing": "medium",
+                    "verticalContentAlignment": "center",
+                    "items": [
+                        {
+                            "type": "TextBlock",
+                            "wrap": true,
+                            "style": "heading",
+                            "weight": "Bolder",
+                            "size": "Large",
+                            "text": title
+                        },
+                    ],
+                    "width": "auto"
+                }
+            ]
+        })];
+        body.extend(header_items.iter().cloned());
+        body.push(json!({
+            "type": "Container",
+            "showBorder": true,
+            "roundedCorners": true,
+            "maxHeight": "400px",
+            "items": [
+                {
+                    "type": "TextBlock",
+                    "maxLines": 100,
+                    "wrap": true,
+                    "text": text
+                }
+            ]
+        }));
+
+        // Create the adaptive card payload
+        json!({
+            "type":"message",
+            "attachments":[
+               {
+                  "contentType":"application/vnd.microsoft.card.adaptive",
+                  "contentUrl":null,
+                  "content":{
+                     "$schema":"http://adaptivecards.io/schemas/adaptive-card.json",
+                     "type":"AdaptiveCard",
+                     "version":"1.5",
+                     "msteams": {
+                        "width": "Full"
+                      },
+                     "body": body
+                  }
+               }
+            ]
+        })
+    };
+
+    let single = card(title, header_items, summarized_text);
+    if single.to_string().len() <= max_bytes {
+        return vec![single];
     }
-    
-    // Update the spinner with the final result
-    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-        if failure_count == 0 && success_count > 0 {
-            let message = format!("Summary sent to {} Slack webhooks", success_count);
-            let static_message: &'static str = Box::leak(message.into_boxed_str());
-            spinner.success(static_message);
-        } else if failure_count > 0 && success_count > 0 {
-            let message = format!("Sent to {} Slack webhooks, failed to send to {} webhooks", success_count, failure_count);
-            let static_message: &'static str = Box::leak(message.into_boxed_str());
-            spinner.stop_and_persist("⚠️", static_message);
-        } else {
-            spinner.stop_and_persist("❌", "Failed to send summary to any Slack webhooks!");
+
+    // JSON escaping makes the text larger than its byte count, so shrink the chunks
+    // until every card fits
+    let overhead = card(&format!("{} (99/99)", title), header_items, "").to_string().len();
+    let mut chunk_bytes = max_bytes.saturating_sub(overhead).max(1);
+    loop {
+        let chunks = summarize::split_into_chunks(summarized_text, chunk_bytes);
+        let cards: Vec<serde_json::Value> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let part_title = format!("{} ({}/{})", title, index + 1, chunks.len());
+                let part_header = if index == 0 { header_items } else { &[] };
+                card(&part_title, part_header, chunk)
+            })
+            .collect();
+        if chunk_bytes <= 1 || cards.iter().all(|card| card.to_string().len() <= max_bytes) {
+            return cards;
         }
-        SPINNER_STOPPED.store(true, Ordering::SeqCst);
+        chunk_bytes = chunk_bytes * 9 / 10;
     }
-    
-    Ok(())
 }
 
 /// Sends a summary notification to one or more Microsoft Teams webhooks
 ///
 /// # Arguments
 ///
-/// * `settings` - Application configuration containing the Teams webhook URLs
+/// * `settings` - Application settings containing the Teams webhook URLs
+/// * `client` - HTTP client used for the webhook requests
 /// * `spinner` - Progress spinner to update during the process
 /// * `summarized_text` - The text content to send to Teams
 /// * `user_input` - Title for the Teams card
+/// * `metadata` - Run metadata shown as a FactSet when `teams.show_facts` is set
 /// * `success_message` - Message to display on successful delivery
 /// * `webhook_indices` - Indices of the selected webhooks to use
 ///
 /// # Returns
 ///
-/// A Result indicating success or an error
+/// A Result containing the number of webhooks the summary was delivered to, or an error
 ///
 /// # Spinner Management
 ///
-/// This function checks the `SPINNER_STOPPED` flag before stopping the spinner
-/// and updates the flag after stopping it. It uses `spinner.update()` for progress
-/// updates and only stops the spinner at the end of processing.
+/// Progress is reported with `update_spinner()`, and the spinner is only stopped
+/// (via `finish_spinner()` / `persist_spinner()`) at the end of processing.
 ///
 /// # Webhook Processing
 ///
 /// Retrieves the Teams webhooks from settings, creates an adaptive card with the summary content,
 /// and sends the card to each selected Teams webhook endpoint. Supports both legacy
-/// single webhook configuration and multiple webhook configuration.
+/// single webhook configuration and multiple webhook configuration. A summary too large
+/// for one card is sent as several cards in order (see `teams_card_payloads()`).
+#[allow(clippy::too_many_arguments)]
 pub async fn send_teams_notification(
-    settings: &Config,
+    settings: &Settings,
+    client: &ReqwestClient,
     spinner: &mut Spinner,
     summarized_text: &str,
     user_input: &str,
+    metadata: &RunMetadata,
     success_message: &str,
     webhook_indices: &[usize],
-) -> Result<()> {
-    let client = ReqwestClient::new();
-    
+) -> Result<usize> {
     // Get current date and format it
     let current_date = chrono::Local::now();
     let formatted_date = current_date.format("%m-%d-%Y %I:%M:%S %p").to_string();
@@ -434,269 +2326,124 @@ pub async fn send_teams_notification(
     let tz_name = tz.find_current_local_time_type()
         .expect("Could not find local timezone type")
         .time_zone_designation();
-    let date_header = format!("Date: {} {}", formatted_date, tz_name);
+    let date = format!("{} {}", formatted_date, tz_name);
 
-    // Get icon settings from config with defaults
-    let icon_name = settings
-        .get_string("teams.icon.name")
-        .unwrap_or_else(|_| "Flash".to_string());
-    
-    let icon_size = settings
-        .get_string("teams.icon.size")
-        .unwrap_or_else(|_| "Large".to_string());
-    
-    let icon_style = settings
-        .get_string("teams.icon.style")
-        .unwrap_or_else(|_| "Filled".to_string());
-    
-    let icon_color = settings
-        .get_string("teams.icon.color")
-        .unwrap_or_else(|_| "Accent".to_string());
-
-    // Create the adaptive card payload
-    let text = format!("{}", summarized_text);
-    let payload = json!({
-        "type":"message",
-        "attachments":[
-           {
-              "contentType":"application/vnd.microsoft.card.adaptive",
-              "contentUrl":null,
-              "content":{
-                 "$schema":"http://adaptivecards.io/schemas/adaptive-card.json",
-                 "type":"AdaptiveCard",
-                 "version":"1.5",
-                 "msteams": {
-                    "width": "Full"
-                  },
-                 "body":[
-                    {
-                        "type": "ColumnSet",
-                        "columns": [
-                            {
-                                "type": "Column",
-                                "items": [
-                                    {
-                                        "type": "Icon",
-                                        "name": icon_name,
-                                        "size": icon_size,
-                                        "style": icon_style,
-                                        "color": icon_color
-                                    },
-                                ],
-                                "width": "auto"
-                            },
-                            {
-                                "type": "Column",
-                                "spacing": "medium",
-                                "verticalContentAlignment": "center",
-                                "items": [
-                                    {
-                                        "type": "TextBlock",
-                                        "wrap": true,
-                                        "style": "heading",
-                                        "weight": "Bolder",
-                                        "size": "Large",
-                                        "text": user_input
-                                    },
-                                ],
-                                "width": "auto"
-                            }
-                        ]
-                    },
-                    {
-                        "type": "TextBlock",
-                        "wrap": true,
-                        "style": "heading",
-                        "weight": "Bolder",
-                        "size": "Medium",
-                        "text": date_header
-                    },
-                    {
-                        "type": "Container",
-                        "showBorder": true,
-                        "roundedCorners": true,
-                        "maxHeight": "400px",
-                        "items": [
-                            {
-                                "type": "TextBlock",
-                                "maxLines": 100,
-                                "wrap": true,
-                                "text": text
-                            }
-                        ]
-                    }
-                 ]
-              }
-           }
-        ]
-    });
+    // Icon settings (defaults are filled in by `TeamsIconSettings`)
+    let icon = &settings.teams.icon;
+
+    // Metadata between the title and the summary: a date header and/or a FactSet
+    let mut header_items = Vec::new();
+    if settings.teams.show_date_header {
+        header_items.push(json!({
+            "type": "TextBlock",
+            "wrap": true,
+            "style": "heading",
+            "weight": "Bolder",
+            "size": "Medium",
+            "text": format!("Date: {}", date)
+        }));
+    }
+    if settings.teams.show_facts {
+        let facts = teams_facts(&settings.teams.facts, &date, metadata);
+        if !facts.is_empty() {
+            header_items.push(json!({
+                "type": "FactSet",
+                "facts": facts
+            }));
+        }
+    }
+
+    // Long summaries are split across several cards, so none exceeds the Teams size limit
+    let payloads = teams_card_payloads(user_input, &header_items, icon, summarized_text, TEAMS_CARD_MAX_BYTES);
+    if payloads.len() > 1 {
+        report(format!("✂️ The summary is too large for one Teams card; sending it as {} cards.", payloads.len()));
+    }
 
     // Get webhooks from config
-    let webhooks = match settings.get_array("teams.webhooks") {
-        Ok(webhooks) => webhooks,
-        Err(_) => {
+    let webhooks = match &settings.teams.webhooks {
+        Some(webhooks) => webhooks,
+        None => {
             // Try legacy single webhook endpoint for backward compatibility
-            let teams_webhook_endpoint = settings
-                .get_string("teams.webhook_endpoint")
-                .unwrap_or_default();
+            let teams_webhook_endpoint = settings.teams.webhook_endpoint.clone();
                 
             if teams_webhook_endpoint.is_empty() {
-                if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                    spinner.stop_and_persist(
-                        "⚠️",
-                        "Teams webhook endpoint is not configured. Skipping Teams notification.",
-                    );
-                    SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                }
-                println!("Summary:\n{}\n", summarized_text);
-                return Ok(());
+                persist_spinner(spinner, "⚠️", "Teams webhook endpoint is not configured. Skipping Teams notification.");
+                report(format!("Summary:\n{}\n", summarized_text));
+                return Ok(0);
             }
+
+            let teams_webhook_endpoint = match aws_utils::resolve_endpoint(&teams_webhook_endpoint).await {
+                Ok(endpoint) => endpoint,
+                Err(err) => {
+                    report(format!("❌ Error resolving Teams webhook endpoint: {:#}", err));
+                    persist_spinner(spinner, "❌", "Failed to send summary to Teams!");
+                    return Ok(0);
+                }
+            };
             
             // For legacy single webhook, just use it directly
             let message = "Sending to Teams";
-            spinner.update(spinners::Dots, message, Some(Color::White));
+            update_spinner(spinner, message, spinner_color());
             
-            let result = client
-                .post(&teams_webhook_endpoint)
-                .header("Content-Type", "application/json")
-                .json(&payload)
-                .send()
-                .await;
-                
-            match result {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                            spinner.success(success_message);
-                            SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                        }
-                    } else {
-                        let status = response.status();
-                        println!("❌ Error sending summary to Teams: {}", status);
-                        if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                            spinner.stop_and_persist("❌", "Failed to send summary to Teams!");
-                            SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                        }
-                    }
-                }
-                Err(err) => {
-                    let err_msg = err.to_string();
-                    println!("❌ Error sending summary to Teams: {}", err_msg);
-                    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-                        spinner.stop_and_persist("❌", "Failed to send summary to Teams!");
-                        SPINNER_STOPPED.store(true, Ordering::SeqCst);
-                    }
-                }
+            log::debug!("Posting summary to Teams webhook {}", redact_url(&teams_webhook_endpoint));
+            for payload in &payloads {
+                let error = match post_webhook(client, &teams_webhook_endpoint, payload).await {
+                    Ok(status) if status.is_success() => continue,
+                    Ok(status) => status.to_string(),
+                    Err(err) => describe_request_error(err, &teams_webhook_endpoint),
+                };
+                report(format!("❌ Error sending summary to Teams: {}", error));
+                persist_spinner(spinner, "❌", "Failed to send summary to Teams!");
+                return Ok(0);
             }
+            finish_spinner(spinner, success_message);
             
-            return Ok(());
+            return Ok(1);
         }
     };
     
     if webhooks.is_empty() || webhook_indices.is_empty() {
-        if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-            spinner.stop_and_persist(
-                "⚠️",
-                "No Teams webhooks selected. Skipping Teams notification.",
-            );
-            SPINNER_STOPPED.store(true, Ordering::SeqCst);
-        }
-        println!("Summary:\n{}\n", summarized_text);
-        return Ok(());
+        persist_spinner(spinner, "⚠️", "No Teams webhooks selected. Skipping Teams notification.");
+        report(format!("Summary:\n{}\n", summarized_text));
+        return Ok(0);
     }
     
-    // Update the main spinner instead of stopping it
-    let processing_msg = format!("Processing {} Teams webhooks...", webhook_indices.len());
-    let static_processing_msg: &'static str = Box::leak(processing_msg.into_boxed_str());
-    spinner.update(spinners::Dots, static_processing_msg, Some(Color::White));
-    
-    // Send to each selected webhook
-    let mut success_count = 0;
-    let mut failure_count = 0;
-    
-    for &index in webhook_indices {
-        if index >= webhooks.len() {
-            continue;
-        }
-        
-        // Extract webhook details safely
-        let webhook = &webhooks[index];
-        
-        // Use into_table() to get a table view of the config value
-        let webhook_table = match webhook.clone().into_table() {
-            Ok(table) => table,
-            Err(_) => continue,
-        };
-        
-        // Get name and endpoint from the table
-        let webhook_name = webhook_table.get("name")
-            .and_then(|v| v.clone().into_string().ok())
-            .unwrap_or_else(|| format!("Webhook {}", index + 1));
-            
-        let endpoint = match webhook_table.get("endpoint").and_then(|v| v.clone().into_string().ok()) {
-            Some(ep) => ep,
-            None => continue,
-        };
-        
-        if endpoint.is_empty() {
-            continue;
-        }
-        
-        // Update the main spinner with the current webhook
-        let message = format!("Sending to Teams ({})", webhook_name);
-        let static_message: &'static str = Box::leak(message.into_boxed_str());
-        spinner.update(spinners::Dots, static_message, Some(Color::White));
-        
-        let result = client
-            .post(&endpoint)
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await;
-            
-        match result {
-            Ok(response) => {
-                if response.status().is_success() {
-                    success_count += 1;
-                    // Update spinner with success message
-                    let success_msg = format!("Successfully sent to Teams ({})", webhook_name);
-                    let static_success_msg: &'static str = Box::leak(success_msg.into_boxed_str());
-                    spinner.update(spinners::Dots, static_success_msg, Some(Color::Green));
-                } else {
-                    let status = response.status();
-                    // Update spinner with error message
-                    let error_msg = format!("❌ Error sending to Teams ({}): {}", webhook_name, status);
-                    let static_error_msg: &'static str = Box::leak(error_msg.into_boxed_str());
-                    spinner.update(spinners::Dots, static_error_msg, Some(Color::Red));
-                    failure_count += 1;
-                }
-            }
-            Err(err) => {
-                let err_msg = err.to_string();
-                // Update spinner with error message
-                let error_msg = format!("❌ Error sending to Teams ({}): {}", webhook_name, err_msg);
-                let static_error_msg: &'static str = Box::leak(error_msg.into_boxed_str());
-                spinner.update(spinners::Dots, static_error_msg, Some(Color::Red));
-                failure_count += 1;
-            }
+    // Post to the selected webhooks concurrently; the spinner only shows the overall result
+    let selected = selected_webhooks(settings, "teams", webhook_indices);
+    let total = selected.len();
+    update_spinner(spinner, format!("Sending to {} Teams webhooks...", total), spinner_color());
+    // The cards of a split summary go out in order; a webhook that fails gets no more
+    // cards. Webhooks are told apart by position, since several may share a name.
+    let mut failures: Vec<(usize, String, String)> = Vec::new();
+    for payload in &payloads {
+        let (positions, remaining): (Vec<usize>, Vec<(String, String)>) = selected
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|(position, _)| !failures.iter().any(|(failed, _, _)| failed == position))
+            .unzip();
+        if remaining.is_empty() {
+            break;
         }
+        let failed = broadcast(client, "Teams", remaining, payload, settings.notify.concurrency).await;
+        failures.extend(failed.into_iter().map(|(position, name, err)| (positions[position], name, err)));
     }
-    
+    failures.sort_by_key(|(position, _, _)| *position);
+    for (_, name, err) in &failures {
+        report(format!("❌ Error sending to Teams ({}): {}", name, err));
+    }
+
     // Update the spinner with the final result
-    if !SPINNER_STOPPED.load(Ordering::SeqCst) {
-        if failure_count == 0 && success_count > 0 {
-            let message = format!("{} (Sent to {} webhooks)", success_message, success_count);
-            let static_message: &'static str = Box::leak(message.into_boxed_str());
-            spinner.success(static_message);
-        } else if failure_count > 0 && success_count > 0 {
-            let message = format!("Sent to {} Teams webhooks, failed to send to {} webhooks", success_count, failure_count);
-            let static_message: &'static str = Box::leak(message.into_boxed_str());
-            spinner.stop_and_persist("⚠️", static_message);
-        } else {
-            spinner.stop_and_persist("❌", "Failed to send summary to any Teams webhooks!");
-        }
-        SPINNER_STOPPED.store(true, Ordering::SeqCst);
+    let success_count = total - failures.len();
+    if failures.is_empty() && success_count > 0 {
+        let message = format!("{} (Sent to {}/{} webhooks)", success_message, success_count, total);
+        finish_spinner(spinner, &message);
+    } else if success_count > 0 {
+        let message = format!("Sent to {}/{} Teams webhooks", success_count, total);
+        persist_spinner(spinner, "⚠️", &message);
+    } else {
+        persist_spinner(spinner, "❌", "Failed to send summary to any Teams webhooks!");
     }
-    
-    Ok(())
+
+    Ok(success_count)
 }
\ No newline at end of file
@@ -0,0 +1,1990 @@
+//! # Pipeline Module
+//!
+//! This module runs the core Distill pipeline for a single audio file:
+//! 1. Upload the audio file to S3 with server-side encryption (AES-256)
+//! 2. Transcribe the audio using Amazon Transcribe
+//! 3. Optionally check the transcription confidence, redact the `[redaction]` terms and
+//!    clean up or correct the transcript
+//! 4. Summarize the transcription using Amazon Bedrock
+//! 5. Optionally detect chapters, summarize each speaker, rate the summary and classify
+//!    the sentiment
+//! 6. Optionally delete the S3 object
+//!
+//! With `transcribe_json` set, steps 1 and 2 are replaced by reading a saved Transcribe
+//! result, and nothing is uploaded. With `parallel_transcribe` set, the audio is split into
+//! segments that are uploaded and transcribed concurrently, then stitched back together.
+//! An `s3://bucket/key` input is transcribed where it is: nothing is downloaded or
+//! uploaded, and a single `HeadObject` replaces the bucket region lookup.
+//!
+//! With `fallback_language` set and the language identified automatically, a summary
+//! that fails or comes back nearly empty is taken as a sign of a wrong identification:
+//! the audio is transcribed again in the fallback language and summarized once more.
+//!
+//! ## Stages
+//! `stages` runs part of the pipeline: without `upload`, the audio is expected in S3
+//! already (under the key it would have been uploaded to); without `summarize`, the run
+//! stops after the transcription checks with an empty summary. `upload()` runs the
+//! upload on its own.
+//!
+//! ## Delivery
+//! `deliver()` takes the result to the chosen output (files, terminal, Slack, Teams),
+//! runs the post-processing command and saves the transcript, word timings, metadata
+//! and index entry. Interactive steps (choosing a bucket or webhooks, confirming a
+//! preview) are left to the caller, which passes questions in as callbacks, so the
+//! pipeline can be used from other programs as well as from the CLI. Status messages
+//! are not printed either: they go through `output::report()` to the reporter the
+//! caller set with `output::set_reporter()`, or to the log.
+//!
+//! ## Usage
+//! ```rust,ignore
+//! let config = distill_cli::aws_utils::load_config(None, None).await;
+//! let settings = distill_cli::settings::Settings::load("./config.toml", None, None)?;
+//! let options = PipelineOptions::new("meeting.mp3", "my-bucket");
+//! let result = distill_cli::run(&config, &settings, &options, &mut spinner).await?;
+//! println!("{}", result.summary);
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use aws_config::SdkConfig;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::ServerSideEncryption;
+use aws_sdk_s3::Client;
+use regex::Regex;
+use reqwest::Client as ReqwestClient;
+use serde_json::Value;
+use spinoff::Spinner;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Duration};
+
+use crate::redact::Redactor;
+use crate::settings::{Settings, TranscribeSettings};
+use crate::{aws_utils, compare, index, output, segment, sentiment, summarize, transcribe};
+
+/// Time between Bedrock keep-warm pings
+const KEEP_WARM_INTERVAL: Duration = Duration::from_secs(240);
+
+/// Summaries with fewer words are taken as a sign of a wrongly identified language
+/// (with `fallback_language`)
+const FALLBACK_MIN_SUMMARY_WORDS: usize = 15;
+
+/// Changed lines listed by `DeliveryOptions::previous_summary`
+const COMPARISON_MAX_LINES: usize = 40;
+
+/// The parts of the pipeline to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stages {
+    /// Upload the audio file to S3
+    pub upload: bool,
+    /// Transcribe the uploaded audio
+    pub transcribe: bool,
+    /// Summarize the transcription (and run the optional analysis steps)
+    pub summarize: bool,
+}
+
+impl Default for Stages {
+    fn default() -> Self {
+        Self {
+            upload: true,
+            transcribe: true,
+            summarize: true,
+        }
+    }
+}
+
+/// Options for a single pipeline run
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    /// Path of the audio file to process
+    pub input_audio_file: String,
+    /// S3 bucket the audio file is uploaded to for transcription
+    pub bucket_name: String,
+    /// Language code for transcription (e.g., "en-US")
+    pub language_code: String,
+    /// Delete the uploaded S3 object once the run is complete
+    pub delete_s3_object: bool,
+    /// Leave the file extension out of the S3 object key
+    pub key_strip_extension: bool,
+    /// KMS key uploads are encrypted with (SSE-KMS) instead of AES-256
+    pub kms_key_id: Option<String>,
+    /// Add a presigned link to the uploaded recording to the summary (only when the
+    /// object is kept)
+    pub recording_link: bool,
+    /// Text appended to the file stem in the S3 object key
+    pub s3_key_suffix: Option<String>,
+    /// Folder the audio is uploaded under instead of the bucket root
+    pub s3_key_prefix: Option<String>,
+    /// How the transcript is broken into lines (speaker turns, paragraphs or sentences)
+    pub transcript_format: transcribe::TranscriptFormat,
+    /// Remove stutters and filler words from the transcript before summarizing
+    pub dedupe_transcript: bool,
+    /// Fix obvious transcription errors with an extra Bedrock call before summarizing
+    pub clean_transcript: bool,
+    /// Ping Bedrock periodically while waiting for the transcription
+    pub keep_warm: bool,
+    /// Transcribe each audio channel separately instead of using speaker diarization
+    pub by_channel: bool,
+    /// Split the audio into this many segments and transcribe them concurrently
+    /// (experimental, requires ffmpeg)
+    pub parallel_transcribe: Option<usize>,
+    /// Read the transcription from a saved Transcribe result JSON file instead of
+    /// uploading and transcribing `input_audio_file`
+    pub transcribe_json: Option<PathBuf>,
+    /// The stages to run (all by default)
+    pub stages: Stages,
+    /// Named sections the summary is structured into, in order (empty for free form)
+    pub sections: Vec<String>,
+    /// Condensed summaries of earlier related recordings to keep this summary consistent
+    /// with (see `summarize::CarriedContext`)
+    pub carried_context: Option<String>,
+    /// Reference documents (`--reference`) as a prompt instruction (see
+    /// `summarize::reference_instruction()`)
+    pub references: Option<String>,
+    /// Persona snippet appended to the system message when summarizing
+    pub persona: Option<String>,
+    /// Maximum number of items per bulleted list in the summary
+    pub max_items: Option<usize>,
+    /// Rewrite summaries shorter than this many words
+    pub min_words: Option<usize>,
+    /// Rewrite summaries longer than this many words
+    pub max_words: Option<usize>,
+    /// Append timestamped chapters to the summary
+    pub chapters: bool,
+    /// Append a short summary of each speaker's contributions
+    pub per_speaker_summary: bool,
+    /// Ask the model to rate its own summary
+    pub self_rate: bool,
+    /// Classify the overall sentiment of the transcript
+    pub sentiment: bool,
+    /// Stop before summarizing when the average word confidence is below this value
+    pub min_confidence_fail: Option<f64>,
+    /// Reuse saved summarization progress for the same transcript, and save new progress
+    pub resume_summary: bool,
+    /// Delete saved summarization progress for the same transcript before summarizing
+    pub discard_summary_state: bool,
+    /// Post a one-time "still in progress" message when transcription runs long
+    pub progress_notification: Option<ProgressNotification>,
+    /// Stop before summarizing when an automatically identified language has a lower confidence
+    pub min_language_confidence: Option<f64>,
+    /// Asked whether to continue when the identified language is below
+    /// `min_language_confidence`; without it the run stops with `LowLanguageConfidenceError`
+    pub confirm_language: Option<fn(&transcribe::IdentifiedLanguage) -> bool>,
+    /// Language to transcribe in once more when the summary of an automatically
+    /// identified language fails or is nearly empty
+    pub fallback_language: Option<String>,
+    /// Most speakers told apart by speaker diarization, overriding `transcribe.max_speakers`
+    pub max_speakers: Option<i32>,
+}
+
+/// Where and when to post a "still in progress" message for long transcriptions
+#[derive(Debug, Clone)]
+pub struct ProgressNotification {
+    /// How long transcription may run before the message is posted
+    pub after: Duration,
+    /// HTTP client used for the webhook requests
+    pub client: ReqwestClient,
+    /// Service whose webhooks receive the message ("slack" or "teams")
+    pub service: String,
+    /// Indices of the selected webhooks
+    pub webhook_indices: Vec<usize>,
+}
+
+impl PipelineOptions {
+    /// Creates options for an input file and bucket, using the CLI defaults for everything else
+    ///
+    /// # Arguments
+    ///
+    /// * `input_audio_file` - Path of the audio file to process
+    /// * `bucket_name` - S3 bucket used for transcription
+    ///
+    /// # Returns
+    ///
+    /// Options that transcribe in en-US and delete the S3 object afterwards
+    pub fn new(input_audio_file: impl Into<String>, bucket_name: impl Into<String>) -> Self {
+        Self {
+            input_audio_file: input_audio_file.into(),
+            bucket_name: bucket_name.into(),
+            language_code: "en-US".to_string(),
+            delete_s3_object: true,
+            key_strip_extension: false,
+            kms_key_id: None,
+            recording_link: false,
+            s3_key_suffix: None,
+            s3_key_prefix: None,
+            transcript_format: transcribe::TranscriptFormat::Raw,
+            dedupe_transcript: false,
+            clean_transcript: false,
+            keep_warm: false,
+            by_channel: false,
+            parallel_transcribe: None,
+            transcribe_json: None,
+            stages: Stages::default(),
+            sections: Vec::new(),
+            carried_context: None,
+            references: None,
+            persona: None,
+            max_items: None,
+            min_words: None,
+            max_words: None,
+            chapters: false,
+            per_speaker_summary: false,
+            self_rate: false,
+            sentiment: false,
+            min_confidence_fail: None,
+            resume_summary: false,
+            discard_summary_state: false,
+            progress_notification: None,
+            min_language_confidence: None,
+            confirm_language: None,
+            fallback_language: None,
+            max_speakers: None,
+        }
+    }
+}
+
+/// Outcome of a pipeline run
+#[derive(Debug, Clone)]
+pub struct PipelineResult {
+    /// The summary, or the labeled transcript when summarization failed
+    pub summary: String,
+    /// Whether summarization failed and `summary` holds the transcript instead
+    pub summary_failed: bool,
+    /// Transcript as returned by Amazon Transcribe
+    pub transcript: String,
+    /// Transcript that was summarized (redacted and deduplicated when configured)
+    pub cleaned_transcript: String,
+    /// Raw Amazon Transcribe JSON output, when available
+    pub transcript_json: Option<String>,
+    /// Metadata describing the run
+    pub metadata: output::RunMetadata,
+}
+
+/// What to deliver when the audio was transcribed but summarization failed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BedrockFallback {
+    /// Deliver the labeled transcript to the selected output
+    #[default]
+    Transcript,
+    /// Print the labeled transcript to the terminal, whatever the output type
+    Terminal,
+    /// Deliver nothing
+    None,
+}
+
+/// Asked before a summary is sent to Slack or Teams (`--preview`), with the summary, the
+/// service name and, for Teams, the card title that may be edited; returns whether to send
+pub type ConfirmSend = fn(&str, &str, Option<&mut String>) -> Result<bool>;
+
+/// Options for delivering the result of a pipeline run (see `deliver()`)
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryOptions {
+    /// File name the outputs are written to, without the extension of the output type
+    pub summary_file_name: String,
+    /// Where the summary goes
+    pub output_type: output::OutputType,
+    /// Whether the output stage runs; without it, the summary is saved for a later run
+    pub deliver_output: bool,
+    /// The stages the pipeline ran; a transcription without `summarize` is saved for a later run
+    pub stages: Stages,
+    /// What to deliver when summarization failed
+    pub bedrock_fallback: BedrockFallback,
+    /// Add a table of contents to Markdown and Word files
+    pub toc: bool,
+    /// Start Markdown files with a YAML frontmatter block of the run metadata
+    pub frontmatter: bool,
+    /// Asked before sending to Slack or Teams; without it, sending is always confirmed
+    pub confirm_send: Option<ConfirmSend>,
+    /// Title of the Teams card
+    pub teams_title: String,
+    /// Title of Slack messages
+    pub notify_title: Option<String>,
+    /// Whether Slack messages go through `slack.bot_token` instead of the webhooks
+    pub slack_bot: bool,
+    /// Slack thread to reply in (with `slack_bot`)
+    pub thread_ts: Option<String>,
+    /// Indices of the selected Slack webhooks
+    pub slack_webhook_indices: Vec<usize>,
+    /// Indices of the selected Teams webhooks
+    pub teams_webhook_indices: Vec<usize>,
+    /// HTTP client used for the webhook requests
+    pub webhook_client: ReqwestClient,
+    /// File a summary is appended to when it reached none of the Slack or Teams webhooks
+    pub fallback_file: Option<PathBuf>,
+    /// Save the transcript next to the output (`.trans`)
+    pub save_transcript: bool,
+    /// Save the cleaned transcript instead of the one Transcribe returned
+    pub save_cleaned: bool,
+    /// Apply the `[redaction]` terms to the saved transcript
+    pub redact_transcript_file: bool,
+    /// Save the word timings of the transcript (`.words.json`)
+    pub save_word_timings: bool,
+    /// Path and contents of a previous summary to compare the new one with
+    pub previous_summary: Option<(PathBuf, String)>,
+    /// Save the run metadata (`.meta.json`)
+    pub save_metadata: bool,
+    /// SQLite database the run is stored in
+    pub index: Option<PathBuf>,
+    /// Write a manifest of the files written so far
+    pub manifest: Option<PathBuf>,
+    /// Write a zip archive of the files written so far
+    pub bundle: Option<PathBuf>,
+    /// Command the summary is piped to
+    pub output_command: Option<String>,
+    /// Don't fail when `output_command` exits with a non-zero status
+    pub ignore_output_command_errors: bool,
+}
+
+/// Error returned when the transcription confidence is below `min_confidence_fail`
+#[derive(Debug, Clone, Copy)]
+pub struct LowConfidenceError {
+    /// Average word confidence of the transcription
+    pub confidence: f64,
+    /// Configured minimum confidence
+    pub threshold: f64,
+}
+
+impl fmt::Display for LowConfidenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Average transcription confidence {:.2} is below {:.2}, not summarizing",
+            self.confidence, self.threshold
+        )
+    }
+}
+
+impl std::error::Error for LowConfidenceError {}
+
+/// Error returned when the identified language's confidence is below `min_language_confidence`
+#[derive(Debug, Clone)]
+pub struct LowLanguageConfidenceError {
+    /// The identified language code
+    pub language_code: String,
+    /// Transcribe's confidence in the identification
+    pub confidence: f64,
+    /// Configured minimum confidence
+    pub threshold: f64,
+}
+
+impl fmt::Display for LowLanguageConfidenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Identified language {} has confidence {:.2}, below {:.2}; not summarizing. Set --language-code explicitly to continue",
+            self.language_code, self.confidence, self.threshold
+        )
+    }
+}
+
+impl std::error::Error for LowLanguageConfidenceError {}
+
+/// Runs the pipeline for one audio file
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings loaded from config.toml
+/// * `options` - Options for this run
+/// * `spinner` - Progress spinner to update while the pipeline runs
+///
+/// # Returns
+///
+/// A Result containing the summary, transcripts and run metadata or an error
+///
+/// A failed summarization is not an error: the transcript is returned in its place and
+/// `summary_failed` is set. A transcription below `min_confidence_fail` returns a
+/// `LowConfidenceError` before Bedrock is called. With `fallback_language`, an
+/// automatically identified language whose summary fails or is nearly empty is retried
+/// once in the fallback language, reusing the uploaded audio.
+pub async fn run(
+    config: &SdkConfig,
+    settings: &Settings,
+    options: &PipelineOptions,
+    spinner: &mut Spinner,
+) -> Result<PipelineResult> {
+    // A saved Transcribe result can't be transcribed again
+    let fallback_language = options
+        .fallback_language
+        .as_deref()
+        .filter(|_| options.language_code == transcribe::AUTO_LANGUAGE && options.transcribe_json.is_none());
+
+    let (identified, reason) = match run_attempt(config, settings, options, fallback_language, spinner).await? {
+        Attempt::Done(result) => return Ok(*result),
+        Attempt::Retry { identified, reason } => (identified, reason),
+    };
+    let fallback_language = fallback_language.unwrap_or_default();
+    output::report(format!(
+        "\n🔁 The summary {} after identifying {}; transcribing again in {}.",
+        reason, identified, fallback_language
+    ));
+
+    // The audio is already in S3 from the first attempt
+    let retry_options = PipelineOptions {
+        language_code: fallback_language.to_string(),
+        stages: Stages {
+            upload: false,
+            ..options.stages
+        },
+        ..options.clone()
+    };
+    match run_attempt(config, settings, &retry_options, None, spinner).await? {
+        Attempt::Done(result) => Ok(*result),
+        Attempt::Retry { .. } => unreachable!("the retry has no fallback language"),
+    }
+}
+
+/// Outcome of one pass through the pipeline
+enum Attempt {
+    /// The run is complete
+    Done(Box<PipelineResult>),
+    /// The summary suggests a wrongly identified language; the S3 object was kept
+    Retry {
+        /// The identified language code
+        identified: String,
+        /// What was wrong with the summary, for the message
+        reason: &'static str,
+    },
+}
+
+/// Runs the pipeline once
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings loaded from config.toml
+/// * `options` - Options for this run
+/// * `fallback_language` - Language to retry in when the summary of an identified
+///   language looks wrong
+/// * `spinner` - Progress spinner to update while the pipeline runs
+///
+/// # Returns
+///
+/// A Result containing the finished run, a request to retry, or an error
+async fn run_attempt(
+    config: &SdkConfig,
+    settings: &Settings,
+    options: &PipelineOptions,
+    fallback_language: Option<&str>,
+    spinner: &mut Spinner,
+) -> Result<Attempt> {
+    if !options.stages.transcribe && options.transcribe_json.is_none() {
+        bail!("Without the transcribe stage, a saved Transcribe result (transcribe_json) is needed");
+    }
+
+    let file_path = Path::new(&options.input_audio_file);
+    let file_name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| options.input_audio_file.clone());
+
+    // An s3:// input is transcribed where it is, under its own key
+    let s3_key = match aws_utils::parse_s3_uri(&options.input_audio_file) {
+        Some((_, key)) => key,
+        None => object_key(options, &file_name),
+    };
+
+    // Sensitive terms are replaced before any text is sent to Bedrock
+    let redactor = Redactor::new(&settings.redaction)?;
+
+    let model_id = settings.model.model_id.clone();
+
+    let mut metadata = output::RunMetadata {
+        source_file: options.input_audio_file.clone(),
+        language_code: options.language_code.clone(),
+        model_id: model_id.clone(),
+        created: chrono::Local::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    // Keep the Bedrock connection warm while waiting for the transcription
+    let keep_warm = options
+        .keep_warm
+        .then(|| summarize::KeepWarm::start(config, settings, KEEP_WARM_INTERVAL));
+
+    // Upload and transcribe, or read a saved Transcribe result instead
+    let (transcription, regional_s3_client) = match &options.transcribe_json {
+        Some(path) => {
+            output::update_spinner(spinner, "Reading Transcribe result...", output::spinner_color());
+            (transcribe::load_transcription(path)?, None)
+        }
+        None => {
+            let (transcription, client) =
+                upload_and_transcribe(config, settings, options, &file_name, &s3_key, spinner).await?;
+            (transcription, Some(client))
+        }
+    };
+    let transcribe::Transcription {
+        text: transcription,
+        json: transcription_json,
+        identified_language,
+    } = transcription;
+
+    // Break the transcript into paragraphs or sentences; the result needs word timings
+    let transcription = match (&transcription_json, options.transcript_format) {
+        (_, transcribe::TranscriptFormat::Raw) | (None, _) => transcription,
+        (Some(json), format) => {
+            let result: Value = serde_json::from_str(json).context("Failed to parse the Transcribe result")?;
+            transcribe::format_transcript_as(&result, format)?
+        }
+    };
+
+    // With automatic language identification, continue in the identified language
+    let mut language_code = options.language_code.clone();
+    if let Some(identified) = &identified_language {
+        log::info!(
+            "Identified language {} with confidence {:.2}",
+            identified.code, identified.confidence
+        );
+        output::report(format!("\n🌐 Identified language: {} (confidence {:.2})", identified.code, identified.confidence));
+        language_code = identified.code.clone();
+        metadata.language_code = identified.code.clone();
+        metadata.language_confidence = Some(identified.confidence);
+
+        if let Some(threshold) = options.min_language_confidence {
+            if identified.confidence < threshold {
+                // Clear the spinner so the question isn't drawn over
+                let spinning = output::clear_spinner(spinner);
+                let confirmed = options.confirm_language.is_some_and(|confirm| confirm(identified));
+                if spinning {
+                    output::restart_spinner(spinner, "Continuing...");
+                }
+
+                if !confirmed {
+                    if let (true, Some(client)) = (options.delete_s3_object, &regional_s3_client) {
+                        delete_s3_object(client, &options.bucket_name, &s3_key).await?;
+                    }
+                    return Err(LowLanguageConfidenceError {
+                        language_code: identified.code.clone(),
+                        confidence: identified.confidence,
+                        threshold,
+                    }
+                    .into());
+                }
+            }
+        }
+    }
+
+    // Refuse to summarize a low-quality transcription
+    if let Some(threshold) = options.min_confidence_fail {
+        match transcription_json.as_deref().and_then(transcribe::average_confidence) {
+            Some(confidence) if confidence < threshold => {
+                if let (true, Some(client)) = (options.delete_s3_object, &regional_s3_client) {
+                    delete_s3_object(client, &options.bucket_name, &s3_key).await?;
+                }
+                return Err(LowConfidenceError { confidence, threshold }.into());
+            }
+            Some(confidence) => log::info!("Average transcription confidence: {:.2}", confidence),
+            None => output::report("\n⚠️ Could not determine the transcription confidence, skipping the --min-confidence-fail check."),
+        }
+    }
+
+    // Stop after transcription when summarizing isn't one of the stages
+    if !options.stages.summarize {
+        if let (true, Some(client)) = (options.delete_s3_object, &regional_s3_client) {
+            delete_s3_object(client, &options.bucket_name, &s3_key).await?;
+        }
+        return Ok(Attempt::Done(Box::new(PipelineResult {
+            summary: String::new(),
+            summary_failed: false,
+            cleaned_transcript: transcription.clone(),
+            transcript: transcription,
+            transcript_json: transcription_json,
+            metadata,
+        })));
+    }
+
+    // Redact sensitive terms; `transcription` itself stays raw for the local .trans file
+    let cleaned_transcription = redactor.redact(&transcription);
+    if redactor.is_active() && cleaned_transcription != transcription {
+        output::report("\n🔒 Redacted sensitive terms from the transcript.");
+    }
+
+    // Optionally clean up stutters and filler words before summarizing
+    let cleaned_transcription = if options.dedupe_transcript {
+        transcribe::dedupe_transcript(&cleaned_transcription, &settings.transcribe.filler_words)
+    } else {
+        cleaned_transcription
+    };
+
+    // Optionally have the model fix misrecognized words before summarizing
+    let cleaned_transcription = if options.clean_transcript {
+        output::update_spinner(spinner, "Correcting transcript...", output::spinner_color());
+        match summarize::clean_transcript(config, settings, &cleaned_transcription).await {
+            Ok(corrected) => corrected,
+            Err(err) => {
+                output::report(format!("\n⚠️ Could not correct the transcript, using it as is: {:#}", err));
+                cleaned_transcription
+            }
+        }
+    } else {
+        cleaned_transcription
+    };
+
+    // Summarization follows right away, so the pings are no longer needed
+    drop(keep_warm);
+
+    // Run metadata available to the prompt template as {placeholders}
+    let mut prompt_options = summarize::PromptOptions {
+        variables: [
+            ("filename", file_name.clone()),
+            ("date", chrono::Local::now().format("%Y-%m-%d").to_string()),
+            ("language", language_code.clone()),
+            ("model", model_id),
+        ]
+        .into(),
+        persona: options.persona.clone(),
+        ..Default::default()
+    };
+    if !options.sections.is_empty() {
+        prompt_options.instructions.push(summarize::sections_instruction(&options.sections));
+    }
+    if let Some(references) = options.references.as_deref().filter(|references| !references.is_empty()) {
+        prompt_options.instructions.push(references.to_string());
+    }
+    if let Some(context) = options.carried_context.as_deref().filter(|context| !context.is_empty()) {
+        prompt_options.instructions.push(format!(
+            "For context, here are condensed summaries of earlier related recordings, oldest first. Keep names, decisions and open items consistent with them and point out what changed, but summarize only the current transcript.\n\n{}",
+            context
+        ));
+    }
+    if let Some(max_items) = options.max_items {
+        prompt_options.instructions.push(format!(
+            "Limit every bulleted list, including the action items, to at most {} items. Keep only the most important ones.",
+            max_items
+        ));
+    }
+
+    // Reuse a summary saved by an earlier, interrupted run on the same transcript
+    let summary_state = summarize::SummaryState::new(settings, &prompt_options, &cleaned_transcription);
+    if options.discard_summary_state {
+        summary_state.clear();
+    }
+    let mut summary_state = if options.resume_summary {
+        summary_state.load()
+    } else {
+        summary_state
+    };
+
+    // Summarize the transcription
+    output::update_spinner(spinner, "Summarizing text...", output::spinner_color());
+    let summary_result = match summary_state.summary.clone() {
+        Some(summary) => {
+            output::report("\n♻️ Reusing the saved summary for this transcript.");
+            Ok(summary)
+        }
+        None => {
+            summarize::summarize_text(
+                config,
+                settings,
+                &cleaned_transcription,
+                &prompt_options,
+                options.resume_summary.then_some(&mut summary_state),
+                spinner,
+            )
+            .await
+        }
+    };
+    if options.resume_summary {
+        if let Ok(summary) = &summary_result {
+            summary_state.summary = Some(summary.clone());
+            if let Err(err) = summary_state.save() {
+                output::report(format!("\n⚠️ Could not save the summary state: {:#}", err));
+            }
+        }
+    }
+    let (summarized_text, summary_failed) =
+        match summary_result {
+            // Enforce the item limit even when the model ignores the instruction
+            Ok(summary) => match options.max_items {
+                Some(max_items) => (summarize::limit_list_items(&summary, max_items), false),
+                None => (summary, false),
+            },
+            Err(err) => {
+                // Don't lose the (already paid for) transcript when summarization fails
+                output::report(format!("\n⚠️ Summarization failed: {:#}", err));
+                let transcription = if settings.redaction.apply_to_outputs {
+                    redactor.redact(&transcription)
+                } else {
+                    transcription.clone()
+                };
+                (
+                    format!("(summary unavailable — transcript shown)\n\n{}", transcription),
+                    true,
+                )
+            }
+        };
+
+    // A failed or nearly empty summary of an identified language is retried in the
+    // fallback language; the S3 object is kept for that
+    if let (Some(fallback_language), Some(identified)) = (fallback_language, &identified_language) {
+        let reason = if summary_failed {
+            Some("failed")
+        } else if summarized_text.split_whitespace().count() < FALLBACK_MIN_SUMMARY_WORDS {
+            Some("is nearly empty")
+        } else {
+            None
+        };
+        if let Some(reason) = reason.filter(|_| identified.code != fallback_language) {
+            return Ok(Attempt::Retry {
+                identified: identified.code.clone(),
+                reason,
+            });
+        }
+    }
+
+    // Optionally rewrite the summary until it lands in the word-count band
+    let summarized_text = if (options.min_words.is_some() || options.max_words.is_some()) && !summary_failed {
+        output::update_spinner(spinner, "Checking summary length...", output::spinner_color());
+        match summarize::fit_word_band(
+            config,
+            settings,
+            &cleaned_transcription,
+            summarized_text.clone(),
+            &prompt_options,
+            options.min_words,
+            options.max_words,
+        )
+        .await
+        {
+            Ok(fit) => {
+                let band = if fit.met { "within the band" } else { "outside the band" };
+                output::report(format!(
+                    "\n📏 Summary length: {} words, {} after {} rewrite(s)",
+                    fit.words, band, fit.attempts
+                ));
+                // A rewrite may bring back list items beyond the limit
+                match options.max_items {
+                    Some(max_items) => summarize::limit_list_items(&fit.summary, max_items),
+                    None => fit.summary,
+                }
+            }
+            Err(err) => {
+                output::report(format!("\n⚠️ Could not adjust the summary length: {:#}", err));
+                summarized_text
+            }
+        }
+    } else {
+        summarized_text
+    };
+
+    // The text of a response cut off at max_tokens is kept, but may be incomplete
+    if summarize::take_truncated() && !summary_failed {
+        output::report_above_spinner(
+            spinner,
+            "\n⚠️ The response was cut off at model.max_tokens; the summary may be incomplete.",
+        );
+    }
+
+    // Give the summary exactly the requested sections, in order
+    let summarized_text = if !options.sections.is_empty() && !summary_failed {
+        metadata.sections = summarize::parse_sections(&summarized_text, &options.sections);
+        summarize::render_sections(&metadata.sections)
+    } else {
+        summarized_text
+    };
+
+    // Optionally segment long recordings into chapters
+    let summarized_text = if options.chapters && !summary_failed {
+        output::update_spinner(spinner, "Detecting chapters...", output::spinner_color());
+        let detected = match transcription_json.as_deref() {
+            Some(json) => match transcribe::timed_transcript(json) {
+                Ok(timed) => summarize::detect_chapters(config, settings, &redactor.redact(&timed)).await,
+                Err(err) => Err(err),
+            },
+            None => Err(anyhow!("no timed transcript is available")),
+        };
+        match detected {
+            Ok(detected) if !detected.is_empty() => {
+                format!("{}\n\n{}", summarized_text, summarize::format_chapters(&detected))
+            }
+            Ok(_) => {
+                output::report("\n⚠️ No chapters were detected.");
+                summarized_text
+            }
+            Err(err) => {
+                output::report(format!("\n⚠️ Could not detect chapters: {:#}", err));
+                summarized_text
+            }
+        }
+    } else {
+        summarized_text
+    };
+
+    // Optionally summarize each speaker's contributions separately
+    let summarized_text = if options.per_speaker_summary && !summary_failed {
+        output::update_spinner(spinner, "Summarizing each speaker...", output::spinner_color());
+        match summarize::summarize_speakers(config, settings, &cleaned_transcription).await {
+            Ok(speakers) if !speakers.is_empty() => {
+                format!("{}\n\n{}", summarized_text, summarize::format_speaker_summaries(&speakers))
+            }
+            Ok(_) => {
+                output::report("\n⚠️ No speakers were found in the transcript.");
+                summarized_text
+            }
+            Err(err) => {
+                output::report(format!("\n⚠️ Could not summarize each speaker: {:#}", err));
+                summarized_text
+            }
+        }
+    } else {
+        summarized_text
+    };
+
+    // Optionally have the model rate its own summary
+    if options.self_rate && !summary_failed {
+        output::update_spinner(spinner, "Rating summary...", output::spinner_color());
+        match summarize::rate_summary(config, settings, &cleaned_transcription, &summarized_text).await {
+            Ok(rating) => {
+                log::info!("Summary self-rating: {}/5", rating);
+                output::report(format!("\n⭐ Summary self-rating: {}/5", rating));
+                metadata.self_rating = Some(rating);
+            }
+            Err(err) => output::report(format!("\n⚠️ Could not rate the summary: {:#}", err)),
+        }
+    }
+
+    // Optionally classify the overall sentiment of the conversation
+    let summarized_text = if options.sentiment {
+        output::update_spinner(spinner, "Analyzing sentiment...", output::spinner_color());
+        match sentiment::analyze_sentiment(config, settings, &cleaned_transcription, &language_code).await {
+            Ok(sentiment) => {
+                metadata.sentiment = Some(sentiment.to_string());
+                format!("{}\n\nSentiment: {}", summarized_text, sentiment)
+            }
+            Err(err) => {
+                output::report(format!("\n⚠️ Could not analyze sentiment: {:#}", err));
+                summarized_text
+            }
+        }
+    } else {
+        summarized_text
+    };
+
+    // Optionally link the summary back to the recording it was made from
+    let summarized_text = match (&regional_s3_client, options.recording_link) {
+        (Some(client), true) if !options.delete_s3_object => {
+            let expires_in = Duration::from_secs(settings.aws.presign_expiry_secs);
+            match aws_utils::presigned_object_url(client, &options.bucket_name, &s3_key, expires_in).await {
+                Ok(url) => {
+                    metadata.recording_url = Some(url.clone());
+                    format!("{}\n\nRecording: {}", summarized_text, url)
+                }
+                Err(err) => {
+                    output::report(format!("\n⚠️ Could not create a link to the recording, leaving it out: {:#}", err));
+                    summarized_text
+                }
+            }
+        }
+        (Some(_), true) => {
+            output::report("\n⚠️ The recording is deleted from S3 after the run, so no link is added. Use --delete-s3-object N to keep it.");
+            summarized_text
+        }
+        _ => summarized_text,
+    };
+
+    if let (true, Some(client)) = (options.delete_s3_object, &regional_s3_client) {
+        delete_s3_object(client, &options.bucket_name, &s3_key).await?;
+    }
+
+    // The model may echo or guess a redacted term; optionally scrub the summary too
+    let summarized_text = if settings.redaction.apply_to_outputs {
+        redactor.redact(&summarized_text)
+    } else {
+        summarized_text
+    };
+
+    Ok(Attempt::Done(Box::new(PipelineResult {
+        summary: summarized_text,
+        summary_failed,
+        transcript: transcription,
+        cleaned_transcript: cleaned_transcription,
+        transcript_json: transcription_json,
+        metadata,
+    })))
+}
+
+/// Uploads the audio file to S3 without transcribing it (`--stages upload`)
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `options` - Options for this run (input file, bucket and key settings)
+/// * `spinner` - Progress spinner
+///
+/// # Returns
+///
+/// A Result containing the S3 URI of the uploaded object or an error
+///
+/// The object is uploaded under the same key a full run would use, so a later run
+/// without the upload stage finds it.
+pub async fn upload(config: &SdkConfig, options: &PipelineOptions, spinner: &mut Spinner) -> Result<String> {
+    let file_name = Path::new(&options.input_audio_file)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| options.input_audio_file.clone());
+    let s3_key = object_key(options, &file_name);
+
+    let (_, regional_s3_client) = regional_s3_client(config, options, spinner).await?;
+    let path = local_audio_path(options)?;
+    upload_object(&regional_s3_client, options, &path, &s3_key).await?;
+
+    Ok(format!("s3://{}/{}", options.bucket_name, s3_key))
+}
+
+/// Reads a summary saved by an earlier run, for delivering it without the other stages
+///
+/// # Arguments
+///
+/// * `path` - The saved summary (`--from-summary`)
+/// * `settings` - Application settings loaded from config.toml
+/// * `options` - Options for this run (input file and language code)
+///
+/// # Returns
+///
+/// A Result containing the summary as a pipeline result without a transcript, or an error
+pub fn read_summary(path: &Path, settings: &Settings, options: &PipelineOptions) -> Result<PipelineResult> {
+    Ok(PipelineResult {
+        summary: fs::read_to_string(path).with_context(|| format!("❌ Failed to read summary {}", path.display()))?,
+        summary_failed: false,
+        transcript: String::new(),
+        cleaned_transcript: String::new(),
+        transcript_json: None,
+        metadata: output::RunMetadata {
+            source_file: options.input_audio_file.clone(),
+            language_code: options.language_code.clone(),
+            model_id: settings.model.model_id.clone(),
+            created: chrono::Local::now().to_rfc3339(),
+            ..Default::default()
+        },
+    })
+}
+
+/// Delivers the result of a pipeline run and saves the requested extras
+///
+/// # Arguments
+///
+/// * `settings` - Application settings loaded from config.toml
+/// * `options` - Where the result goes and what else is saved
+/// * `result` - The result of `run()` or `read_summary()`
+/// * `redactor` - Redaction terms for the saved and printed transcripts
+/// * `transcript_stdout` - Where the transcript is written as well (`--transcript-to-stdout`), if anywhere
+/// * `spinner` - Progress spinner, stopped when done
+///
+/// # Returns
+///
+/// A Result containing whether summarization had failed and the transcript was
+/// delivered in its place, or an error
+///
+/// Without the `summarize` stage, the Transcribe result is saved for a later run; without
+/// the output stage, the summary is. Otherwise the summary goes to the output type, the
+/// written file is post-processed, and the transcript, word timings, comparison,
+/// metadata, index entry, manifest, bundle and output command follow.
+pub async fn deliver(
+    settings: &Settings,
+    options: &DeliveryOptions,
+    result: PipelineResult,
+    redactor: &Redactor,
+    transcript_stdout: Option<&mut fs::File>,
+    spinner: &mut Spinner,
+) -> Result<bool> {
+    let PipelineResult {
+        summary: summarized_text,
+        summary_failed,
+        transcript: transcription,
+        cleaned_transcript: cleaned_transcription,
+        transcript_json,
+        mut metadata,
+    } = result;
+    let summary_file_name = &options.summary_file_name;
+
+    // Transcribe without summarize: save the Transcribe result for the summarize stage
+    if options.stages.transcribe && !options.stages.summarize {
+        let json = transcript_json.with_context(|| format!("❌ Transcription did not complete: {}", transcription))?;
+        let json_path = format!("{}.transcribe.json", summary_file_name);
+        fs::write(&json_path, json).with_context(|| format!("❌ Error writing {}", json_path))?;
+        output::record_artifact("transcribe_json", Path::new(&json_path))?;
+        output::finish_spinner(spinner, &format!("Transcribe result saved to {}", json_path));
+        output::report(format!("➡️ Continue with: --stages summarize,output --from-transcribe-json {}", json_path));
+        return Ok(false);
+    }
+
+    // When summarization failed, the fallback decides where the transcript goes
+    let output_type = if summary_failed && options.bedrock_fallback == BedrockFallback::Terminal {
+        output::OutputType::Terminal
+    } else {
+        options.output_type
+    };
+    let skip_output =
+        !options.deliver_output || (summary_failed && options.bedrock_fallback == BedrockFallback::None);
+    if summary_failed && !skip_output {
+        output::report(format!("⚠️ Falling back to the transcript for the {:?} output.", output_type));
+    }
+
+    // Summarize without output: save the summary for the output stage
+    if !options.deliver_output && !summary_failed {
+        let summary_path = format!("{}.summary.txt", summary_file_name);
+        fs::write(&summary_path, &summarized_text).with_context(|| format!("❌ Error writing {}", summary_path))?;
+        output::record_artifact("summary", Path::new(&summary_path))?;
+        output::finish_spinner(spinner, &format!("Summary saved to {}", summary_path));
+        output::report(format!("➡️ Continue with: --stages output --from-summary {}", summary_path));
+    }
+
+    if skip_output {
+        if options.deliver_output {
+            output::report("⚠️ Summary unavailable, no output written (--fallback-on-bedrock-error none).");
+        }
+    } else {
+        deliver_summary(settings, options, output_type, &summarized_text, transcript_json.as_deref(), &metadata, spinner)
+            .await?;
+
+        // Run the format's post-processing command on the written file
+        let post_process = match output_type {
+            output::OutputType::Markdown => Some(&settings.output.markdown),
+            output::OutputType::Word => Some(&settings.output.word),
+            output::OutputType::Text | output::OutputType::SlackSplit | output::OutputType::TeamsSplit => {
+                Some(&settings.output.text)
+            }
+            _ => None,
+        };
+        if let (Some(post_process), Some(ext)) = (post_process, output_type.extension()) {
+            let output_path = format!("{}{}", summary_file_name, ext);
+            if Path::new(&output_path).exists() {
+                output::post_process_output(post_process, Path::new(&output_path))?;
+            }
+        }
+    }
+
+    // Save transcript if requested (as the last operation)
+    if options.save_transcript {
+        let transcription = if options.save_cleaned {
+            &cleaned_transcription
+        } else {
+            &transcription
+        };
+        let redacted;
+        let transcription = if options.redact_transcript_file {
+            redacted = redactor.redact(transcription);
+            &redacted
+        } else {
+            transcription
+        };
+        let trans_file = format!("{}.trans", summary_file_name);
+        let trans_path = Path::new(&trans_file);
+        let mut trans_file = fs::File::create(trans_path)
+            .map_err(|e| anyhow!("❌ Error creating transcript file: {}", e))?;
+
+        trans_file
+            .write_all(transcription.as_bytes())
+            .map_err(|e| anyhow!("❌ Error writing transcript file: {}", e))?;
+        output::record_artifact("transcript", trans_path)?;
+
+        output::report(format!("📝 Full transcript saved to {}", trans_path.display()));
+    }
+
+    if options.save_word_timings {
+        match transcript_json.as_deref() {
+            Some(json) => {
+                let words = transcribe::word_timings(json)?;
+                let words_file = format!("{}.words.json", summary_file_name);
+                fs::write(&words_file, serde_json::to_string_pretty(&words)?)
+                    .with_context(|| format!("❌ Error writing word timings file {}", words_file))?;
+                output::record_artifact("word_timings", Path::new(&words_file))?;
+                output::report(format!("⏱️ Word timings saved to {}", words_file));
+            }
+            None => output::report("⚠️ No Transcribe result to take word timings from; skipping --save-word-timings."),
+        }
+    }
+
+    if let Some(transcript_stdout) = transcript_stdout {
+        let transcription = if settings.redaction.apply_to_outputs {
+            redactor.redact(&transcription)
+        } else {
+            transcription.clone()
+        };
+        transcript_stdout
+            .write_all(transcription.as_bytes())
+            .map_err(|e| anyhow!("❌ Error writing transcript to stdout: {}", e))?;
+    }
+
+    if let Some((path, previous)) = &options.previous_summary {
+        if summary_failed {
+            output::report(format!("⚠️ No summary to compare with {}.", path.display()));
+        } else {
+            let comparison = compare::compare(compare::strip_output_header(previous), &summarized_text);
+            metadata.similarity_to_previous = Some(comparison.similarity);
+            print_comparison(path, &comparison);
+        }
+    }
+
+    if options.save_metadata {
+        output::write_metadata_file(summary_file_name, &metadata)?;
+    }
+
+    if let Some(db_path) = options.index.as_ref().filter(|_| !summary_failed) {
+        let connection = index::open(db_path)?;
+        let id = index::insert_run(&connection, &metadata, &transcription, &summarized_text)?;
+        output::report(format!("🗂️ Stored the run in {} (#{})", db_path.display(), id));
+    }
+
+    if let Some(manifest_path) = &options.manifest {
+        output::write_manifest(manifest_path)?;
+    }
+
+    if let Some(bundle_path) = &options.bundle {
+        output::write_bundle(bundle_path)?;
+    }
+
+    if let Some(command) = options.output_command.as_ref().filter(|_| !skip_output) {
+        let status = output::run_output_command(command, &summarized_text, &metadata)?;
+        if status.success() {
+            output::report(format!("🔌 Output command finished ({})", status));
+        } else if options.ignore_output_command_errors {
+            output::report(format!("⚠️ Output command failed ({}), ignoring.", status));
+        } else {
+            bail!("❌ Output command failed ({})", status);
+        }
+    }
+
+    if summary_failed {
+        if !output::persist_spinner(spinner, "⚠️", "Transcribed, but summarization failed") {
+            output::report("⚠️ Transcribed, but summarization failed");
+        }
+    } else if !output::finish_spinner(spinner, "Done!") {
+        output::report("Done!");
+    }
+
+    Ok(summary_failed)
+}
+
+/// Writes, prints or sends the summary for the output type
+///
+/// # Arguments
+///
+/// * `settings` - Application settings loaded from config.toml
+/// * `options` - Where the result goes
+/// * `output_type` - The output type, after the Bedrock fallback
+/// * `summarized_text` - The summary (or the labeled transcript)
+/// * `transcript_json` - Raw Amazon Transcribe result, needed for subtitles
+/// * `metadata` - Metadata of the run, for Markdown frontmatter and Teams cards
+/// * `spinner` - Progress spinner
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+///
+/// A summary that reached none of the Slack or Teams webhooks is appended to
+/// `fallback_file`, unless sending was declined at the preview.
+async fn deliver_summary(
+    settings: &Settings,
+    options: &DeliveryOptions,
+    output_type: output::OutputType,
+    summarized_text: &str,
+    transcript_json: Option<&str>,
+    metadata: &output::RunMetadata,
+    spinner: &mut Spinner,
+) -> Result<()> {
+    let summary_file_name = &options.summary_file_name;
+    let notify_title = options.notify_title.as_deref();
+    let mut teams_title = options.teams_title.clone();
+
+    match output_type {
+        output::OutputType::Word => {
+            output::write_word_file(summary_file_name, summarized_text, settings, options.toc, spinner)?;
+        }
+        output::OutputType::Text => {
+            output::write_text_file(summary_file_name, summarized_text, spinner)?;
+        }
+        output::OutputType::Terminal => {
+            output::finish_spinner(spinner, "Done!");
+            // A summary streamed to the terminal is not printed a second time
+            match summarize::streamed_reprint(summarize::take_streamed_summary().as_deref(), summarized_text) {
+                summarize::StreamedReprint::Full => {
+                    output::report("");
+                    output::report(format!("Summary:\n{}\n", summarized_text));
+                }
+                summarize::StreamedReprint::Nothing => {}
+                summarize::StreamedReprint::Appended(rest) => output::report(format!("{}\n", rest)),
+                summarize::StreamedReprint::Adjusted => {
+                    output::report("(final summary below, adjusted)\n");
+                    output::report(format!("Summary:\n{}\n", summarized_text));
+                }
+            }
+        }
+        output::OutputType::Ssml => {
+            output::write_ssml_file(summary_file_name, summarized_text, spinner)?;
+        }
+        output::OutputType::Vtt | output::OutputType::Srt => {
+            let json =
+                transcript_json.context("❌ Subtitles need the Amazon Transcribe result, which is not available")?;
+            let words = transcribe::word_timings(json)?;
+            if output_type == output::OutputType::Vtt {
+                output::write_vtt_file(summary_file_name, &words, spinner)?;
+            } else {
+                output::write_srt_file(summary_file_name, &words, spinner)?;
+            }
+            output::report("");
+            output::report(format!("Summary:\n{}\n", summarized_text));
+        }
+        output::OutputType::Markdown => {
+            output::write_markdown_file(
+                summary_file_name,
+                summarized_text,
+                options.frontmatter.then_some(metadata),
+                options.toc,
+                spinner,
+            )?;
+        }
+        output::OutputType::Slack => {
+            let delivered = if options.slack_webhook_indices.is_empty() && !options.slack_bot {
+                output::report("⚠️ No Slack webhooks selected. Displaying summary in terminal instead.");
+                output::report(format!("Summary:\n{}\n", summarized_text));
+                Some(0)
+            } else if !confirm_send(options, spinner, summarized_text, "Slack", None)? {
+                output::report("✋ Summary not sent to Slack.");
+                None
+            } else if options.slack_bot {
+                let delivered = output::send_slack_bot_message(
+                    settings,
+                    &options.webhook_client,
+                    spinner,
+                    summarized_text,
+                    notify_title,
+                    options.thread_ts.as_deref(),
+                )
+                .await?;
+                Some(delivered)
+            } else {
+                let delivered = output::send_slack_notification(
+                    settings,
+                    &options.webhook_client,
+                    spinner,
+                    summarized_text,
+                    notify_title,
+                    &options.slack_webhook_indices,
+                )
+                .await?;
+                Some(delivered)
+            };
+            if let (Some(0), Some(fallback_file)) = (delivered, &options.fallback_file) {
+                output::write_fallback_summary(fallback_file, "Slack", summarized_text)?;
+            }
+        }
+        output::OutputType::SlackSplit => {
+            write_split_file(summary_file_name, summarized_text)?;
+
+            let send_to_slack = options.slack_bot || !options.slack_webhook_indices.is_empty();
+            if send_to_slack && !confirm_send(options, spinner, summarized_text, "Slack", None)? {
+                output::report("✋ Summary not sent to Slack. It was only written to file.");
+            } else if options.slack_bot {
+                output::update_spinner(spinner, "Sending to Slack...", output::spinner_color());
+
+                output::send_slack_bot_message(
+                    settings,
+                    &options.webhook_client,
+                    spinner,
+                    summarized_text,
+                    notify_title,
+                    options.thread_ts.as_deref(),
+                )
+                .await?;
+            } else if send_to_slack {
+                output::update_spinner(spinner, "Sending to Slack...", output::spinner_color());
+
+                output::send_slack_notification(
+                    settings,
+                    &options.webhook_client,
+                    spinner,
+                    summarized_text,
+                    notify_title,
+                    &options.slack_webhook_indices,
+                )
+                .await?;
+            } else {
+                output::report("⚠️ No Slack webhooks selected. Summary was only written to file.");
+            }
+        }
+        output::OutputType::Teams => {
+            let delivered = if options.teams_webhook_indices.is_empty() {
+                output::report("⚠️ No Teams webhooks selected. Displaying summary in terminal instead.");
+                output::report(format!("Summary:\n{}\n", summarized_text));
+                Some(0)
+            } else if !confirm_send(options, spinner, summarized_text, "Teams", Some(&mut teams_title))? {
+                output::report("✋ Summary not sent to Teams.");
+                None
+            } else {
+                let delivered = output::send_teams_notification(
+                    settings,
+                    &options.webhook_client,
+                    spinner,
+                    summarized_text,
+                    &teams_title,
+                    metadata,
+                    "Summary sent to Teams!",
+                    &options.teams_webhook_indices,
+                )
+                .await?;
+                Some(delivered)
+            };
+            if let (Some(0), Some(fallback_file)) = (delivered, &options.fallback_file) {
+                output::write_fallback_summary(fallback_file, "Teams", summarized_text)?;
+            }
+        }
+        output::OutputType::TeamsSplit => {
+            write_split_file(summary_file_name, summarized_text)?;
+
+            if !options.teams_webhook_indices.is_empty()
+                && !confirm_send(options, spinner, summarized_text, "Teams", Some(&mut teams_title))?
+            {
+                output::report("✋ Summary not sent to Teams. It was only written to file.");
+            } else if !options.teams_webhook_indices.is_empty() {
+                output::update_spinner(spinner, "Sending to Teams...", output::spinner_color());
+
+                output::send_teams_notification(
+                    settings,
+                    &options.webhook_client,
+                    spinner,
+                    summarized_text,
+                    &teams_title,
+                    metadata,
+                    "Summary sent to Teams and written to output file!",
+                    &options.teams_webhook_indices,
+                )
+                .await?;
+            } else {
+                output::report("⚠️ No Teams webhooks selected. Summary was only written to file.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the `.txt` file of the Slack and Teams split output types
+fn write_split_file(summary_file_name: &str, summarized_text: &str) -> Result<()> {
+    let outfile = format!("{}.txt", summary_file_name);
+    let output_file_path_txt = Path::new(&outfile);
+    let mut file = fs::File::create(output_file_path_txt).map_err(|e| anyhow!("❌ Error creating file: {}", e))?;
+
+    file.write_all(summarized_text.as_bytes())
+        .map_err(|e| anyhow!("❌ Error creating file: {}", e))?;
+    output::record_artifact("summary", output_file_path_txt)?;
+
+    output::report(format!("\n💾 Summary written to {}", output_file_path_txt.display()));
+    Ok(())
+}
+
+/// Asks before a summary is sent, with the spinner cleared while asking
+///
+/// # Arguments
+///
+/// * `options` - Delivery options holding the optional `confirm_send` question
+/// * `spinner` - Progress spinner, cleared while asking and restarted afterwards
+/// * `summary` - The summary about to be sent
+/// * `service` - Service name shown in the question ("Slack" or "Teams")
+/// * `title` - Card title that may be edited before sending (Teams)
+///
+/// # Returns
+///
+/// A Result containing whether to send the summary or an error
+fn confirm_send(
+    options: &DeliveryOptions,
+    spinner: &mut Spinner,
+    summary: &str,
+    service: &str,
+    title: Option<&mut String>,
+) -> Result<bool> {
+    let Some(confirm) = options.confirm_send else {
+        return Ok(true);
+    };
+
+    // Clear the spinner so the preview and questions aren't drawn over
+    let spinning = output::clear_spinner(spinner);
+    let confirmed = confirm(summary, service, title)?;
+    if spinning {
+        output::restart_spinner(spinner, format!("Sending to {}...", service));
+    }
+    Ok(confirmed)
+}
+
+/// Prints how the new summary differs from the previous one
+///
+/// # Arguments
+///
+/// * `path` - Path of the previous summary
+/// * `comparison` - Result of `compare::compare()`
+///
+/// At most `COMPARISON_MAX_LINES` changed lines are listed.
+fn print_comparison(path: &Path, comparison: &compare::Comparison) {
+    output::report(format!(
+        "🔀 Compared with {}: {:.0}% similar, {} changed line(s)",
+        path.display(),
+        comparison.similarity * 100.0,
+        comparison.changes.len()
+    ));
+    for change in comparison.changes.iter().take(COMPARISON_MAX_LINES) {
+        match change {
+            compare::DiffLine::Removed(line) => output::report(format!("   - {}", line)),
+            compare::DiffLine::Added(line) => output::report(format!("   + {}", line)),
+        }
+    }
+    if comparison.changes.len() > COMPARISON_MAX_LINES {
+        output::report(format!("   … and {} more", comparison.changes.len() - COMPARISON_MAX_LINES));
+    }
+}
+
+/// Computes the S3 object key for the input file
+fn object_key(options: &PipelineOptions, file_name: &str) -> String {
+    aws_utils::s3_object_key(
+        file_name,
+        options.s3_key_prefix.as_deref(),
+        options.key_strip_extension,
+        options.s3_key_suffix.as_deref(),
+    )
+}
+
+/// Creates an SDK configuration and S3 client for the bucket's region
+async fn regional_s3_client(
+    config: &SdkConfig,
+    options: &PipelineOptions,
+    spinner: &mut Spinner,
+) -> Result<(SdkConfig, Client)> {
+    let s3_client = Client::new(config);
+    let region = aws_utils::bucket_region(&s3_client, &options.bucket_name).await?;
+    output::report("");
+
+    let region_message = format!("Using bucket region {}", region);
+    output::update_spinner(spinner, region_message, output::spinner_color());
+
+    let regional_config = aws_utils::with_region(config, region);
+    let regional_s3_client = Client::new(&regional_config);
+    Ok((regional_config, regional_s3_client))
+}
+
+/// Lists the audio files in a directory whose extension is on the allowlist
+///
+/// # Arguments
+///
+/// * `dir` - The directory to look in (not recursively)
+/// * `extensions` - Allowed extensions, e.g. `["mp3", "m4a"]`; a leading dot and case
+///   are ignored
+///
+/// # Returns
+///
+/// A Result containing the matching files sorted by path, or an error if the directory
+/// can't be read
+///
+/// Subdirectories and files with other extensions are skipped with a debug log.
+pub fn audio_files_in(dir: &Path, extensions: &[String]) -> Result<Vec<PathBuf>> {
+    let allowed: Vec<String> = extensions
+        .iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect();
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("❌ Cannot read directory {}", dir.display()))? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if allowed.contains(&extension) {
+            files.push(path);
+        } else {
+            log::debug!("Skipping {}: .{} is not in input.extensions", path.display(), extension);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Whether an input path is a glob pattern (`*` or `?` in the file name)
+pub fn is_glob(input: &str) -> bool {
+    Path::new(input)
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().contains(['*', '?']))
+}
+
+/// Lists the files matching a glob pattern
+///
+/// # Arguments
+///
+/// * `pattern` - A path whose file name may contain `*` (any characters) and `?` (one
+///   character), e.g. `recordings/*.m4a`
+///
+/// # Returns
+///
+/// A Result containing the matching files sorted by path, or an error if the pattern
+/// has wildcards outside the file name or its directory can't be read
+///
+/// Matching is case-sensitive and, unlike a shell, `*` also matches a leading dot.
+pub fn audio_files_matching(pattern: &str) -> Result<Vec<PathBuf>> {
+    let pattern = shellexpand::tilde(pattern).to_string();
+    let pattern = Path::new(&pattern);
+    let name = pattern.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let dir = match pattern.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if dir.to_string_lossy().contains(['*', '?']) {
+        bail!("❌ Wildcards are only supported in the file name: {}", pattern.display());
+    }
+
+    let name_regex = name
+        .split('*')
+        .map(|part| part.split('?').map(regex::escape).collect::<Vec<_>>().join("."))
+        .collect::<Vec<_>>()
+        .join(".*");
+    let name_regex = Regex::new(&format!("^{}$", name_regex))?;
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("❌ Cannot read directory {}", dir.display()))? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .is_some_and(|name| name_regex.is_match(&name.to_string_lossy()));
+        if matches && path.is_file() {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Reads a list of input paths, one per line
+///
+/// # Arguments
+///
+/// * `reader` - Where to read the list from, e.g. stdin for `-i -`
+///
+/// # Returns
+///
+/// A Result containing the paths in order, trimmed and without blank lines, or an
+/// error if the list can't be read
+pub fn read_input_list(reader: impl BufRead) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for line in reader.lines() {
+        let line = line.context("❌ Cannot read the list of input paths")?;
+        let path = line.trim();
+        if !path.is_empty() {
+            paths.push(path.to_string());
+        }
+    }
+    Ok(paths)
+}
+
+/// Expands the `-i` values into the inputs to process
+///
+/// # Arguments
+///
+/// * `values` - The `-i` values, in order
+/// * `extensions` - Extensions of the audio files picked up from a directory
+///
+/// # Returns
+///
+/// A Result containing the inputs and whether they are a batch, or an error if a
+/// directory, pattern or stdin yields no files
+///
+/// `-` reads one path per line from stdin (blank lines are skipped). A directory gives
+/// its audio files and a glob pattern the files it matches; `s3://` URIs and other paths
+/// are kept as they are. Anything but a single plain path is a batch.
+pub fn expand_inputs(values: &[String], extensions: &[String]) -> Result<(Vec<String>, bool)> {
+    let mut inputs = Vec::new();
+    let mut batch = values.len() > 1;
+    for value in values {
+        let files = if value == "-" {
+            let paths = read_input_list(std::io::stdin().lock())?;
+            if paths.is_empty() {
+                bail!("❌ No input paths were read from stdin");
+            }
+            paths
+        } else if value.starts_with("s3://") {
+            inputs.push(value.clone());
+            continue;
+        } else if Path::new(value).is_dir() {
+            let files = audio_files_in(Path::new(value), extensions)?;
+            if files.is_empty() {
+                bail!("❌ {} has no audio files with extension {}", value, extensions.join(", "));
+            }
+            files.iter().map(|file| file.display().to_string()).collect()
+        } else if is_glob(value) {
+            let files = audio_files_matching(value)?;
+            if files.is_empty() {
+                bail!("❌ No files match {}", value);
+            }
+            files.iter().map(|file| file.display().to_string()).collect()
+        } else {
+            inputs.push(value.clone());
+            continue;
+        };
+        inputs.extend(files);
+        batch = true;
+    }
+    Ok((inputs, batch))
+}
+
+/// Output file names for the files of a batch run, one per input file
+///
+/// # Arguments
+///
+/// * `summary_file_name` - The `--summary-file-name` of the run
+/// * `files` - The input files of the batch
+///
+/// # Returns
+///
+/// The summary file name with each input's stem appended, before any extension:
+/// `notes.md` and `standup.m4a` give `notes-standup.md`. Inputs that share a stem
+/// (`a.mp3`, `a.wav`) get their extension as well (`notes-a-mp3.md`), so no two
+/// names are the same.
+pub fn batch_output_names(summary_file_name: &str, files: &[PathBuf]) -> Vec<String> {
+    let summary_path = Path::new(summary_file_name);
+    let base = summary_path.with_extension("");
+    let extension = summary_path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    let stem = |file: &PathBuf| file.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    files
+        .iter()
+        .map(|file| {
+            let shared = files.iter().filter(|other| stem(other) == stem(file)).count() > 1;
+            let suffix = match file.extension().filter(|_| shared) {
+                Some(ext) => format!("{}-{}", stem(file), ext.to_string_lossy()),
+                None => stem(file),
+            };
+            format!("{}-{}{}", base.display(), suffix, extension)
+        })
+        .collect()
+}
+
+/// Resolves the input file to an absolute path, failing if it doesn't exist
+fn local_audio_path(options: &PipelineOptions) -> Result<PathBuf> {
+    // Handle conversion of relative paths to absolute paths
+    let absolute_path = shellexpand::tilde(&options.input_audio_file).to_string();
+    let absolute_path = Path::new(&absolute_path);
+
+    if !absolute_path.exists() {
+        bail!("\n❌ The path {} does not exist.", absolute_path.display());
+    }
+
+    Ok(absolute_path.canonicalize()?)
+}
+
+/// Uploads a file to S3 with server-side encryption and waits until it is readable
+///
+/// Uses SSE-KMS with `options.kms_key_id` when set, AES-256 (SSE-S3) otherwise.
+async fn upload_object(client: &Client, options: &PipelineOptions, path: &Path, key: &str) -> Result<()> {
+    let bucket_name = &options.bucket_name;
+    let body = ByteStream::from_path(path)
+        .await
+        .with_context(|| format!("❌ Error loading file: {}", path.display()))?;
+
+    let request = client.put_object().bucket(bucket_name).key(key).body(body);
+    let request = match &options.kms_key_id {
+        Some(kms_key_id) => request
+            .server_side_encryption(ServerSideEncryption::AwsKms)
+            .ssekms_key_id(kms_key_id),
+        None => request.server_side_encryption(ServerSideEncryption::Aes256),
+    };
+
+    if let Err(err) = request.send().await {
+        let code = err.as_service_error().and_then(|err| err.code()).unwrap_or_default().to_string();
+        if let Some(kms_key_id) = options.kms_key_id.as_ref().filter(|_| code == "AccessDenied" || code.starts_with("KMS.")) {
+            return Err(anyhow!(err)).with_context(|| {
+                format!(
+                    "❌ S3 rejected the upload encrypted with KMS key {} ({}). Check that the key exists in the bucket's region and that your credentials may use it (kms:GenerateDataKey)",
+                    kms_key_id, code
+                )
+            });
+        }
+        return Err(anyhow!(err)).context("❌ Failed to upload to S3");
+    }
+
+    // Make sure the object is visible before Transcribe tries to read it
+    aws_utils::wait_for_object(client, bucket_name, key).await
+}
+
+/// Uploads the audio file to S3 and transcribes it with Amazon Transcribe
+///
+/// An `s3://` input isn't uploaded; it is transcribed in place.
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings, used for progress notifications
+/// * `options` - Options for this run
+/// * `file_name` - File name of the input, used in messages
+/// * `s3_key` - S3 object key to upload to
+/// * `spinner` - Progress spinner
+///
+/// # Returns
+///
+/// A Result containing the transcription and an S3 client for the bucket's region
+async fn upload_and_transcribe(
+    config: &SdkConfig,
+    settings: &Settings,
+    options: &PipelineOptions,
+    file_name: &str,
+    s3_key: &str,
+    spinner: &mut Spinner,
+) -> Result<(transcribe::Transcription, Client)> {
+    let parallel = options.parallel_transcribe.filter(|count| *count > 1);
+    let transcribe_settings = TranscribeSettings {
+        max_speakers: options.max_speakers.or(settings.transcribe.max_speakers),
+        ..settings.transcribe.clone()
+    };
+
+    // An s3:// input is already in place: one HeadObject finds its region and size
+    if aws_utils::parse_s3_uri(&options.input_audio_file).is_some() {
+        if parallel.is_some() {
+            bail!("❌ --parallel-transcribe needs a local audio file, not an s3:// URI");
+        }
+        let object = aws_utils::head_s3_object(config, &options.bucket_name, s3_key).await?;
+        output::report("");
+        output::update_spinner(
+            spinner,
+            format!("Found {} in {} ({:.1} MB)", options.input_audio_file, object.region, object.size as f64 / 1_048_576.0),
+            output::spinner_color(),
+        );
+        let regional_config = aws_utils::with_region(config, object.region);
+        let regional_s3_client = Client::new(&regional_config);
+
+        output::report("");
+        output::update_spinner(spinner, "Transcribing audio...", output::spinner_color());
+        let transcription = transcribe::transcribe_audio(
+            &regional_config,
+            Path::new(&options.input_audio_file),
+            &options.input_audio_file,
+            spinner,
+            &options.language_code,
+            options.by_channel,
+            &transcribe_settings,
+        );
+        let transcription = notify_while_waiting(settings, options, file_name, transcription).await?;
+        return Ok((transcription, regional_s3_client));
+    }
+
+    let (regional_config, regional_s3_client) = regional_s3_client(config, options, spinner).await?;
+    let canonicalized_path = local_audio_path(options)?;
+
+    // In parallel mode the segments are uploaded instead of the whole file
+    if parallel.is_none() && options.stages.upload {
+        upload_object(&regional_s3_client, options, &canonicalized_path, s3_key).await?;
+    } else if parallel.is_none() {
+        // The upload stage was skipped, so the object must be there from an earlier run
+        aws_utils::wait_for_object(&regional_s3_client, &options.bucket_name, s3_key)
+            .await
+            .context("❌ The audio is not in S3 yet. Run the upload stage first.")?;
+    }
+
+    let s3_uri = format!("s3://{}/{}", options.bucket_name, s3_key);
+
+    output::report("");
+    output::update_spinner(spinner, "Transcribing audio...", output::spinner_color());
+
+    // Transcribe the audio
+    let transcription = async {
+        match parallel {
+            Some(count) => {
+                parallel_transcribe(&regional_config, &transcribe_settings, options, &canonicalized_path, s3_key, count, spinner)
+                    .await
+            }
+            None => {
+                transcribe::transcribe_audio(
+                    &regional_config,
+                    Path::new(&options.input_audio_file),
+                    &s3_uri,
+                    spinner,
+                    &options.language_code,
+                    options.by_channel,
+                    &transcribe_settings,
+                )
+                .await
+            }
+        }
+    };
+    let transcription = notify_while_waiting(settings, options, file_name, transcription).await?;
+
+    Ok((transcription, regional_s3_client))
+}
+
+/// Waits for a transcription, posting the progress notification if it takes long
+///
+/// # Arguments
+///
+/// * `settings` - Application settings, used for the webhooks
+/// * `options` - Options for this run (`progress_notification`)
+/// * `file_name` - File name of the input, used in the message
+/// * `transcription` - The running transcription
+///
+/// # Returns
+///
+/// A Result containing the transcription or an error
+async fn notify_while_waiting(
+    settings: &Settings,
+    options: &PipelineOptions,
+    file_name: &str,
+    transcription: impl Future<Output = Result<transcribe::Transcription>>,
+) -> Result<transcribe::Transcription> {
+    let transcription = match &options.progress_notification {
+        Some(progress) => {
+            tokio::pin!(transcription);
+            tokio::select! {
+                result = &mut transcription => result?,
+                _ = sleep(progress.after) => {
+                    // Let people know a long job hasn't died, then keep waiting
+                    let message = format!("Transcription still in progress for {}", file_name);
+                    let delivered = output::send_status_message(
+                        settings,
+                        &progress.client,
+                        &progress.service,
+                        &progress.webhook_indices,
+                        &message,
+                    )
+                    .await;
+                    log::info!("Progress notification delivered to {} webhooks", delivered);
+                    transcription.await?
+                }
+            }
+        }
+        None => transcription.await?,
+    };
+
+    Ok(transcription)
+}
+
+/// Splits the audio into segments and transcribes them concurrently
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration for the bucket's region
+/// * `polling` - Job status polling intervals (`transcribe.poll_*` settings)
+/// * `options` - Pipeline options (bucket, language, channels, S3 object deletion)
+/// * `input` - Path of the audio file
+/// * `s3_key` - S3 key of the whole file; segments are stored next to it
+/// * `count` - Number of segments
+/// * `spinner` - Progress spinner to update
+///
+/// # Returns
+///
+/// A Result containing the stitched transcription or an error
+///
+/// The segments are extracted with ffmpeg into a temporary directory, uploaded as
+/// `<key>.partNNN.flac`, and transcribed as separate jobs at the same time. The results
+/// are stitched with `segment::stitch_results()`. The temporary files are always
+/// removed; the segment objects are removed when `delete_s3_object` is set.
+async fn parallel_transcribe(
+    config: &SdkConfig,
+    polling: &TranscribeSettings,
+    options: &PipelineOptions,
+    input: &Path,
+    s3_key: &str,
+    count: usize,
+    spinner: &mut Spinner,
+) -> Result<transcribe::Transcription> {
+    let dir = std::env::temp_dir().join(format!("distill-segments-{}", uuid::Uuid::new_v4().simple()));
+    fs::create_dir_all(&dir).with_context(|| format!("❌ Could not create {}", dir.display()))?;
+
+    output::update_spinner(spinner, format!("Splitting audio into {} segments...", count), output::spinner_color());
+    let split = {
+        let (input, dir) = (input.to_path_buf(), dir.clone());
+        tokio::task::spawn_blocking(move || segment::split_audio(&input, count, &dir)).await?
+    };
+    let segments = match split {
+        Ok(segments) => segments,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(err);
+        }
+    };
+
+    let s3_client = Client::new(config);
+    let keys: Vec<String> = (0..segments.len())
+        .map(|index| format!("{}.part{:03}.flac", s3_key, index))
+        .collect();
+
+    let result = async {
+        output::update_spinner(spinner, format!("Uploading {} segments...", segments.len()), output::spinner_color());
+        for (segment, key) in segments.iter().zip(&keys) {
+            upload_object(&s3_client, options, &segment.path, key).await?;
+        }
+
+        output::update_spinner(
+            spinner,
+            format!("Transcribing {} segments in parallel...", segments.len()),
+            output::spinner_color(),
+        );
+        let mut jobs = JoinSet::new();
+        for (index, (segment, key)) in segments.iter().zip(&keys).enumerate() {
+            let (config, polling) = (config.clone(), polling.clone());
+            let (path, language_code) = (segment.path.clone(), options.language_code.clone());
+            let s3_uri = format!("s3://{}/{}", options.bucket_name, key);
+            let by_channel = options.by_channel;
+            jobs.spawn(async move {
+                let mut quiet = |_: &str| {};
+                let transcription = transcribe::run_transcription_job(
+                    &config, &path, &s3_uri, &language_code, by_channel, &polling, &mut quiet,
+                )
+                .await;
+                (index, transcription)
+            });
+        }
+
+        let mut transcriptions: Vec<Option<transcribe::Transcription>> = vec![None; segments.len()];
+        while let Some(joined) = jobs.join_next().await {
+            let (index, transcription) = joined?;
+            let transcription = transcription.with_context(|| format!("Transcription of segment {} failed", index + 1))?;
+            if transcription.json.is_none() {
+                bail!("❌ Transcription of segment {} failed: {}", index + 1, transcription.text);
+            }
+            transcriptions[index] = Some(transcription);
+        }
+        Ok(transcriptions.into_iter().flatten().collect::<Vec<_>>())
+    }
+    .await;
+
+    let _ = fs::remove_dir_all(&dir);
+    if options.delete_s3_object {
+        for key in &keys {
+            delete_s3_object(&s3_client, &options.bucket_name, key).await?;
+        }
+    }
+    let transcriptions = result?;
+
+    let mut parts = Vec::new();
+    for (segment, transcription) in segments.into_iter().zip(&transcriptions) {
+        let json = transcription.json.as_deref().unwrap_or_default();
+        parts.push((segment, serde_json::from_str(json).context("Failed to parse JSON")?));
+    }
+    let stitched = segment::stitch_results(&parts)?;
+    output::report(format!("\n🧩 Stitched {} transcribed segments", parts.len()));
+
+    Ok(transcribe::Transcription {
+        text: transcribe::format_transcript(&stitched)?,
+        json: Some(serde_json::to_string(&stitched)?),
+        identified_language: transcriptions.into_iter().next().and_then(|first| first.identified_language),
+    })
+}
+
+/// Deletes the uploaded audio file from S3
+async fn delete_s3_object(client: &Client, bucket_name: &str, key: &str) -> Result<()> {
+    client
+        .delete_object()
+        .bucket(bucket_name)
+        .key(key)
+        .send()
+        .await?;
+    Ok(())
+}
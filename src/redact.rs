@@ -0,0 +1,88 @@
+//! # Redact Module
+//!
+//! This module replaces sensitive terms (e.g. internal codenames) in the transcript
+//! before anything is sent to Bedrock. The terms come from the `[redaction]` section
+//! of config.toml:
+//! - `terms` are matched literally, case-insensitively and as whole words
+//! - `patterns` are regular expressions, matched as written
+//!
+//! Every match is replaced with the configured placeholder. Redaction runs locally, so
+//! the original text never reaches the model.
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::settings::RedactionSettings;
+
+/// Replaces the configured terms and patterns with a placeholder
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    regex: Option<Regex>,
+    placeholder: String,
+}
+
+impl Redactor {
+    /// Compiles the terms and patterns of the `[redaction]` section
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The `[redaction]` settings
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the redactor or an error naming an invalid pattern
+    pub fn new(settings: &RedactionSettings) -> Result<Self> {
+        let mut alternatives = Vec::new();
+
+        for term in settings.terms.iter().map(|term| term.trim()).filter(|term| !term.is_empty()) {
+            // Only anchor at word boundaries where the term itself starts or ends with a word
+            let boundary = |c: Option<char>| if c.is_some_and(|c| c.is_alphanumeric() || c == '_') { r"\b" } else { "" };
+            alternatives.push(format!(
+                "(?i:{}{}{})",
+                boundary(term.chars().next()),
+                regex::escape(term),
+                boundary(term.chars().last())
+            ));
+        }
+        for pattern in settings.patterns.iter().filter(|pattern| !pattern.is_empty()) {
+            Regex::new(pattern).with_context(|| format!("❌ Invalid [redaction] pattern {:?}", pattern))?;
+            alternatives.push(format!("(?:{})", pattern));
+        }
+
+        let regex = if alternatives.is_empty() {
+            None
+        } else {
+            Some(
+                RegexBuilder::new(&alternatives.join("|"))
+                    .build()
+                    .context("❌ Could not compile the [redaction] terms")?,
+            )
+        };
+
+        Ok(Self {
+            regex,
+            placeholder: settings.placeholder.clone(),
+        })
+    }
+
+    /// Whether any terms or patterns are configured
+    pub fn is_active(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    /// Redacts a text
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to redact
+    ///
+    /// # Returns
+    ///
+    /// The text with every match replaced by the placeholder
+    pub fn redact(&self, text: &str) -> String {
+        match &self.regex {
+            Some(regex) => regex.replace_all(text, regex::NoExpand(&self.placeholder)).into_owned(),
+            None => text.to_string(),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! # Segment Module
+//!
+//! This module supports the experimental `--parallel-transcribe <N>` mode for long
+//! recordings:
+//! - Splits the audio into N time segments with ffmpeg
+//! - Stitches the Transcribe results of the segments back into one result, in order,
+//!   with the timestamps shifted back to the position in the original recording
+//!
+//! ## Seams
+//! Each segment reaches `OVERLAP_SECS` into its neighbours, so a word spoken across a
+//! seam is complete in at least one segment. When stitching, every segment only keeps the
+//! words starting inside its own time range; the overlap is dropped, so no word appears
+//! twice. Punctuation follows the word before it.
+//!
+//! ## Requirements
+//! `ffmpeg` and `ffprobe` must be on the PATH. Segments are re-encoded as FLAC, which
+//! Transcribe accepts whatever the input format was.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+
+/// Seconds each segment extends into its neighbours
+pub const OVERLAP_SECS: f64 = 2.0;
+
+/// A time segment of the input audio
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Path of the extracted audio
+    pub path: PathBuf,
+    /// Where the extracted audio starts in the original recording, in seconds
+    pub offset: f64,
+    /// Start of the range whose words this segment keeps, in seconds
+    pub keep_from: f64,
+    /// End of the range whose words this segment keeps (exclusive), in seconds
+    pub keep_until: f64,
+}
+
+/// Reads the duration of an audio file with ffprobe
+///
+/// # Arguments
+///
+/// * `path` - Path to the audio file
+///
+/// # Returns
+///
+/// A Result containing the duration in seconds or an error if ffprobe is missing or fails
+pub fn probe_duration(path: &Path) -> Result<f64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .context("❌ Could not run ffprobe. Install ffmpeg, or run without --parallel-transcribe.")?;
+    if !output.status.success() {
+        bail!(
+            "❌ ffprobe failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("❌ ffprobe did not report a duration for {}", path.display()))
+}
+
+/// Plans the time ranges of the segments
+///
+/// # Arguments
+///
+/// * `duration` - Length of the recording in seconds
+/// * `count` - Number of segments
+/// * `dir` - Directory the segment files will be written to
+///
+/// # Returns
+///
+/// One segment per equal share of the recording, each extended by `OVERLAP_SECS` on both
+/// sides (clipped to the recording)
+pub fn plan_segments(duration: f64, count: usize, dir: &Path) -> Vec<Segment> {
+    let count = count.max(1);
+    let length = duration / count as f64;
+
+    (0..count)
+        .map(|index| {
+            let keep_from = index as f64 * length;
+            let offset = (keep_from - OVERLAP_SECS).max(0.0);
+            Segment {
+                path: dir.join(format!("segment_{:03}.flac", index)),
+                offset,
+                keep_from: if index == 0 { 0.0 } else { keep_from },
+                keep_until: if index + 1 == count { f64::INFINITY } else { keep_from + length },
+            }
+        })
+        .collect()
+}
+
+/// Splits an audio file into segments with ffmpeg
+///
+/// # Arguments
+///
+/// * `input` - Path to the audio file
+/// * `count` - Number of segments
+/// * `dir` - Directory to write the segment files to (must exist)
+///
+/// # Returns
+///
+/// A Result containing the segments, in order, or an error if ffmpeg is missing or fails
+pub fn split_audio(input: &Path, count: usize, dir: &Path) -> Result<Vec<Segment>> {
+    let duration = probe_duration(input)?;
+    let segments = plan_segments(duration, count, dir);
+
+    for segment in &segments {
+        let length = (segment.keep_until + OVERLAP_SECS).min(duration) - segment.offset;
+        let status = Command::new("ffmpeg")
+            .args(["-v", "error", "-y", "-ss", &format!("{:.3}", segment.offset), "-i"])
+            .arg(input)
+            .args(["-t", &format!("{:.3}", length), "-vn", "-c:a", "flac"])
+            .arg(&segment.path)
+            .status()
+            .context("❌ Could not run ffmpeg. Install ffmpeg, or run without --parallel-transcribe.")?;
+        if !status.success() {
+            bail!("❌ ffmpeg failed to extract {} ({})", segment.path.display(), status);
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Stitches the Transcribe results of consecutive segments into one result
+///
+/// # Arguments
+///
+/// * `parts` - Each segment with its parsed Transcribe result JSON, in order
+///
+/// # Returns
+///
+/// A Result containing a Transcribe-shaped result with the timestamps of the original
+/// recording, or an error if a result has no items
+///
+/// The stitched result has the `items` of all segments and, for channel-identified
+/// results, merged `channel_labels`. The language identification of the first segment is
+/// kept. Speaker labels are assigned by Transcribe per segment, so `spk_0` in one segment
+/// isn't necessarily `spk_0` in the next.
+pub fn stitch_results(parts: &[(Segment, Value)]) -> Result<Value> {
+    let mut items = Vec::new();
+    let mut channels: Vec<(String, Vec<Value>)> = Vec::new();
+
+    for (segment, result) in parts {
+        let segment_items = result["results"]["items"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Missing 'results.items' data in {}", segment.path.display()))?;
+        items.extend(shift_items(segment_items, segment)?);
+
+        for channel in result["results"]["channel_labels"]["channels"].as_array().into_iter().flatten() {
+            let label = channel["channel_label"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing 'channel_label' data"))?;
+            let shifted = shift_items(channel["items"].as_array().map_or(&[][..], Vec::as_slice), segment)?;
+            match channels.iter_mut().find(|(existing, _)| existing == label) {
+                Some((_, channel_items)) => channel_items.extend(shifted),
+                None => channels.push((label.to_string(), shifted)),
+            }
+        }
+    }
+
+    let transcript = items
+        .iter()
+        .filter_map(|item| Some((item["type"].as_str()?, item["alternatives"][0]["content"].as_str()?)))
+        .fold(String::new(), |mut text, (kind, content)| {
+            if kind == "pronunciation" && !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(content);
+            text
+        });
+
+    let mut results = json!({
+        "transcripts": [{ "transcript": transcript }],
+        "items": items,
+    });
+    if !channels.is_empty() {
+        results["channel_labels"] = json!({
+            "number_of_channels": channels.len(),
+            "channels": channels
+                .into_iter()
+                .map(|(label, items)| json!({ "channel_label": label, "items": items }))
+                .collect::<Vec<_>>(),
+        });
+    }
+    if let Some((_, first)) = parts.first() {
+        if let Some(languages) = first["results"].get("language_identification") {
+            results["language_identification"] = languages.clone();
+        }
+    }
+
+    Ok(json!({ "status": "COMPLETED", "results": results }))
+}
+
+/// Keeps the items of a segment that start inside its range, shifted to the original timeline
+fn shift_items(items: &[Value], segment: &Segment) -> Result<Vec<Value>> {
+    let mut kept = Vec::new();
+    let mut keeping = false;
+
+    for item in items {
+        match item["type"].as_str() {
+            Some("pronunciation") => {
+                let start = time_field(item, "start_time")? + segment.offset;
+                keeping = start >= segment.keep_from && start < segment.keep_until;
+                if keeping {
+                    let mut item = item.clone();
+                    item["start_time"] = json!(format!("{:.3}", start));
+                    if item.get("end_time").is_some() {
+                        let end = time_field(&item, "end_time")? + segment.offset;
+                        item["end_time"] = json!(format!("{:.3}", end));
+                    }
+                    kept.push(item);
+                }
+            }
+            // Punctuation belongs to the word before it
+            Some("punctuation") if keeping => kept.push(item.clone()),
+            _ => {}
+        }
+    }
+
+    Ok(kept)
+}
+
+/// Parses a Transcribe time field (a string of seconds)
+fn time_field(item: &Value, field: &str) -> Result<f64> {
+    item[field]
+        .as_str()
+        .and_then(|time| time.parse().ok())
+        .ok_or_else(|| anyhow!("Missing '{}' data", field))
+}
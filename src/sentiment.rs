@@ -0,0 +1,169 @@
+//! # Sentiment Module
+//!
+//! This module classifies the overall sentiment of a transcript as Positive, Neutral,
+//! Negative or Mixed, using one of two backends:
+//! - `comprehend`: Amazon Comprehend's `DetectSentiment`, run over chunks of the
+//!   transcript (Comprehend accepts at most 5000 bytes per request) with the
+//!   per-chunk scores averaged, weighted by chunk length
+//! - `bedrock`: asks the configured Bedrock model for a one-word classification
+//!
+//! ## Configuration
+//! The backend is selected with `sentiment.backend` in config.toml (default: `bedrock`).
+
+use std::fmt;
+
+use anyhow::{anyhow, bail, Error};
+use aws_config::SdkConfig;
+use aws_sdk_comprehend::types::LanguageCode as ComprehendLanguageCode;
+use crate::settings::Settings;
+use crate::summarize;
+
+/// Maximum number of UTF-8 bytes Comprehend accepts in one DetectSentiment request
+const COMPREHEND_MAX_BYTES: usize = 5000;
+
+/// Upper bound on the response length for the Bedrock classification
+const SENTIMENT_MAX_TOKENS: i64 = 10;
+
+/// Languages supported by Comprehend's DetectSentiment
+const COMPREHEND_LANGUAGES: &[&str] = &["ar", "de", "en", "es", "fr", "hi", "it", "ja", "ko", "pt", "zh", "zh-TW"];
+
+/// Overall sentiment of a transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sentiment {
+    Positive,
+    Neutral,
+    Negative,
+    Mixed,
+}
+
+impl fmt::Display for Sentiment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Sentiment::Positive => "Positive",
+            Sentiment::Neutral => "Neutral",
+            Sentiment::Negative => "Negative",
+            Sentiment::Mixed => "Mixed",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Classifies the overall sentiment of a transcript with the configured backend
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings containing `sentiment.backend` and model settings
+/// * `transcript` - The transcript to classify
+/// * `language_code` - Transcription language code (e.g., "en-US"), used by Comprehend
+///
+/// # Returns
+///
+/// A Result containing the overall sentiment or an error
+pub async fn analyze_sentiment(
+    config: &SdkConfig,
+    settings: &Settings,
+    transcript: &str,
+    language_code: &str,
+) -> Result<Sentiment, Error> {
+    match settings.sentiment.backend.as_str() {
+        "comprehend" => comprehend_sentiment(config, transcript, language_code).await,
+        "bedrock" => bedrock_sentiment(config, settings, transcript).await,
+        other => bail!("Unknown sentiment.backend '{}', expected \"comprehend\" or \"bedrock\"", other),
+    }
+}
+
+/// Classifies sentiment with Amazon Comprehend, chunking the transcript to fit its size limit
+async fn comprehend_sentiment(
+    config: &SdkConfig,
+    transcript: &str,
+    language_code: &str,
+) -> Result<Sentiment, Error> {
+    let comprehend_language = comprehend_language(language_code)?;
+    let client = aws_sdk_comprehend::Client::new(config);
+
+    // Sum of scores over all chunks, weighted by chunk length
+    let (mut positive, mut neutral, mut negative, mut mixed) = (0.0, 0.0, 0.0, 0.0);
+    let mut total_weight = 0.0;
+
+    for chunk in summarize::split_into_chunks(transcript, COMPREHEND_MAX_BYTES) {
+        let resp = client
+            .detect_sentiment()
+            .text(chunk)
+            .language_code(comprehend_language.clone())
+            .send()
+            .await?;
+        let Some(score) = resp.sentiment_score() else {
+            continue;
+        };
+
+        let weight = chunk.len() as f64;
+        positive += score.positive().unwrap_or_default() as f64 * weight;
+        neutral += score.neutral().unwrap_or_default() as f64 * weight;
+        negative += score.negative().unwrap_or_default() as f64 * weight;
+        mixed += score.mixed().unwrap_or_default() as f64 * weight;
+        total_weight += weight;
+    }
+
+    if total_weight == 0.0 {
+        bail!("Comprehend returned no sentiment scores");
+    }
+
+    let scores = [
+        (Sentiment::Positive, positive),
+        (Sentiment::Neutral, neutral),
+        (Sentiment::Negative, negative),
+        (Sentiment::Mixed, mixed),
+    ];
+    let (sentiment, _) = scores
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((Sentiment::Neutral, 0.0));
+
+    Ok(sentiment)
+}
+
+/// Maps a Transcribe language code to the matching Comprehend language code
+fn comprehend_language(language_code: &str) -> Result<ComprehendLanguageCode, Error> {
+    let code = match language_code {
+        "zh-TW" => "zh-TW",
+        _ => language_code.split('-').next().unwrap_or(language_code),
+    };
+
+    if !COMPREHEND_LANGUAGES.contains(&code) {
+        bail!(
+            "Comprehend does not support sentiment analysis for {}. Set sentiment.backend = \"bedrock\" instead.",
+            language_code
+        );
+    }
+
+    Ok(ComprehendLanguageCode::from(code))
+}
+
+/// Classifies sentiment by asking the configured Bedrock model
+async fn bedrock_sentiment(
+    config: &SdkConfig,
+    settings: &Settings,
+    transcript: &str,
+) -> Result<Sentiment, Error> {
+    let client = aws_sdk_bedrockruntime::Client::new(config);
+
+    let prompt = format!(
+        "Classify the overall sentiment of the conversation below. Reply with exactly one word: \
+         Positive, Neutral, Negative or Mixed.\n\n<transcript>\n{transcript}\n</transcript>"
+    );
+
+    let response =
+        summarize::generate_text(&client, settings, &prompt, Some(SENTIMENT_MAX_TOKENS), None).await?;
+    let response = response.trim().to_lowercase();
+
+    [
+        Sentiment::Positive,
+        Sentiment::Neutral,
+        Sentiment::Negative,
+        Sentiment::Mixed,
+    ]
+    .into_iter()
+    .find(|sentiment| response.contains(&sentiment.to_string().to_lowercase()))
+    .ok_or_else(|| anyhow!("Model did not return a sentiment: {}", response))
+}
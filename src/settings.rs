@@ -0,0 +1,691 @@
+//! # Settings Module
+//!
+//! This module describes the structure of config.toml as typed, serde-deserializable
+//! structs, one per section (`aws`, `input`, `transcribe`, `model`, `prompt`, `anthropic`,
+//! `sentiment`, `output`, `notify`, `pricing`, `personas`, `redaction`, `slack`, `teams`,
+//! `profiles`). The settings are loaded once at
+//! startup with `Settings::load()` and passed through the pipeline; every default
+//! lives here, in the `Default` implementations.
+//!
+//! ## Environments
+//! With `--env <NAME>`, `config.<NAME>.toml` is layered on top of config.toml, so each
+//! environment (dev, staging, prod) only needs the settings that differ, such as
+//! webhooks, the bucket or the model.
+//!
+//! ## Profiles
+//! `[profiles.<name>]` tables bundle settings for one kind of recording (prompt, model,
+//! parameters, output type, ...) using the same keys as the top level, e.g.
+//! `[profiles.standup.prompt]`. With `--profile-name <NAME>` the profile's values
+//! override the top-level (and environment) settings key by key.
+//!
+//! ## Strict Checking
+//! config.toml is loaded leniently by default: a misspelled key is simply ignored and
+//! the setting falls back to its default. `check_strict()` instead rejects unknown keys
+//! and values of the wrong type, naming the offending key, so such mistakes surface
+//! before anything is uploaded (`--strict-config`).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use config::{Config, File as ConfigFile};
+use serde::{Deserialize, Serialize};
+
+/// All settings in config.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub aws: AwsSettings,
+    pub input: InputSettings,
+    pub transcribe: TranscribeSettings,
+    pub model: ModelSettings,
+    pub prompt: PromptSettings,
+    pub anthropic: AnthropicSettings,
+    pub sentiment: SentimentSettings,
+    pub output: OutputSettings,
+    pub notify: NotifySettings,
+    pub pricing: PricingSettings,
+    /// Named system-prompt snippets selectable with `--persona`
+    pub personas: BTreeMap<String, String>,
+    pub redaction: RedactionSettings,
+    pub slack: WebhookSettings,
+    pub teams: TeamsSettings,
+    /// Named bundles of overrides selectable with `--profile-name` (matched case-insensitively)
+    pub profiles: BTreeMap<String, toml::Value>,
+}
+
+/// The `[aws]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AwsSettings {
+    /// S3 bucket for audio files; prompts for a bucket when empty
+    pub s3_bucket_name: String,
+    /// Leave the file extension out of the S3 object key
+    pub key_strip_extension: bool,
+    /// Folder audio files are uploaded under (e.g. "distill/"); empty for the bucket root
+    pub s3_key_prefix: String,
+    /// KMS key (id, alias or ARN) uploads are encrypted with; AES-256 (SSE-S3) when unset
+    pub kms_key_id: Option<String>,
+    /// How long `--recording-link` URLs stay valid, in seconds (at most 7 days)
+    pub presign_expiry_secs: u64,
+}
+
+impl Default for AwsSettings {
+    fn default() -> Self {
+        Self {
+            s3_bucket_name: String::new(),
+            key_strip_extension: false,
+            s3_key_prefix: String::new(),
+            kms_key_id: None,
+            presign_expiry_secs: 86_400,
+        }
+    }
+}
+
+/// The `[input]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    /// Extensions of the audio files picked up from a directory input (without the dot)
+    pub extensions: Vec<String>,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            extensions: crate::transcribe::SUPPORTED_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+        }
+    }
+}
+
+/// The `[redaction]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionSettings {
+    /// Terms replaced case-insensitively as whole words (e.g. internal codenames)
+    pub terms: Vec<String>,
+    /// Regular expressions replaced as written
+    pub patterns: Vec<String>,
+    /// Text every match is replaced with
+    pub placeholder: String,
+    /// Also redact the summary and the transcript written to stdout
+    pub apply_to_outputs: bool,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            terms: Vec::new(),
+            patterns: Vec::new(),
+            placeholder: "[REDACTED]".to_string(),
+            apply_to_outputs: false,
+        }
+    }
+}
+
+/// The `[transcribe]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscribeSettings {
+    /// Filler words or phrases stripped by `--dedupe-transcript`
+    pub filler_words: Vec<String>,
+    /// Seconds to wait before the first job status check
+    pub poll_initial_secs: f64,
+    /// Upper bound for the wait between job status checks, in seconds
+    pub poll_max_secs: f64,
+    /// Factor the wait grows by after every check
+    pub poll_multiplier: f64,
+    /// Language transcribed in by `--retry-with-fallback-language` when the summary of
+    /// an automatically identified language fails or is nearly empty
+    pub fallback_language: Option<String>,
+    /// Most speakers told apart by speaker diarization (2-30); `--speakers` overrides it
+    pub max_speakers: Option<i32>,
+    /// Retries of a throttled Transcribe request, with exponential backoff
+    pub max_retries: u32,
+}
+
+impl Default for TranscribeSettings {
+    fn default() -> Self {
+        Self {
+            filler_words: Vec::new(),
+            poll_initial_secs: 5.0,
+            poll_max_secs: 60.0,
+            poll_multiplier: 2.0,
+            fallback_language: None,
+            max_speakers: None,
+            max_retries: crate::aws_utils::DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+impl TranscribeSettings {
+    /// The wait before the next job status check
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - Number of status checks made so far (0 for the first wait)
+    ///
+    /// # Returns
+    ///
+    /// `poll_initial_secs * poll_multiplier^attempt`, capped at `poll_max_secs`
+    ///
+    /// Out-of-range values are clamped: the initial wait to at least 0.1 seconds, the
+    /// multiplier to at least 1.0 and the cap to at least the initial wait.
+    pub fn poll_interval(&self, attempt: u32) -> Duration {
+        let initial = self.poll_initial_secs.max(0.1);
+        let max = self.poll_max_secs.max(initial);
+        let multiplier = self.poll_multiplier.max(1.0);
+        let secs = initial * multiplier.powi(attempt.min(64) as i32);
+        Duration::from_secs_f64(secs.min(max))
+    }
+}
+
+/// The `[model]` section
+///
+/// Unset inference parameters are left out of Converse requests so the model
+/// defaults apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    /// Bedrock API used for summarization ("invoke" or "converse")
+    pub api: String,
+    /// Model family the InvokeModel body is built for ("anthropic", "titan" or "meta")
+    pub provider: String,
+    /// Bedrock model ID
+    pub model_id: String,
+    pub max_tokens: Option<i64>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub top_k: Option<i64>,
+    /// Custom Bedrock endpoint (e.g. a gateway or proxy); `AWS_ENDPOINT_URL_BEDROCK` takes precedence
+    pub endpoint_url: Option<String>,
+    /// Longest transcript, in characters, summarized in one request; longer ones are chunked
+    pub max_input_chars: Option<usize>,
+    /// Retries of a throttled Bedrock request, with exponential backoff
+    pub max_retries: u32,
+    /// Stream the summary with `InvokeModelWithResponseStream`, printing it as it arrives
+    /// for terminal output
+    pub stream: bool,
+}
+
+impl Default for ModelSettings {
+    fn default() -> Self {
+        Self {
+            api: "invoke".to_string(),
+            provider: "anthropic".to_string(),
+            model_id: String::new(),
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            endpoint_url: None,
+            max_input_chars: None,
+            max_retries: crate::aws_utils::DEFAULT_MAX_RETRIES,
+            stream: false,
+        }
+    }
+}
+
+/// The `[prompt]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptSettings {
+    /// Prompt template for summarization
+    pub template: String,
+    /// Fixed sections the summary is structured into, in order (empty for free form)
+    pub sections: Vec<String>,
+    /// Most characters of `--reference` documents included in the prompt
+    /// (default `summarize::REFERENCE_MAX_CHARS`)
+    pub reference_max_chars: Option<usize>,
+}
+
+/// The `[anthropic]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnthropicSettings {
+    pub anthropic_version: String,
+    /// System prompt
+    pub system: String,
+    pub beta: Option<String>,
+}
+
+/// The `[sentiment]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SentimentSettings {
+    /// Backend used by `--sentiment` ("bedrock" or "comprehend")
+    pub backend: String,
+}
+
+impl Default for SentimentSettings {
+    fn default() -> Self {
+        Self {
+            backend: "bedrock".to_string(),
+        }
+    }
+}
+
+/// The `[output]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// Output type used when `--output-type` is not given (e.g. "markdown")
+    pub default_type: Option<String>,
+    /// Infer the output type from the summary file name extension
+    pub infer_type_from_extension: bool,
+    /// Font for Word output
+    pub word_font: Option<String>,
+    /// Font size for Word output, in points
+    pub word_font_size: Option<f64>,
+    /// Line spacing for Word output, as a multiple of single spacing
+    pub word_line_spacing: Option<f64>,
+    /// Progress spinner style (a spinoff spinner name, default: Dots)
+    pub spinner_style: Option<String>,
+    /// Progress spinner color (a color name or "#rrggbb", default: White)
+    pub spinner_color: Option<String>,
+    /// Post-processing of Markdown output (`[output.markdown]`)
+    pub markdown: PostProcessSettings,
+    /// Post-processing of Word output (`[output.word]`)
+    pub word: PostProcessSettings,
+    /// Post-processing of text output, including the text file of the split types (`[output.text]`)
+    pub text: PostProcessSettings,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            default_type: None,
+            infer_type_from_extension: true,
+            word_font: None,
+            word_font_size: None,
+            word_line_spacing: None,
+            spinner_style: None,
+            spinner_color: None,
+            markdown: PostProcessSettings::default(),
+            word: PostProcessSettings::default(),
+            text: PostProcessSettings::default(),
+        }
+    }
+}
+
+/// A command run on an output file after it is written (`[output.<format>]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessSettings {
+    /// Shell command run on the file; `{file}` is replaced by its quoted path, which is
+    /// appended when the command has no placeholder
+    pub post_command: Option<String>,
+    /// Fail the run when the command exits with an error (otherwise only warn)
+    pub fail_on_error: bool,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            post_command: None,
+            fail_on_error: true,
+        }
+    }
+}
+
+/// The `[notify]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifySettings {
+    /// PEM bundle of additional root certificates for webhook requests
+    pub ca_bundle: Option<String>,
+    /// Post a progress message when transcription runs longer than this (0 disables)
+    pub progress_after_secs: u64,
+    /// Title of Slack messages and Teams cards, instead of the fixed Slack prefix and
+    /// the interactive Teams prompt
+    pub title: Option<String>,
+    /// Seconds a webhook request may take before it is abandoned (0 waits indefinitely)
+    pub timeout_secs: u64,
+    /// Maximum number of webhook requests sent at the same time
+    pub concurrency: usize,
+    /// File a Slack or Teams summary is appended to when it reaches no destination
+    pub fallback_file: String,
+}
+
+impl Default for NotifySettings {
+    fn default() -> Self {
+        Self {
+            ca_bundle: None,
+            progress_after_secs: 0,
+            title: None,
+            timeout_secs: 30,
+            concurrency: 4,
+            fallback_file: "undelivered-summaries.md".to_string(),
+        }
+    }
+}
+
+/// The `[pricing]` section, used by `--estimate-only`
+///
+/// Defaults are us-east-1 list prices for Amazon Transcribe (standard batch) and
+/// Claude 3 Sonnet, in USD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PricingSettings {
+    pub transcribe_per_minute: f64,
+    pub bedrock_input_per_1k_tokens: f64,
+    pub bedrock_output_per_1k_tokens: f64,
+}
+
+impl Default for PricingSettings {
+    fn default() -> Self {
+        Self {
+            transcribe_per_minute: 0.024,
+            bedrock_input_per_1k_tokens: 0.003,
+            bedrock_output_per_1k_tokens: 0.015,
+        }
+    }
+}
+
+/// A named webhook in a `webhooks` array
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webhook {
+    pub name: Option<String>,
+    pub endpoint: String,
+}
+
+/// The `[slack]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    /// Legacy single webhook endpoint, used when no `webhooks` array is configured
+    pub webhook_endpoint: String,
+    pub webhooks: Option<Vec<Webhook>>,
+    /// Slack bot token (or `secretsmanager://` reference); when set, summaries are posted
+    /// with `chat.postMessage` instead of the webhooks
+    pub bot_token: Option<String>,
+    /// Channel ID the bot posts to
+    pub channel: Option<String>,
+}
+
+/// The `[teams]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamsSettings {
+    /// Legacy single webhook endpoint, used when no `webhooks` array is configured
+    pub webhook_endpoint: String,
+    pub webhooks: Option<Vec<Webhook>>,
+    pub icon: TeamsIconSettings,
+    /// Show the run metadata as a FactSet on the card
+    pub show_facts: bool,
+    /// Facts shown when `show_facts` is set, in order: "date", "source_file", "language", "model"
+    pub facts: Vec<String>,
+    /// Show the "Date: ..." header above the summary
+    pub show_date_header: bool,
+}
+
+impl Default for TeamsSettings {
+    fn default() -> Self {
+        Self {
+            webhook_endpoint: String::new(),
+            webhooks: None,
+            icon: TeamsIconSettings::default(),
+            show_facts: false,
+            facts: ["date", "source_file", "language", "model"].map(String::from).to_vec(),
+            show_date_header: true,
+        }
+    }
+}
+
+impl TeamsSettings {
+    /// The Slack-style view of the Teams webhooks, for code shared between both services
+    pub fn webhook_settings(&self) -> WebhookSettings {
+        WebhookSettings {
+            webhook_endpoint: self.webhook_endpoint.clone(),
+            webhooks: self.webhooks.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The `[teams.icon]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamsIconSettings {
+    pub name: String,
+    pub size: String,
+    pub style: String,
+    pub color: String,
+}
+
+impl Default for TeamsIconSettings {
+    fn default() -> Self {
+        Self {
+            name: "Flash".to_string(),
+            size: "Large".to_string(),
+            style: "Filled".to_string(),
+            color: "Accent".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings from a config file, optionally overlaid with an environment file
+    /// and a profile
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to config.toml
+    /// * `env` - Optional environment name (e.g. "prod"); `config.<env>.toml` next to
+    ///   `path` is layered on top of the base file
+    /// * `profile` - Optional name of a `[profiles.<name>]` table applied on top of both
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the settings or an error
+    ///
+    /// Values in the environment file and the profile override the base file key by key;
+    /// arrays such as `webhooks` are replaced as a whole. Missing keys fall back to their
+    /// defaults and unknown keys are ignored; use `check_strict()` to reject unknown keys
+    /// as well.
+    pub fn load(path: &str, env: Option<&str>, profile: Option<&str>) -> Result<Self> {
+        let mut builder = Config::builder().add_source(ConfigFile::with_name(path));
+        if let Some(env) = env {
+            let env_path = env_config_path(Path::new(path), env)?;
+            if !env_path.exists() {
+                bail!("❌ Config file for environment '{}' not found: {}", env, env_path.display());
+            }
+            builder = builder.add_source(ConfigFile::from(env_path));
+        }
+
+        let config = builder
+            .build()
+            .with_context(|| format!("Failed to load {}. Make sure it exists in the current directory.", path))?;
+        let config = match profile {
+            Some(profile) => apply_profile(config, profile)?,
+            None => config,
+        };
+
+        config
+            .try_deserialize()
+            .with_context(|| format!("Invalid setting in {}", path))
+    }
+
+    /// Looks up a persona from the `[personas]` table
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Persona name, matched case-insensitively
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the persona's system-prompt snippet or an error listing the
+    /// configured personas
+    pub fn persona(&self, name: &str) -> Result<&str> {
+        self.personas
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, snippet)| snippet.as_str())
+            .ok_or_else(|| {
+                let available = if self.personas.is_empty() {
+                    "none are configured; add a [personas] table to config.toml".to_string()
+                } else {
+                    format!("available: {}", self.personas.keys().cloned().collect::<Vec<_>>().join(", "))
+                };
+                anyhow!("❌ Unknown persona '{}' ({})", name, available)
+            })
+    }
+
+    /// The webhook settings for a service
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - Service name ("teams" or "slack")
+    pub fn webhooks_for(&self, service: &str) -> WebhookSettings {
+        match service {
+            "teams" => self.teams.webhook_settings(),
+            _ => self.slack.clone(),
+        }
+    }
+}
+
+/// Returns the path of the config file for an environment
+///
+/// # Arguments
+///
+/// * `path` - Path to the base config file (e.g. "./config.toml")
+/// * `env` - Environment name (letters, digits, `-` and `_`)
+///
+/// # Returns
+///
+/// A Result containing the environment file path (e.g. "./config.prod.toml") or an
+/// error if the name isn't valid
+pub fn env_config_path(path: &Path, env: &str) -> Result<PathBuf> {
+    if env.is_empty() || !env.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("❌ Invalid environment name '{}': use letters, digits, '-' and '_' only", env);
+    }
+
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("config");
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("toml");
+    Ok(path.with_file_name(format!("{}.{}.{}", stem, env, extension)))
+}
+
+/// Checks a config file against the known settings, rejecting unknown keys and bad types
+///
+/// # Arguments
+///
+/// * `path` - Path to config.toml
+///
+/// # Returns
+///
+/// A Result containing the parsed settings or an error naming the offending key
+///
+/// Type mismatches are reported by the TOML deserializer with the key and line. Unknown
+/// keys are found by serializing the parsed settings back and comparing the key sets:
+/// any key in the file that didn't survive the round trip isn't a known setting.
+pub fn check_strict(path: &Path) -> Result<Settings> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let settings: Settings = toml::from_str(&contents)
+        .map_err(|e| anyhow!("❌ Invalid setting in {}: {}", path.display(), e))?;
+
+    let raw: toml::Value = toml::from_str(&contents)?;
+    let known = toml::Value::try_from(&settings)?;
+
+    let mut unknown = Vec::new();
+    collect_unknown_keys(&raw, &known, "", &mut unknown);
+
+    // Profiles use the top-level keys, so each is checked as settings of its own
+    for (name, profile) in &settings.profiles {
+        let profile_settings: Settings = profile
+            .clone()
+            .try_into()
+            .map_err(|e| anyhow!("❌ Invalid setting in profile '{}' of {}: {}", name, path.display(), e))?;
+        let known = toml::Value::try_from(&profile_settings)?;
+        collect_unknown_keys(profile, &known, &format!("profiles.{}", name), &mut unknown);
+    }
+
+    if !unknown.is_empty() {
+        bail!(
+            "❌ Unknown setting(s) in {}: {}. Check for typos, or run without --strict-config.",
+            path.display(),
+            unknown.join(", ")
+        );
+    }
+
+    Ok(settings)
+}
+
+/// Overrides the loaded settings with the values of a `[profiles.<name>]` table
+///
+/// # Arguments
+///
+/// * `config` - The loaded configuration
+/// * `name` - Profile name, matched case-insensitively
+///
+/// # Returns
+///
+/// A Result containing the configuration with the profile applied, or an error listing
+/// the configured profiles when there is no such profile
+fn apply_profile(config: Config, name: &str) -> Result<Config> {
+    let profiles: BTreeMap<String, config::Value> = config.get("profiles").unwrap_or_default();
+    let Some(profile) = profiles
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, profile)| profile)
+    else {
+        let available = if profiles.is_empty() {
+            "none are configured; add a [profiles.<name>] table to config.toml".to_string()
+        } else {
+            format!("available: {}", profiles.keys().cloned().collect::<Vec<_>>().join(", "))
+        };
+        bail!("❌ Unknown profile '{}' ({})", name, available);
+    };
+    let table = profile
+        .clone()
+        .into_table()
+        .map_err(|_| anyhow!("❌ Profile '{}' must be a table of settings", name))?;
+
+    let mut overrides = Vec::new();
+    flatten_table(table, "", &mut overrides);
+    let mut builder = Config::builder().add_source(config);
+    for (key, value) in overrides {
+        builder = builder.set_override(key, value)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Collects the dotted keys and leaf values of a table; arrays are kept whole
+fn flatten_table(table: config::Map<String, config::Value>, prefix: &str, leaves: &mut Vec<(String, config::Value)>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+        match value.clone().into_table() {
+            Ok(table) => flatten_table(table, &path, leaves),
+            Err(_) => leaves.push((path, value)),
+        }
+    }
+}
+
+/// Collects the dotted paths of keys present in `raw` but missing from `known`
+fn collect_unknown_keys(raw: &toml::Value, known: &toml::Value, prefix: &str, unknown: &mut Vec<String>) {
+    match (raw, known) {
+        (toml::Value::Table(raw), toml::Value::Table(known)) => {
+            for (key, value) in raw {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match known.get(key) {
+                    Some(known_value) => collect_unknown_keys(value, known_value, &path, unknown),
+                    None => unknown.push(path),
+                }
+            }
+        }
+        (toml::Value::Array(raw), toml::Value::Array(known)) => {
+            for (index, (value, known_value)) in raw.iter().zip(known).enumerate() {
+                collect_unknown_keys(value, known_value, &format!("{}[{}]", prefix, index), unknown);
+            }
+        }
+        _ => {}
+    }
+}
@@ -10,10 +10,37 @@
 //! a simple interface for generating summaries from transcription text.
 //!
 //! ## Configuration
-//! The module uses settings from config.toml to configure:
-//! - The prompt template for summarization
+//! The module uses the settings loaded from config.toml by the caller to configure:
+//! - The prompt template for summarization, which may reference run metadata through
+//!   `{filename}`, `{date}`, `{language}` and `{model}` placeholders
 //! - The AI model to use (default: Claude)
 //! - Model parameters like max_tokens, temperature, etc.
+//! - The Bedrock API to call (`model.api`): the model-specific `InvokeModel` body
+//!   (default) or the model-agnostic `Converse` API
+//! - The model family (`model.provider`: `anthropic`, `titan` or `meta`), which decides
+//!   the shape of the `InvokeModel` body and response
+//! - A custom Bedrock endpoint (`model.endpoint_url`, or the `AWS_ENDPOINT_URL_BEDROCK`
+//!   environment variable), e.g. a gateway or proxy; S3 and Transcribe are unaffected
+//!
+//! ## Long Transcripts
+//! Transcripts longer than `model.max_input_chars` (by default 50 characters per
+//! `model.max_tokens`) are summarized map-reduce style: the transcript is split on
+//! paragraph, line or sentence boundaries, each chunk is summarized on its own and the
+//! chunk summaries are combined into the final summary with the configured prompt.
+//!
+//! ## Resumable Summaries
+//! With `--resume-summary`, intermediate results are saved to a small JSON state file in
+//! `.distill-state/`, keyed by a hash of the model, prompt and transcript. A re-run on the
+//! same transcript reuses completed work instead of calling Bedrock again.
+//!
+//! ## Streaming
+//! With `model.stream`, the final summary is requested with
+//! `InvokeModelWithResponseStream` and, for terminal output, printed as it arrives.
+//!
+//! ## Request Dumps
+//! With `--dump-request <PATH>`, every Bedrock request of the run (model id, API and the
+//! full body, including the transcript) is written to a JSON file right before it is
+//! sent, for support tickets and for reproducing model behavior.
 //!
 //! ## Usage
 //! This module is typically used after transcription to condense long transcripts
@@ -21,115 +48,2034 @@
 //!
 //! ## Spinner Thread Management
 //!
-//! This module imports the `SPINNER_STOPPED` flag from the output module to ensure
-//! proper spinner thread management. When updating or stopping spinners, it checks
-//! this flag to prevent stopping a spinner thread more than once, which would cause
-//! a panic.
+//! Spinners are only updated or stopped through the `output` helpers
+//! (`update_spinner()`, `persist_spinner()`, ...). They check the `SPINNER_STOPPED`
+//! flag, so a stopped spinner isn't brought back by a late progress message and its
+//! thread is never stopped twice, which would cause a panic.
 
 use aws_config::SdkConfig;
+use aws_sdk_bedrockruntime::types::{
+    ContentBlock, ConversationRole, ConverseOutput, InferenceConfiguration, Message,
+    ResponseStream, SystemContentBlock,
+};
 use aws_sdk_bedrockruntime::{primitives::Blob, Client};
+use aws_smithy_types::{Document, Number};
 
-use anyhow::{anyhow, Error};
+use anyhow::{anyhow, Context, Error};
 
-use config::{Config, File};
+use crate::settings::Settings;
+use crate::{aws_utils, output, transcribe};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use serde_json::json;
 use spinoff::Spinner;
-use std::str::from_utf8;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Response token cap for the self-rating call
+const SELF_RATING_MAX_TOKENS: i64 = 10;
+
+/// Size of the transcript chunks sent for correction, in bytes
+const CLEAN_CHUNK_BYTES: usize = 6000;
+
+/// Response token cap for a corrected chunk, enough for a full chunk of text
+const CLEAN_MAX_TOKENS: i64 = 4096;
+
+/// Rewrites attempted to bring a summary into the `--min-words`/`--max-words` band
+const WORD_BAND_ATTEMPTS: u32 = 2;
+
+/// Speakers with fewer words than this are noted as a minimal contribution instead of summarized
+const MIN_SPEAKER_WORDS: usize = 30;
+
+/// Content of a requested section the model left out or left empty
+pub const MISSING_SECTION: &str = "None noted";
+
+/// Most characters of earlier summaries carried into the next prompt
+pub const CARRIED_CONTEXT_CHARS: usize = 6000;
+
+/// Most characters kept of a single earlier summary in the carried context
+const CARRIED_SUMMARY_CHARS: usize = 1500;
+
+/// Model families whose InvokeModel body and response are supported (`model.provider`)
+pub const PROVIDERS: [&str; 3] = ["anthropic", "titan", "meta"];
+
+/// Most characters of reference documents (`--reference`) included in the prompt,
+/// unless `prompt.reference_max_chars` says otherwise
+pub const REFERENCE_MAX_CHARS: usize = 20_000;
+
+/// Directory holding resumable summarization state
+const SUMMARY_STATE_DIR: &str = ".distill-state";
+
+/// Characters of a malformed Bedrock response quoted in the error
+const RESPONSE_EXCERPT_CHARS: usize = 300;
+
+/// Temperature sent in the InvokeModel body when `model.temperature` is unset
+pub const DEFAULT_TEMPERATURE: f64 = 0.5;
+
+/// Top P sent in the InvokeModel body when `model.top_p` is unset
+pub const DEFAULT_TOP_P: f64 = 0.9;
+
+/// Default `model.max_input_chars` per token of `model.max_tokens`
+const INPUT_CHARS_PER_MAX_TOKEN: usize = 50;
+
+/// `model.max_tokens` assumed for the default input limit when it is unset
+const DEFAULT_MAX_TOKENS: usize = 2000;
+
+/// Environment variable pointing the Bedrock client at a custom endpoint
+pub const BEDROCK_ENDPOINT_ENV: &str = "AWS_ENDPOINT_URL_BEDROCK";
+
+/// File the Bedrock requests are written to (`--dump-request`)
+static DUMP_REQUEST_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Bedrock requests sent so far in this run, when dumping is enabled
+static DUMPED_REQUESTS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+
+/// Print streamed summaries (`model.stream`) to the terminal as they arrive
+static STREAM_TO_TERMINAL: AtomicBool = AtomicBool::new(false);
+
+/// The last summary printed while it was streamed, so it isn't printed a second time
+static STREAMED_SUMMARY: Mutex<Option<String>> = Mutex::new(None);
+
+/// Whether a model response was cut off at `model.max_tokens` since the last check
+static TRUNCATED: AtomicBool = AtomicBool::new(false);
+
+/// Saved progress of a summarization, used to resume interrupted runs
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SummaryState {
+    /// Hash of the model, prompt and transcript the state belongs to
+    pub key: String,
+    /// Summaries of the transcript chunks completed so far, in order
+    #[serde(default)]
+    pub chunk_summaries: Vec<String>,
+    /// The final summary, once the run got that far
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+impl SummaryState {
+    /// Creates an empty state for a summarization
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - Application settings containing the model and prompt
+    /// * `prompt_options` - Extra instructions for the prompt
+    /// * `transcript` - The transcript being summarized
+    ///
+    /// # Returns
+    ///
+    /// A state keyed by a SHA-256 hash of the model id, API, provider, sampling
+    /// parameters, chunk size, system prompt, prompt template, extra instructions and
+    /// transcript, so a changed prompt or model never reuses old results
+    pub fn new(settings: &Settings, prompt_options: &PromptOptions, transcript: &str) -> Self {
+        let model = &settings.model;
+        let mut hasher = Sha256::new();
+        hasher.update(&model.model_id);
+        hasher.update([0]);
+        hasher.update(&model.api);
+        hasher.update([0]);
+        hasher.update(&model.provider);
+        hasher.update([0]);
+        hasher.update(format!(
+            "{:?} {:?} {:?} {:?} {}",
+            model.temperature,
+            model.top_p,
+            model.top_k,
+            model.max_tokens,
+            max_input_chars(settings)
+        ));
+        hasher.update([0]);
+        hasher.update(&settings.anthropic.system);
+        hasher.update([0]);
+        hasher.update(&settings.prompt.template);
+        for instruction in &prompt_options.instructions {
+            hasher.update([0]);
+            hasher.update(instruction);
+        }
+        if let Some(persona) = &prompt_options.persona {
+            hasher.update([1]);
+            hasher.update(persona);
+        }
+        hasher.update([0]);
+        hasher.update(transcript);
+
+        let key = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Self {
+            key,
+            ..Default::default()
+        }
+    }
+
+    /// Path of the state file for this key
+    fn path(&self) -> PathBuf {
+        Path::new(SUMMARY_STATE_DIR).join(format!("{}.json", self.key))
+    }
+
+    /// Loads previously saved progress for this key, if any
+    ///
+    /// # Returns
+    ///
+    /// The saved state, or the unchanged empty state when nothing usable was saved
+    pub fn load(self) -> Self {
+        fs::read_to_string(self.path())
+            .ok()
+            .and_then(|contents| serde_json::from_str::<SummaryState>(&contents).ok())
+            .filter(|saved| saved.key == self.key)
+            .unwrap_or(self)
+    }
+
+    /// Saves the current progress to the state file
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or an error
+    pub fn save(&self) -> Result<(), Error> {
+        fs::create_dir_all(SUMMARY_STATE_DIR)
+            .with_context(|| format!("Failed to create {}", SUMMARY_STATE_DIR))?;
+        fs::write(self.path(), serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write summary state {}", self.path().display()))
+    }
+
+    /// Removes any saved progress for this key
+    pub fn clear(&self) {
+        let _ = fs::remove_file(self.path());
+    }
+}
+
+/// Per-run additions to the configured prompt
+#[derive(Debug, Default, Clone)]
+pub struct PromptOptions {
+    /// Values substituted for `{name}` placeholders in the prompt template
+    pub variables: HashMap<&'static str, String>,
+    /// Extra instructions appended after the prompt template (e.g. item limits)
+    pub instructions: Vec<String>,
+    /// Persona snippet from the `[personas]` table, appended to the system message
+    pub persona: Option<String>,
+}
+
+/// The Bedrock endpoint override, if any
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing the optional `model.endpoint_url`
+///
+/// # Returns
+///
+/// The URL from `AWS_ENDPOINT_URL_BEDROCK` or, without it, from `model.endpoint_url`;
+/// None when neither is set, so the SDK's regular endpoint resolution applies
+pub fn bedrock_endpoint(settings: &Settings) -> Option<String> {
+    std::env::var(BEDROCK_ENDPOINT_ENV)
+        .ok()
+        .or_else(|| settings.model.endpoint_url.clone())
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// Creates the Bedrock runtime client, honoring the endpoint override
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings containing the optional `model.endpoint_url`
+///
+/// # Returns
+///
+/// A Bedrock runtime client
+///
+/// Only this client uses the override; S3 and Transcribe keep their own endpoints.
+pub fn bedrock_client(config: &SdkConfig, settings: &Settings) -> Client {
+    match bedrock_endpoint(settings) {
+        Some(url) => Client::from_conf(aws_sdk_bedrockruntime::config::Builder::from(config).endpoint_url(url).build()),
+        None => Client::new(config),
+    }
+}
 
 /// Summarizes transcribed text using Amazon Bedrock's AI models
 ///
 /// # Arguments
 ///
 /// * `config` - AWS SDK configuration
+/// * `settings` - Application settings loaded from config.toml
 /// * `transcribed_text` - The text to summarize, typically from a transcription
+/// * `prompt_options` - Placeholder values and extra instructions for the prompt
+/// * `summary_state` - Saved progress to resume from and save to (`--resume-summary`), if any
 /// * `spinner` - Progress spinner to update during the summarization process
 ///
 /// # Returns
 ///
 /// A Result containing the summarized text or an error
 ///
-/// Loads the prompt template and model settings from config.toml,
+/// Reads the prompt template and model settings from the already-loaded settings,
 /// formats the prompt with the transcribed text, and sends it to
-/// the Amazon Bedrock model (default: Claude).
+/// the Amazon Bedrock model (default: Claude). Transcripts longer than
+/// `max_input_chars()` are summarized in chunks by `map_reduce()`.
 pub async fn summarize_text(
     config: &SdkConfig,
+    settings: &Settings,
     transcribed_text: &str,
+    prompt_options: &PromptOptions,
+    summary_state: Option<&mut SummaryState>,
     spinner: &mut Spinner,
 ) -> Result<String, Error> {
-    let client = Client::new(config);
-    let settings = Config::builder()
-        .add_source(File::with_name("config.toml"))
-        .build()?;
+    let client = bedrock_client(config, settings);
 
-    let prompt_template = settings.get_string("prompt.template").unwrap_or_default();
+    let max_chars = max_input_chars(settings);
+    if transcribed_text.chars().count() > max_chars {
+        return map_reduce(
+            &client,
+            settings,
+            transcribed_text,
+            prompt_options,
+            max_chars,
+            summary_state,
+            spinner,
+        )
+        .await;
+    }
 
-    let prompt = format!("{prompt_template}\n\n{transcribed_text}");
+    let prompt = summary_prompt(settings, transcribed_text, prompt_options);
 
-    // We're using the Anthropic Claude Messages API by default.
-    // If you switch models, you may need to update `messages`
-    // and/or `body`.
-    // https://docs.aws.amazon.com/bedrock/latest/userguide/model-parameters.html
-    // Claude: https://docs.aws.amazon.com/bedrock/latest/userguide/model-parameters-anthropic-claude-messages.html
-    let messages = json!([
-        {
-            "role": "user",
-            "content": [
-                {
-                    "type": "text",
-                    "text": prompt,
-                }
-            ]
+    let settings: &Settings = &with_persona(settings, prompt_options.persona.as_deref());
+
+    output::update_spinner(spinner, "Summarizing transcription...", output::spinner_color());
+    let raw_response = summary_response(&client, settings, &prompt, spinner).await?;
+
+    Ok(extract_summary(&raw_response))
+}
+
+/// Summarizes a long transcript chunk by chunk, then combines the chunk summaries
+///
+/// # Arguments
+///
+/// * `client` - Bedrock runtime client
+/// * `settings` - Application settings loaded from config.toml
+/// * `transcribed_text` - The transcript to summarize
+/// * `prompt_options` - Placeholder values and extra instructions for the final prompt
+/// * `max_chars` - Maximum length of a chunk in characters
+/// * `summary_state` - Saved progress: chunks already summarized in it are skipped, and it
+///   is saved after every chunk; None keeps the progress for this run only
+/// * `spinner` - Progress spinner, showing "Summarizing chunk N of M"
+///
+/// # Returns
+///
+/// A Result containing the combined summary or an error
+///
+/// Each chunk gets a fixed prompt asking to keep names, decisions and action items. The
+/// configured prompt, its extra instructions and the persona are only applied to the
+/// final call, which receives the chunk summaries in place of the transcript.
+async fn map_reduce(
+    client: &Client,
+    settings: &Settings,
+    transcribed_text: &str,
+    prompt_options: &PromptOptions,
+    max_chars: usize,
+    summary_state: Option<&mut SummaryState>,
+    spinner: &mut Spinner,
+) -> Result<String, Error> {
+    let chunks = split_transcript(transcribed_text, max_chars);
+    let total = chunks.len();
+
+    // Without --resume-summary, the progress is only kept for this run
+    let mut unsaved_state = SummaryState::default();
+    let save = summary_state.is_some();
+    let state = summary_state.unwrap_or(&mut unsaved_state);
+    state.chunk_summaries.truncate(total);
+    if !state.chunk_summaries.is_empty() {
+        log::info!("Reusing {} saved chunk summaries of {}", state.chunk_summaries.len(), total);
+    }
+
+    for (index, chunk) in chunks.iter().enumerate().skip(state.chunk_summaries.len()) {
+        output::update_spinner(spinner, format!("Summarizing chunk {} of {}...", index + 1, total), output::spinner_color());
+        let prompt = format!(
+            "Below is part {} of {} of a long transcript. Summarize this part only. Keep the \
+             names of speakers, decisions, figures, open questions and action items with their \
+             owners, since the summaries of all parts will be combined later. Reply with the \
+             summary only.\n\n<transcript>\n{}\n</transcript>",
+            index + 1,
+            total,
+            chunk
+        );
+        let summary = generate_text(client, settings, &prompt, None, Some(&mut *spinner))
+            .await
+            .with_context(|| format!("Failed to summarize chunk {} of {}", index + 1, total))?;
+        state.chunk_summaries.push(extract_summary(&summary));
+        if save {
+            if let Err(err) = state.save() {
+                log::warn!("Could not save the summary state: {:#}", err);
+            }
         }
-    ]);
+    }
 
-    let body = json!(
-        {
-            "anthropic_version": settings.get_string("anthropic.anthropic_version").unwrap_or_default(),
-            "max_tokens": settings.get_int("model.max_tokens").unwrap_or_default(),
-            "system": settings.get_string("anthropic.system").unwrap_or_default(),
-            "messages": messages,
-            "temperature": settings.get_int("model.temperature").unwrap_or_default(),
-            "top_p": settings.get_int("model.top_p").unwrap_or_default(),
-            "top_k": settings.get_int("model.top_k").unwrap_or_default(),
+    let combined = state
+        .chunk_summaries
+        .iter()
+        .enumerate()
+        .map(|(index, summary)| format!("<part number=\"{}\">\n{}\n</part>", index + 1, summary.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let combined = format!(
+        "The transcript was too long to summarize at once, so it was split into {} consecutive \
+         parts. Below are the summaries of those parts, in order. Write a single summary of the \
+         whole conversation from them.\n\n{}",
+        total, combined
+    );
+    let prompt = summary_prompt(settings, &combined, prompt_options);
+
+    let settings: &Settings = &with_persona(settings, prompt_options.persona.as_deref());
+
+    output::update_spinner(spinner, format!("Combining {} chunk summaries...", total), output::spinner_color());
+    let raw_response = summary_response(client, settings, &prompt, spinner).await?;
+
+    Ok(extract_summary(&raw_response))
+}
+
+/// The longest transcript summarized in a single request
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing `model.max_input_chars` and `model.max_tokens`
+///
+/// # Returns
+///
+/// `model.max_input_chars` when set, otherwise `INPUT_CHARS_PER_MAX_TOKEN` characters per
+/// token of `model.max_tokens` (2000 when unset)
+pub fn max_input_chars(settings: &Settings) -> usize {
+    settings.model.max_input_chars.filter(|&chars| chars > 0).unwrap_or_else(|| {
+        let max_tokens = settings
+            .model
+            .max_tokens
+            .and_then(|tokens| usize::try_from(tokens).ok())
+            .filter(|&tokens| tokens > 0)
+            .unwrap_or(DEFAULT_MAX_TOKENS);
+        max_tokens * INPUT_CHARS_PER_MAX_TOKEN
+    })
+}
+
+/// Splits a transcript into chunks of at most `max_chars` characters
+///
+/// # Arguments
+///
+/// * `text` - The transcript to split
+/// * `max_chars` - Maximum length of a chunk in characters
+///
+/// # Returns
+///
+/// The non-empty chunks in order
+///
+/// Prefers breaking at a blank line, then at a line break, then after the end of a
+/// sentence and finally at a space; a single word longer than `max_chars` is cut.
+pub fn split_transcript(text: &str, max_chars: usize) -> Vec<&str> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let Some((limit, _)) = rest.char_indices().nth(max_chars) else {
+            chunks.push(rest);
+            break;
+        };
+        let window = &rest[..limit];
+        let split = window
+            .rfind("\n\n")
+            .or_else(|| window.rfind('\n'))
+            .or_else(|| {
+                window
+                    .rmatch_indices([' ', '\t'])
+                    .map(|(index, _)| index)
+                    .find(|&index| window[..index].ends_with(['.', '!', '?']))
+            })
+            .or_else(|| window.rfind(' '))
+            .filter(|&split| split > 0)
+            .unwrap_or(limit);
+
+        let chunk = rest[..split].trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk);
         }
-    )
-    .to_string();
+        rest = rest[split..].trim_start();
+    }
 
-    let blob_body = Blob::new(body);
+    chunks
+}
 
-    spinner.update_text("Summarizing transcription...");
-    let response = client
-        .invoke_model()
-        .body(blob_body)
-        .content_type("application/json")
-        .accept("application/json")
-        .model_id(settings.get_string("model.model_id").unwrap_or_default())
-        .send()
-        .await;
+/// Splits text into chunks of at most `max_bytes` bytes, breaking at line or word boundaries
+///
+/// # Arguments
+///
+/// * `text` - The text to split
+/// * `max_bytes` - Maximum size of a chunk in UTF-8 bytes
+///
+/// # Returns
+///
+/// The non-empty chunks in order
+///
+/// Prefers breaking after a newline, then after a space; a single word longer than
+/// `max_bytes` is cut at the last character boundary that fits.
+pub fn split_into_chunks(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
 
-    match response {
-        Ok(output) => {
-            let response_body = from_utf8(output.body.as_ref()).unwrap_or("");
-            let response_json: serde_json::Value = serde_json::from_str(response_body).unwrap();
+    while !rest.is_empty() {
+        if rest.len() <= max_bytes {
+            chunks.push(rest);
+            break;
+        }
 
-            let raw_response = response_json["content"][0]["text"]
-                .as_str()
-                .unwrap()
-                .replace("\\n", "\n");
-            
-            // Extract only the summary part, removing any transcript that might be included
-            let summarization = if raw_response.contains("Summary:") {
-                // If the response contains "Summary:", extract everything after it
-                raw_response.split("Summary:").nth(1)
-                    .unwrap_or(&raw_response)
-                    .trim()
-                    .to_string()
-            } else {
-                raw_response.trim().to_string()
-            };
-            
-            Ok(summarization)
+        let mut limit = max_bytes;
+        while !rest.is_char_boundary(limit) {
+            limit -= 1;
         }
-        Err(e) => Err(anyhow!(e)),
+        let window = &rest[..limit];
+        let split = window
+            .rfind('\n')
+            .or_else(|| window.rfind(' '))
+            .filter(|&split| split > 0)
+            .unwrap_or(limit);
+
+        let chunk = rest[..split].trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        rest = rest[split..].trim_start();
+    }
+
+    chunks
+}
+
+/// Builds the summarization prompt
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing `prompt.template`
+/// * `transcribed_text` - The text to summarize
+/// * `prompt_options` - Placeholder values and extra instructions for the prompt
+///
+/// # Returns
+///
+/// The rendered template, followed by the extra instructions and the transcript, each
+/// separated by a blank line
+pub fn summary_prompt(settings: &Settings, transcribed_text: &str, prompt_options: &PromptOptions) -> String {
+    let mut prompt_template = render_prompt_template(
+        &settings.prompt.template,
+        &prompt_options.variables,
+    );
+    for instruction in &prompt_options.instructions {
+        prompt_template.push_str("\n\n");
+        prompt_template.push_str(instruction);
+    }
+
+    format!("{prompt_template}\n\n{transcribed_text}")
+}
+
+/// Returns the settings with a persona added to the system message
+///
+/// # Arguments
+///
+/// * `settings` - Application settings loaded from config.toml
+/// * `persona` - Persona snippet from the `[personas]` table, if one was chosen
+///
+/// # Returns
+///
+/// The settings with the persona appended to `anthropic.system`, or used as the
+/// system message when none is configured; the settings unchanged without a persona
+fn with_persona<'a>(settings: &'a Settings, persona: Option<&str>) -> Cow<'a, Settings> {
+    let Some(persona) = persona else {
+        return Cow::Borrowed(settings);
+    };
+    let mut settings = settings.clone();
+    let system = &mut settings.anthropic.system;
+    if !system.is_empty() {
+        system.push_str("\n\n");
+    }
+    system.push_str(persona.trim());
+    Cow::Owned(settings)
+}
+
+/// Substitutes run metadata placeholders in a prompt template
+///
+/// # Arguments
+///
+/// * `template` - The prompt template from config.toml
+/// * `variables` - Placeholder names (without braces) and their values
+///
+/// # Returns
+///
+/// The template with every known `{name}` placeholder replaced
+///
+/// Unknown placeholders are left untouched, so literal braces in a template
+/// don't need escaping.
+pub fn render_prompt_template(template: &str, variables: &HashMap<&str, String>) -> String {
+    variables
+        .iter()
+        .fold(template.to_string(), |prompt, (name, value)| {
+            prompt.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// A topic segment of a recording identified by the model
+#[derive(Debug, Clone, Deserialize)]
+pub struct Chapter {
+    /// Start of the chapter as `HH:MM:SS`
+    pub start: String,
+    /// Short chapter title
+    pub title: String,
+    /// One or two sentence summary of the chapter
+    pub summary: String,
+}
+
+/// Segments a timed transcript into chapters using Amazon Bedrock
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings containing model settings
+/// * `timed_transcript` - Transcript with an `[HH:MM:SS]` timestamp per speaker turn
+///
+/// # Returns
+///
+/// A Result containing the chapters in chronological order or an error
+///
+/// Asks the model to identify topic boundaries and return them as a JSON array, then
+/// parses the first JSON array found in the response.
+pub async fn detect_chapters(
+    config: &SdkConfig,
+    settings: &Settings,
+    timed_transcript: &str,
+) -> Result<Vec<Chapter>, Error> {
+    let client = bedrock_client(config, settings);
+
+    let prompt = format!(
+        "The transcript below has a timestamp at the start of each speaker turn. Split it into \
+         chapters at the points where the topic changes. Reply with a JSON array only, where \
+         each element has the fields \"start\" (the HH:MM:SS timestamp where the chapter begins), \
+         \"title\" (a short title) and \"summary\" (one or two sentences).\n\n\
+         <transcript>\n{timed_transcript}\n</transcript>"
+    );
+
+    let response = generate_text(&client, settings, &prompt, None, None).await?;
+
+    let json = match (response.find('['), response.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &response[start..=end],
+        _ => return Err(anyhow!("Model did not return a chapter list")),
+    };
+    let mut chapters: Vec<Chapter> =
+        serde_json::from_str(json).context("Failed to parse the chapter list")?;
+    chapters.sort_by(|a, b| a.start.cmp(&b.start));
+
+    Ok(chapters)
+}
+
+/// Renders chapters as a Markdown section that can be appended to a summary
+///
+/// # Arguments
+///
+/// * `chapters` - Chapters returned by `detect_chapters()`
+///
+/// # Returns
+///
+/// A "Chapters" section with one bullet per chapter
+pub fn format_chapters(chapters: &[Chapter]) -> String {
+    let mut section = String::from("## Chapters\n\n");
+    for chapter in chapters {
+        section.push_str(&format!(
+            "- **[{}] {}** - {}\n",
+            chapter.start,
+            chapter.title.trim(),
+            chapter.summary.trim()
+        ));
+    }
+    section
+}
+
+/// Fixes obvious transcription errors in a transcript using Amazon Bedrock
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings containing model settings
+/// * `transcript` - Transcript with one "label: text" turn per line
+///
+/// # Returns
+///
+/// A Result containing the corrected transcript or an error
+///
+/// The transcript is sent in chunks of whole lines so every response fits the output
+/// token limit. The model is asked to correct misrecognized words (homophones, run-ons,
+/// broken punctuation) and keep everything else, including the speaker labels. A chunk
+/// whose correction comes back much shorter than the original is kept as it was, since
+/// the model most likely dropped or summarized text.
+pub async fn clean_transcript(
+    config: &SdkConfig,
+    settings: &Settings,
+    transcript: &str,
+) -> Result<String, Error> {
+    let client = bedrock_client(config, settings);
+    let mut cleaned = Vec::new();
+
+    for chunk in split_into_chunks(transcript, CLEAN_CHUNK_BYTES) {
+        let prompt = format!(
+            "The transcript below was produced by speech recognition. Fix obvious recognition \
+             errors only: misheard words and homophones, run-on sentences and punctuation. Do \
+             not summarize, shorten, reword or translate. Keep every line and its speaker label \
+             (e.g. \"spk_0:\") exactly as they are. Reply with the corrected transcript only.\n\n\
+             <transcript>\n{chunk}\n</transcript>"
+        );
+        let response = generate_text(&client, settings, &prompt, Some(CLEAN_MAX_TOKENS), None).await?;
+        let response = response
+            .trim()
+            .trim_start_matches("<transcript>")
+            .trim_end_matches("</transcript>")
+            .trim();
+
+        if response.len() * 10 < chunk.len() * 8 {
+            log::warn!("Transcript correction dropped too much text, keeping the original chunk");
+            cleaned.push(chunk.to_string());
+        } else {
+            cleaned.push(response.to_string());
+        }
+    }
+
+    Ok(cleaned.join("\n"))
+}
+
+/// Summary of one speaker's contributions
+#[derive(Debug, Clone)]
+pub struct SpeakerSummary {
+    /// Speaker or channel label from the transcript (e.g. "spk_0")
+    pub label: String,
+    /// Short summary, or `None` when the speaker said too little to summarize
+    pub summary: Option<String>,
+}
+
+/// Summarizes each speaker's contributions separately using Amazon Bedrock
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings containing model settings
+/// * `transcript` - Transcript with one "label: text" turn per line
+///
+/// # Returns
+///
+/// A Result containing one summary per speaker, in order of first appearance, or an error
+///
+/// Runs one Bedrock call per speaker on that speaker's concatenated turns. Speakers with
+/// fewer than `MIN_SPEAKER_WORDS` words are not sent to the model and get no summary.
+pub async fn summarize_speakers(
+    config: &SdkConfig,
+    settings: &Settings,
+    transcript: &str,
+) -> Result<Vec<SpeakerSummary>, Error> {
+    let client = bedrock_client(config, settings);
+    let mut summaries = Vec::new();
+
+    for (label, turns) in transcribe::speaker_turns(transcript) {
+        if turns.split_whitespace().count() < MIN_SPEAKER_WORDS {
+            summaries.push(SpeakerSummary { label, summary: None });
+            continue;
+        }
+
+        let prompt = format!(
+            "Below is everything one participant said in a conversation. In two to four \
+             sentences, summarize this participant's contributions: the points they made, \
+             questions they raised and anything they committed to. Answer in the same \
+             language as the text.\n\n<turns>\n{turns}\n</turns>"
+        );
+        let response = generate_text(&client, settings, &prompt, None, None).await?;
+        summaries.push(SpeakerSummary {
+            label,
+            summary: Some(response.trim().to_string()),
+        });
+    }
+
+    Ok(summaries)
+}
+
+/// Renders per-speaker summaries as a Markdown section that can be appended to a summary
+///
+/// # Arguments
+///
+/// * `summaries` - Summaries returned by `summarize_speakers()`
+///
+/// # Returns
+///
+/// A "Per-Speaker Summary" section with a subsection per speaker
+///
+/// Labels are shown as "Speaker N" / "Channel N", matching how the main summary refers
+/// to speakers.
+pub fn format_speaker_summaries(summaries: &[SpeakerSummary]) -> String {
+    let mut section = String::from("## Per-Speaker Summary\n");
+    for speaker in summaries {
+        let name = if let Some(number) = speaker.label.strip_prefix("spk_") {
+            format!("Speaker {}", number)
+        } else if let Some(number) = speaker.label.strip_prefix("ch_") {
+            format!("Channel {}", number)
+        } else {
+            speaker.label.clone()
+        };
+        let summary = speaker.summary.as_deref().unwrap_or("Minimal contribution.");
+        section.push_str(&format!("\n### {}\n\n{}\n", name, summary));
+    }
+    section
+}
+
+/// Limits every bulleted or numbered list in a summary to a maximum number of items
+///
+/// # Arguments
+///
+/// * `summary` - The summary text, typically Markdown
+/// * `max_items` - Maximum number of items to keep per list
+///
+/// # Returns
+///
+/// The summary with surplus list items removed
+///
+/// Acts as a deterministic guarantee on top of the prompt instruction, since models
+/// don't always respect item limits. Indented continuation lines and nested items are
+/// removed together with the item they belong to.
+pub fn limit_list_items(summary: &str, max_items: usize) -> String {
+    let mut limited = String::new();
+    let mut items_in_list = 0;
+    let mut dropping = false;
+
+    for line in summary.lines() {
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+
+        if !indented && is_list_item(line) {
+            items_in_list += 1;
+            dropping = items_in_list > max_items;
+        } else if indented && !line.trim().is_empty() {
+            // Continuation of the current item; keep or drop along with it
+        } else if !line.trim().is_empty() {
+            items_in_list = 0;
+            dropping = false;
+        }
+
+        if !dropping {
+            limited.push_str(line);
+            limited.push('\n');
+        }
+    }
+
+    if !summary.ends_with('\n') {
+        limited.pop();
+    }
+    limited
+}
+
+/// A named section of a summary (`--sections`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SummarySection {
+    /// Section name, as requested
+    pub name: String,
+    /// Section content (Markdown), or `MISSING_SECTION`
+    pub content: String,
+}
+
+/// Builds the prompt instruction asking for a fixed list of sections
+///
+/// # Arguments
+///
+/// * `sections` - Section names, in order
+///
+/// # Returns
+///
+/// The instruction to append to the prompt
+pub fn sections_instruction(sections: &[String]) -> String {
+    let headings = sections
+        .iter()
+        .map(|name| format!("## {}", name))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Structure the summary as exactly these sections, in this order, each starting with its Markdown heading on a line of its own:\n{}\nAdd no other sections and no text before the first heading. If the transcript has nothing for a section, write \"{}\" under its heading.",
+        headings, MISSING_SECTION
+    )
+}
+
+/// Splits a summary into the requested sections
+///
+/// # Arguments
+///
+/// * `summary` - The summary returned by the model
+/// * `sections` - Section names, in order
+///
+/// # Returns
+///
+/// One entry per requested section, in the requested order
+///
+/// Headings are recognized case-insensitively as Markdown headings of any level,
+/// bold lines (`**Risks**`) or plain lines, with or without a trailing colon. Text before
+/// the first recognized heading is dropped; other headings stay part of the section
+/// they appear in. Sections the model left out or left empty get `MISSING_SECTION`, so
+/// the shape of the result never depends on the model.
+pub fn parse_sections(summary: &str, sections: &[String]) -> Vec<SummarySection> {
+    let mut contents: Vec<Vec<&str>> = vec![Vec::new(); sections.len()];
+    let mut current: Option<usize> = None;
+
+    for line in summary.lines() {
+        let heading = line
+            .trim()
+            .trim_start_matches('#')
+            .trim()
+            .trim_matches('*')
+            .trim()
+            .trim_end_matches(':')
+            .trim();
+        match sections.iter().position(|name| name.trim().eq_ignore_ascii_case(heading)) {
+            Some(index) if !heading.is_empty() => current = Some(index),
+            _ => {
+                if let Some(index) = current {
+                    contents[index].push(line);
+                }
+            }
+        }
+    }
+
+    sections
+        .iter()
+        .zip(contents)
+        .map(|(name, lines)| {
+            let content = lines.join("\n").trim().to_string();
+            SummarySection {
+                name: name.trim().to_string(),
+                content: if content.is_empty() { MISSING_SECTION.to_string() } else { content },
+            }
+        })
+        .collect()
+}
+
+/// Renders summary sections as Markdown
+///
+/// # Arguments
+///
+/// * `sections` - The sections, as returned by `parse_sections()`
+///
+/// # Returns
+///
+/// One "## Name" heading per section followed by its content
+pub fn render_sections(sections: &[SummarySection]) -> String {
+    sections
+        .iter()
+        .map(|section| format!("## {}\n\n{}", section.name, section.content))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Condensed summaries of earlier recordings, carried into the prompt for the next one
+///
+/// Each summary is condensed to its first `CARRIED_SUMMARY_CHARS` characters, cut at a
+/// line break where possible. When the entries together exceed `max_chars`, the oldest
+/// are dropped first, so the most recent recordings are always represented.
+#[derive(Debug, Clone)]
+pub struct CarriedContext {
+    entries: Vec<(String, String)>,
+    max_chars: usize,
+}
+
+impl CarriedContext {
+    /// Creates an empty context
+    ///
+    /// # Arguments
+    ///
+    /// * `max_chars` - Upper bound for the rendered context (e.g. `CARRIED_CONTEXT_CHARS`)
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            max_chars,
+        }
+    }
+
+    /// Adds the summary of a recording
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Name of the recording
+    /// * `summary` - The recording's summary
+    pub fn push(&mut self, label: &str, summary: &str) {
+        let summary = summary.trim();
+        let mut condensed: String = summary.chars().take(CARRIED_SUMMARY_CHARS).collect();
+        if condensed.len() < summary.len() {
+            if let Some(cut) = condensed.rfind('\n').filter(|cut| *cut > 0) {
+                condensed.truncate(cut);
+            }
+            condensed = format!("{} …", condensed.trim_end());
+        }
+        self.entries.push((label.to_string(), condensed));
+
+        while self.entries.len() > 1 && self.join_entries().chars().count() > self.max_chars {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Whether no summaries have been added yet
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Renders the context for the prompt, oldest recording first
+    ///
+    /// # Returns
+    ///
+    /// One "### label" section per recording, at most `max_chars` characters long
+    pub fn render(&self) -> String {
+        self.join_entries().chars().take(self.max_chars).collect()
+    }
+
+    /// Joins all entries, without the length limit
+    fn join_entries(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(label, summary)| format!("### {}\n{}", label, summary))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Builds the prompt instruction carrying the reference documents (`--reference`)
+///
+/// # Arguments
+///
+/// * `documents` - Name and contents of each reference document, in the order given
+/// * `max_chars` - Upper bound for the contents of all documents together
+///
+/// # Returns
+///
+/// The instruction, with each document between `<reference name="...">` and
+/// `</reference>` tags, and the names of the documents that were cut short or left out
+///
+/// Documents are included in order until `max_chars` is reached: the document that
+/// crosses the limit is cut (marked with "…") and later ones are left out.
+pub fn reference_instruction(documents: &[(String, String)], max_chars: usize) -> (String, Vec<String>) {
+    let mut remaining = max_chars;
+    let mut truncated = Vec::new();
+    let mut blocks = Vec::new();
+    for (name, contents) in documents {
+        let contents = contents.trim();
+        let length = contents.chars().count();
+        if length > remaining {
+            truncated.push(name.clone());
+        }
+        if remaining == 0 {
+            continue;
+        }
+        let contents = if length > remaining {
+            format!("{} …", contents.chars().take(remaining).collect::<String>().trim_end())
+        } else {
+            contents.to_string()
+        };
+        remaining -= length.min(remaining);
+        blocks.push(format!("<reference name=\"{}\">\n{}\n</reference>", name, contents));
+    }
+
+    let instruction = format!(
+        "The reference material below (e.g. a glossary or earlier decisions) is background only. Use it to expand acronyms, spell names correctly and recognize references to earlier decisions, but summarize only the transcript and don't repeat the reference material.\n\n{}",
+        blocks.join("\n\n")
+    );
+    (instruction, truncated)
+}
+
+/// Checks whether a line starts a bulleted ("-", "*", "•") or numbered ("1.", "1)") list item
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("• ") {
+        return true;
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+/// Asks the model to rate how well a summary captures the transcript
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings loaded from config.toml
+/// * `transcribed_text` - The transcript the summary was produced from
+/// * `summary` - The summary to rate
+///
+/// # Returns
+///
+/// A Result containing the self-assessed rating from 1 (poor) to 5 (excellent) or an error
+///
+/// Makes a cheap follow-up call (the response is capped at a few tokens) asking the
+/// model to judge the summary's completeness and accuracy, and parses the first
+/// digit between 1 and 5 from the reply.
+pub async fn rate_summary(
+    config: &SdkConfig,
+    settings: &Settings,
+    transcribed_text: &str,
+    summary: &str,
+) -> Result<u8, Error> {
+    let client = bedrock_client(config, settings);
+
+    let prompt = format!(
+        "Rate how completely and accurately the summary below captures the transcript, \
+         on a scale from 1 (poor) to 5 (excellent). Reply with a single digit only.\n\n\
+         <transcript>\n{transcribed_text}\n</transcript>\n\n<summary>\n{summary}\n</summary>"
+    );
+
+    let response = generate_text(&client, settings, &prompt, Some(SELF_RATING_MAX_TOKENS), None).await?;
+
+    response
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .find(|digit| (1..=5).contains(digit))
+        .map(|digit| digit as u8)
+        .ok_or_else(|| anyhow!("Model did not return a rating: {}", response.trim()))
+}
+
+/// Outcome of fitting a summary into a word-count band
+#[derive(Debug, Clone)]
+pub struct WordBandFit {
+    /// The final summary (the original one when no rewrite was needed)
+    pub summary: String,
+    /// Word count of the final summary
+    pub words: usize,
+    /// Whether the final summary is within the band
+    pub met: bool,
+    /// Number of rewrites that were requested
+    pub attempts: u32,
+}
+
+/// Counts the words of a text, splitting on whitespace
+pub fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Re-prompts the model until a summary fits a word-count band
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `settings` - Application settings loaded from config.toml
+/// * `transcribed_text` - The transcript, so an expanded summary can add real detail
+/// * `summary` - The summary to check
+/// * `prompt_options` - Per-run prompt additions; the persona is kept for rewrites
+/// * `min_words` - Optional lower bound of the band
+/// * `max_words` - Optional upper bound of the band
+///
+/// # Returns
+///
+/// A Result containing the final summary, its word count and whether the band was met
+///
+/// A summary outside the band is rewritten, asking the model to expand or condense it
+/// to the band, up to `WORD_BAND_ATTEMPTS` times. When the attempts are exhausted, the
+/// rewrite closest to the band is returned.
+pub async fn fit_word_band(
+    config: &SdkConfig,
+    settings: &Settings,
+    transcribed_text: &str,
+    summary: String,
+    prompt_options: &PromptOptions,
+    min_words: Option<usize>,
+    max_words: Option<usize>,
+) -> Result<WordBandFit, Error> {
+    let client = bedrock_client(config, settings);
+
+    let settings: &Settings = &with_persona(settings, prompt_options.persona.as_deref());
+
+    let min = min_words.unwrap_or(0);
+    let max = max_words.unwrap_or(usize::MAX);
+    let distance = |words: usize| min.saturating_sub(words) + words.saturating_sub(max);
+    let target = match (min_words, max_words) {
+        (Some(min), Some(max)) => format!("between {} and {} words", min, max),
+        (Some(min), None) => format!("at least {} words", min),
+        (None, Some(max)) => format!("at most {} words", max),
+        (None, None) => unreachable!("fit_word_band called without a band"),
+    };
+
+    let mut best = summary;
+    let mut best_words = count_words(&best);
+    let mut attempts = 0;
+    while distance(best_words) > 0 && attempts < WORD_BAND_ATTEMPTS {
+        attempts += 1;
+        let instruction = if best_words < min {
+            "It is too short: expand it with more relevant detail from the transcript."
+        } else {
+            "It is too long: condense it, keeping the most important points."
+        };
+        let prompt = format!(
+            "The summary below has {best_words} words. Rewrite it to {target}. {instruction} \
+             Keep the same structure and headings. Reply with the rewritten summary only.\n\n\
+             <transcript>\n{transcribed_text}\n</transcript>\n\n<summary>\n{best}\n</summary>"
+        );
+
+        let rewrite = extract_summary(&generate_text(&client, settings, &prompt, None, None).await?);
+        let words = count_words(&rewrite);
+        if distance(words) < distance(best_words) {
+            best = rewrite;
+            best_words = words;
+        }
+    }
+
+    Ok(WordBandFit {
+        summary: best,
+        words: best_words,
+        met: distance(best_words) == 0,
+        attempts,
+    })
+}
+
+/// Sends a prompt to the configured Bedrock model and returns the response text
+///
+/// # Arguments
+///
+/// * `client` - Bedrock runtime client
+/// * `settings` - Application settings loaded from config.toml
+/// * `prompt` - The full user prompt
+/// * `max_tokens` - Optional override for `model.max_tokens`
+/// * `spinner` - Progress spinner showing "Retrying (attempt N)..." while throttled, if any
+///
+/// # Returns
+///
+/// A Result containing the raw response text or an error
+///
+/// Uses the Converse API when `model.api = "converse"`, otherwise the
+/// model-specific `InvokeModel` body. Throttled requests are retried up to
+/// `model.max_retries` times with exponential backoff (see `aws_utils::retry_throttled()`).
+pub(crate) async fn generate_text(
+    client: &Client,
+    settings: &Settings,
+    prompt: &str,
+    max_tokens: Option<i64>,
+    mut spinner: Option<&mut Spinner>,
+) -> Result<String, Error> {
+    let on_retry = |attempt: u32| {
+        if let Some(spinner) = spinner.as_deref_mut() {
+            output::update_spinner(spinner, format!("Retrying (attempt {})...", attempt), output::spinner_color());
+        }
+    };
+    if settings.model.api == "converse" {
+        return converse_text(client, settings, prompt, max_tokens, on_retry).await;
+    }
+
+    check_provider(&settings.model.provider)?;
+    let body = invoke_body(settings, prompt, max_tokens);
+    dump_request(json!({
+        "api": "invoke",
+        "modelId": settings.model.model_id,
+        "body": body,
+    }))?;
+    let body = body.to_string();
+
+    let request = client
+        .invoke_model()
+        .body(Blob::new(body))
+        .content_type("application/json")
+        .accept("application/json")
+        .model_id(&settings.model.model_id);
+    let response =
+        aws_utils::retry_throttled("InvokeModel", settings.model.max_retries, on_retry, || request.clone().send()).await;
+
+    match response {
+        Ok(output) => {
+            let response_body = String::from_utf8_lossy(output.body.as_ref());
+            parse_invoke_response(&settings.model.provider, &response_body)
+        }
+        Err(e) => Err(anyhow!(e)),
+    }
+}
+
+/// Sends the prompt of the final summary, streamed when `model.stream` is set
+///
+/// # Arguments
+///
+/// * `client` - Bedrock runtime client
+/// * `settings` - Application settings loaded from config.toml
+/// * `prompt` - The full user prompt
+/// * `spinner` - Progress spinner
+///
+/// # Returns
+///
+/// A Result containing the raw response text or an error
+///
+/// Streaming uses `InvokeModelWithResponseStream`, so with `model.api = "converse"` the
+/// response is always read at once.
+async fn summary_response(
+    client: &Client,
+    settings: &Settings,
+    prompt: &str,
+    spinner: &mut Spinner,
+) -> Result<String, Error> {
+    if settings.model.stream && settings.model.api != "converse" {
+        return stream_text(client, settings, prompt, spinner).await;
+    }
+    generate_text(client, settings, prompt, None, Some(spinner)).await
+}
+
+/// Prints streamed summaries to the terminal as they arrive
+///
+/// # Arguments
+///
+/// * `enabled` - Whether to print (terminal output with `model.stream`)
+///
+/// Without it, a streamed summary is only collected and returned.
+pub fn set_stream_to_terminal(enabled: bool) {
+    STREAM_TO_TERMINAL.store(enabled, Ordering::SeqCst);
+    STREAMED_SUMMARY.lock().unwrap().take();
+}
+
+/// Takes the summary last printed while it was streamed
+///
+/// # Returns
+///
+/// The summary as printed (after `extract_summary()`), or None when nothing was printed
+/// since the last call
+pub fn take_streamed_summary() -> Option<String> {
+    STREAMED_SUMMARY.lock().unwrap().take()
+}
+
+/// What is printed after a summary was streamed to the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamedReprint {
+    /// Nothing was streamed, so print the whole summary
+    Full,
+    /// The streamed text is the final summary
+    Nothing,
+    /// Sections were appended to the streamed text (chapters, sentiment...); print these
+    Appended(String),
+    /// The summary was rewritten after it was streamed; print it again with a notice
+    Adjusted,
+}
+
+/// Decides what to print once the final summary is known
+///
+/// # Arguments
+///
+/// * `streamed` - The summary printed while it was streamed (`take_streamed_summary()`)
+/// * `summary` - The final summary, after any post-processing
+///
+/// # Returns
+///
+/// Whether to print nothing more, only what was appended, or the whole summary
+pub fn streamed_reprint(streamed: Option<&str>, summary: &str) -> StreamedReprint {
+    let Some(streamed) = streamed else {
+        return StreamedReprint::Full;
+    };
+    match summary.strip_prefix(streamed) {
+        Some(rest) if rest.trim().is_empty() => StreamedReprint::Nothing,
+        Some(rest) => StreamedReprint::Appended(rest.trim().to_string()),
+        None => StreamedReprint::Adjusted,
+    }
+}
+
+/// Sends a prompt with `InvokeModelWithResponseStream` and collects the response text
+///
+/// # Arguments
+///
+/// * `client` - Bedrock runtime client
+/// * `settings` - Application settings loaded from config.toml
+/// * `prompt` - The full user prompt
+/// * `spinner` - Progress spinner, stopped before the first text is printed
+///
+/// # Returns
+///
+/// A Result containing the raw response text or an error
+///
+/// With `set_stream_to_terminal(true)`, the text is printed as it arrives and the
+/// printed summary is kept for `take_streamed_summary()`. Throttled requests are retried
+/// like `generate_text()`, but an error in the middle of the stream is not.
+async fn stream_text(
+    client: &Client,
+    settings: &Settings,
+    prompt: &str,
+    spinner: &mut Spinner,
+) -> Result<String, Error> {
+    check_provider(&settings.model.provider)?;
+    let provider = settings.model.provider.as_str();
+    let body = invoke_body(settings, prompt, None);
+    dump_request(json!({
+        "api": "invoke-stream",
+        "modelId": settings.model.model_id,
+        "body": body,
+    }))?;
+
+    let request = client
+        .invoke_model_with_response_stream()
+        .body(Blob::new(body.to_string()))
+        .content_type("application/json")
+        .accept("application/json")
+        .model_id(&settings.model.model_id);
+    let mut output = aws_utils::retry_throttled(
+        "InvokeModelWithResponseStream",
+        settings.model.max_retries,
+        |attempt| output::update_spinner(spinner, format!("Retrying (attempt {})...", attempt), output::spinner_color()),
+        || request.clone().send(),
+    )
+    .await
+    .map_err(|e| anyhow!(e))?;
+
+    let print = STREAM_TO_TERMINAL.load(Ordering::SeqCst);
+    let mut text = String::new();
+    let mut stop_reason = None;
+    while let Some(event) = output.body.recv().await.map_err(|e| anyhow!(e))? {
+        let ResponseStream::Chunk(part) = event else {
+            continue;
+        };
+        let Some(bytes) = part.bytes() else {
+            continue;
+        };
+        let chunk = parse_stream_chunk(provider, bytes.as_ref())?;
+        stop_reason = chunk.stop_reason.or(stop_reason);
+        let Some(delta) = chunk.text else {
+            continue;
+        };
+
+        if print {
+            if text.is_empty() {
+                output::persist_spinner(spinner, "📝", "Summary:");
+            }
+            print!("{}", delta);
+            let _ = std::io::stdout().flush();
+        }
+        text.push_str(&delta);
+    }
+    if print && !text.is_empty() {
+        println!("\n");
+    }
+
+    check_stop_reason(stop_reason.as_deref().unwrap_or("end_turn"))?;
+    if text.is_empty() {
+        return Err(anyhow!("The model returned no text"));
+    }
+    if print {
+        *STREAMED_SUMMARY.lock().unwrap() = Some(extract_summary(&text));
+    }
+    Ok(text)
+}
+
+/// Enables writing every Bedrock request to a file (`--dump-request`)
+///
+/// # Arguments
+///
+/// * `path` - File to write the requests to, or None to stop dumping
+///
+/// Requests collected before are discarded.
+pub fn set_dump_request(path: Option<PathBuf>) {
+    *DUMP_REQUEST_PATH.lock().unwrap() = path;
+    DUMPED_REQUESTS.lock().unwrap().clear();
+}
+
+/// Adds a request to the dump file, if dumping is enabled
+///
+/// # Arguments
+///
+/// * `request` - The request: API, model id and body
+///
+/// # Returns
+///
+/// A Result indicating success or an error writing the file
+///
+/// The file holds a JSON array of all requests of the run so far, in the order they
+/// were sent, and is rewritten with each request so it is complete even if the run fails.
+fn dump_request(request: serde_json::Value) -> Result<(), Error> {
+    let Some(path) = DUMP_REQUEST_PATH.lock().unwrap().clone() else {
+        return Ok(());
+    };
+
+    let mut requests = DUMPED_REQUESTS.lock().unwrap();
+    requests.push(request);
+    fs::write(&path, serde_json::to_string_pretty(&*requests)?)
+        .with_context(|| format!("❌ Error writing the Bedrock request to {}", path.display()))
+}
+
+/// Checks that `model.provider` names a supported model family
+///
+/// # Arguments
+///
+/// * `provider` - The configured provider
+///
+/// # Returns
+///
+/// A Result indicating the provider is one of `PROVIDERS`, or an error listing them
+pub fn check_provider(provider: &str) -> Result<(), Error> {
+    if PROVIDERS.contains(&provider) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "Unknown model.provider '{}', expected one of: {}",
+        provider,
+        PROVIDERS.join(", ")
+    ))
+}
+
+/// Builds the InvokeModel request body for the configured `model.provider`
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing the model parameters
+/// * `prompt` - The user message
+/// * `max_tokens` - Optional override for `model.max_tokens`
+///
+/// # Returns
+///
+/// The JSON body
+///
+/// `temperature` and `top_p` are sent as floats, defaulting to `DEFAULT_TEMPERATURE` and
+/// `DEFAULT_TOP_P` when unset; `max_tokens` and `top_k` are integers. Titan and Llama
+/// have no `top_k`, so it is only sent to Anthropic models.
+pub fn invoke_body(settings: &Settings, prompt: &str, max_tokens: Option<i64>) -> serde_json::Value {
+    // Each model family has its own InvokeModel body:
+    // https://docs.aws.amazon.com/bedrock/latest/userguide/model-parameters.html
+    match settings.model.provider.as_str() {
+        "titan" => titan_body(settings, prompt, max_tokens),
+        "meta" => llama_body(settings, prompt, max_tokens),
+        _ => anthropic_body(settings, prompt, max_tokens),
+    }
+}
+
+/// Builds an Anthropic Claude Messages API body
+///
+/// https://docs.aws.amazon.com/bedrock/latest/userguide/model-parameters-anthropic-claude-messages.html
+fn anthropic_body(settings: &Settings, prompt: &str, max_tokens: Option<i64>) -> serde_json::Value {
+    let messages = json!([
+        {
+            "role": "user",
+            "content": [
+                {
+                    "type": "text",
+                    "text": prompt,
+                }
+            ]
+        }
+    ]);
+
+    json!(
+        {
+            "anthropic_version": settings.anthropic.anthropic_version,
+            "max_tokens": max_tokens.or(settings.model.max_tokens).unwrap_or_default(),
+            "system": settings.anthropic.system,
+            "messages": messages,
+            "temperature": settings.model.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            "top_p": settings.model.top_p.unwrap_or(DEFAULT_TOP_P),
+            "top_k": settings.model.top_k.unwrap_or_default(),
+        }
+    )
+}
+
+/// Builds an Amazon Titan Text body
+///
+/// Titan has no system message, so the system message (with any persona) is put before
+/// the prompt.
+/// https://docs.aws.amazon.com/bedrock/latest/userguide/model-parameters-titan-text.html
+fn titan_body(settings: &Settings, prompt: &str, max_tokens: Option<i64>) -> serde_json::Value {
+    let system = settings.anthropic.system.trim();
+    let input_text = if system.is_empty() {
+        prompt.to_string()
+    } else {
+        format!("{}\n\n{}", system, prompt)
+    };
+
+    json!(
+        {
+            "inputText": input_text,
+            "textGenerationConfig": {
+                "maxTokenCount": max_tokens.or(settings.model.max_tokens).unwrap_or_default(),
+                "temperature": settings.model.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+                "topP": settings.model.top_p.unwrap_or(DEFAULT_TOP_P),
+            }
+        }
+    )
+}
+
+/// Builds a Meta Llama body, with the prompt in the Llama 3 instruct format
+///
+/// https://docs.aws.amazon.com/bedrock/latest/userguide/model-parameters-meta.html
+fn llama_body(settings: &Settings, prompt: &str, max_tokens: Option<i64>) -> serde_json::Value {
+    let system = settings.anthropic.system.trim();
+    let mut formatted = String::from("<|begin_of_text|>");
+    if !system.is_empty() {
+        formatted.push_str(&format!("<|start_header_id|>system<|end_header_id|>\n\n{}<|eot_id|>", system));
+    }
+    formatted.push_str(&format!(
+        "<|start_header_id|>user<|end_header_id|>\n\n{}<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n",
+        prompt
+    ));
+
+    json!(
+        {
+            "prompt": formatted,
+            "max_gen_len": max_tokens.or(settings.model.max_tokens).unwrap_or_default(),
+            "temperature": settings.model.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            "top_p": settings.model.top_p.unwrap_or(DEFAULT_TOP_P),
+        }
+    )
+}
+
+/// Reads the summary text from an InvokeModel response body
+///
+/// # Arguments
+///
+/// * `provider` - The `model.provider` the request was built for
+/// * `response_body` - The raw response body
+///
+/// # Returns
+///
+/// A Result containing the text or an error
+///
+/// Anthropic text comes from the `content` blocks, Titan text from
+/// `results[0].outputText` and Llama text from `generation`. Errors for bodies that
+/// aren't JSON, that have no text content (an error envelope or a model with a different
+/// schema) or that stopped for a reason other than the end of the answer. Except for the
+/// stop reason, the error quotes the start of the body (`RESPONSE_EXCERPT_CHARS`), so it
+/// shows what Bedrock returned.
+pub fn parse_invoke_response(provider: &str, response_body: &str) -> Result<String, Error> {
+    let response_json: serde_json::Value = serde_json::from_str(response_body).with_context(|| {
+        format!(
+            "Bedrock returned a response that is not valid JSON: {}",
+            response_excerpt(response_body)
+        )
+    })?;
+
+    let text = match provider {
+        "titan" => {
+            let result = &response_json["results"][0];
+            check_stop_reason(titan_stop_reason(result["completionReason"].as_str()))?;
+            result["outputText"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("The model returned no text (no results[0].outputText)"))
+        }
+        "meta" => {
+            check_stop_reason(llama_stop_reason(response_json["stop_reason"].as_str()))?;
+            response_json["generation"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("The model returned no text (no generation)"))
+        }
+        _ => {
+            check_stop_reason(response_json["stop_reason"].as_str().unwrap_or("end_turn"))?;
+            response_text(&response_json)
+        }
+    };
+
+    let text = text.with_context(|| format!("Unexpected Bedrock response: {}", response_excerpt(response_body)))?;
+    Ok(text.replace("\\n", "\n"))
+}
+
+/// Maps a Titan `completionReason` (FINISH, LENGTH or CONTENT_FILTERED) to a stop reason
+fn titan_stop_reason(completion_reason: Option<&str>) -> &'static str {
+    match completion_reason {
+        Some("LENGTH") => "max_tokens",
+        Some("CONTENT_FILTERED") => "content_filtered",
+        _ => "end_turn",
+    }
+}
+
+/// Maps a Llama `stop_reason` (stop or length) to a stop reason
+fn llama_stop_reason(stop_reason: Option<&str>) -> &'static str {
+    match stop_reason {
+        Some("length") => "max_tokens",
+        _ => "end_turn",
+    }
+}
+
+/// Text and stop reason carried by one chunk of a streamed InvokeModel response
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StreamChunk {
+    /// Text to append to the response, if the chunk carries any
+    pub text: Option<String>,
+    /// Why the model stopped, in the names `check_stop_reason()` uses, on the last chunk
+    pub stop_reason: Option<String>,
+}
+
+/// Reads one chunk of a streamed InvokeModel response (`model.stream`)
+///
+/// # Arguments
+///
+/// * `provider` - The `model.provider` the request was built for
+/// * `chunk` - The JSON bytes of the chunk
+///
+/// # Returns
+///
+/// A Result containing the chunk's text and stop reason, or an error for a chunk that
+/// isn't JSON
+///
+/// Anthropic text arrives in `content_block_delta` events (with a `text_delta`) and the
+/// stop reason in the `message_delta` event; other events (`message_start`,
+/// `content_block_start`, `content_block_stop`, `message_stop`) carry neither. Titan
+/// chunks carry `outputText` and `completionReason`, Llama chunks `generation` and
+/// `stop_reason`.
+pub fn parse_stream_chunk(provider: &str, chunk: &[u8]) -> Result<StreamChunk, Error> {
+    let event: serde_json::Value = serde_json::from_slice(chunk).with_context(|| {
+        format!(
+            "Bedrock streamed a chunk that is not valid JSON: {}",
+            response_excerpt(&String::from_utf8_lossy(chunk))
+        )
+    })?;
+    let text = |value: &serde_json::Value| value.as_str().filter(|text| !text.is_empty()).map(str::to_string);
+
+    Ok(match provider {
+        "titan" => StreamChunk {
+            text: text(&event["outputText"]),
+            stop_reason: event["completionReason"]
+                .as_str()
+                .map(|reason| titan_stop_reason(Some(reason)).to_string()),
+        },
+        "meta" => StreamChunk {
+            text: text(&event["generation"]),
+            stop_reason: event["stop_reason"]
+                .as_str()
+                .map(|reason| llama_stop_reason(Some(reason)).to_string()),
+        },
+        _ => match event["type"].as_str() {
+            Some("content_block_delta") if event["delta"]["type"] == "text_delta" => StreamChunk {
+                text: text(&event["delta"]["text"]),
+                stop_reason: None,
+            },
+            Some("message_delta") => StreamChunk {
+                text: None,
+                stop_reason: event["delta"]["stop_reason"].as_str().map(str::to_string),
+            },
+            _ => StreamChunk::default(),
+        },
+    })
+}
+
+/// Shortens a response body for error messages
+fn response_excerpt(response_body: &str) -> String {
+    let body = response_body.trim();
+    if body.is_empty() {
+        "(empty body)".to_string()
+    } else if body.chars().count() > RESPONSE_EXCERPT_CHARS {
+        format!("{}…", body.chars().take(RESPONSE_EXCERPT_CHARS).collect::<String>())
+    } else {
+        body.to_string()
+    }
+}
+
+/// Collects the text of an InvokeModel (Anthropic Messages) response
+///
+/// # Arguments
+///
+/// * `response_json` - The parsed response body
+///
+/// # Returns
+///
+/// A Result containing the text of all `text` content blocks, concatenated in order, or
+/// an error listing the block types when there is no text at all
+///
+/// Other block types, such as `thinking`, `redacted_thinking` or `tool_use`, are
+/// skipped, so responses of models with extended thinking or tools enabled aren't cut off
+/// at the first block.
+pub fn response_text(response_json: &serde_json::Value) -> Result<String, Error> {
+    let content = response_json["content"].as_array().map_or(&[][..], Vec::as_slice);
+
+    let mut text = String::new();
+    let mut found = false;
+    for block in content {
+        match (block["type"].as_str(), block["text"].as_str()) {
+            (Some("text") | None, Some(block_text)) => {
+                text.push_str(block_text);
+                found = true;
+            }
+            (block_type, _) => log::debug!("Skipping {} content block", block_type.unwrap_or("unknown")),
+        }
+    }
+
+    if !found {
+        let block_types: Vec<&str> = content
+            .iter()
+            .map(|block| block["type"].as_str().unwrap_or("unknown"))
+            .collect();
+        return Err(anyhow!(
+            "The model returned no text (content blocks: [{}])",
+            block_types.join(", ")
+        ));
+    }
+
+    Ok(text)
+}
+
+/// Takes whether a model response was cut off at `model.max_tokens`
+///
+/// # Returns
+///
+/// True when a response was truncated since the last call
+///
+/// The text of a truncated response is still used, so the caller can warn that the
+/// summary may be incomplete.
+pub fn take_truncated() -> bool {
+    TRUNCATED.swap(false, Ordering::SeqCst)
+}
+
+/// Checks why the model stopped generating
+///
+/// # Arguments
+///
+/// * `stop_reason` - The `stop_reason` of an InvokeModel response, or the Converse
+///   `stopReason` (both use the same snake_case names)
+///
+/// # Returns
+///
+/// An error describing the stop reason when no usable text can be expected
+///
+/// `end_turn` and `stop_sequence` are normal. `max_tokens` returns the truncated text
+/// and is reported by `take_truncated()`. Content filtering, guardrails, tool use and refusals are errors, so
+/// they aren't mistaken for an empty summary.
+fn check_stop_reason(stop_reason: &str) -> Result<(), Error> {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => Ok(()),
+        "max_tokens" => {
+            log::warn!("The response was cut off at model.max_tokens");
+            TRUNCATED.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        "content_filtered" => Err(anyhow!(
+            "The response was blocked by the model's content filter (stop reason: content_filtered)"
+        )),
+        "guardrail_intervened" => Err(anyhow!(
+            "A Bedrock guardrail blocked the response (stop reason: guardrail_intervened)"
+        )),
+        "refusal" => Err(anyhow!("The model declined to respond (stop reason: refusal)")),
+        "tool_use" | "malformed_tool_use" => Err(anyhow!(
+            "The model stopped to call a tool instead of answering (stop reason: {})",
+            stop_reason
+        )),
+        "model_context_window_exceeded" => Err(anyhow!(
+            "The prompt is too long for the model's context window (stop reason: model_context_window_exceeded)"
+        )),
+        other => Err(anyhow!("The model stopped unexpectedly (stop reason: {})", other)),
+    }
+}
+
+/// Background task that keeps the Bedrock connection warm, stopped when dropped
+pub struct KeepWarm {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl KeepWarm {
+    /// Starts pinging the configured model in the background
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - AWS SDK configuration
+    /// * `settings` - Application settings containing the model id
+    /// * `interval` - Time between pings
+    ///
+    /// # Returns
+    ///
+    /// A handle that stops the pings when dropped
+    ///
+    /// Each ping is a one-token Converse request, so the cost is negligible. The first ping
+    /// is sent after one interval. Failures are logged and otherwise ignored.
+    pub fn start(config: &SdkConfig, settings: &Settings, interval: Duration) -> Self {
+        let client = bedrock_client(config, settings);
+        let model_id = settings.model.model_id.clone();
+
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let message = match Message::builder()
+                    .role(ConversationRole::User)
+                    .content(ContentBlock::Text("Hi".to_string()))
+                    .build()
+                {
+                    Ok(message) => message,
+                    Err(_) => return,
+                };
+                let result = client
+                    .converse()
+                    .model_id(&model_id)
+                    .messages(message)
+                    .inference_config(InferenceConfiguration::builder().max_tokens(1).build())
+                    .send()
+                    .await;
+                match result {
+                    Ok(_) => log::debug!("Bedrock keep-warm ping sent"),
+                    Err(err) => log::warn!("Bedrock keep-warm ping failed: {}", err),
+                }
+            }
+        });
+
+        Self { handle }
+    }
+}
+
+impl Drop for KeepWarm {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Extracts the summary part of a model response
+///
+/// # Arguments
+///
+/// * `raw_response` - The text returned by the model
+///
+/// # Returns
+///
+/// The trimmed summary text
+///
+/// Removes any transcript that might be included before a "Summary:" marker.
+fn extract_summary(raw_response: &str) -> String {
+    if raw_response.contains("Summary:") {
+        // If the response contains "Summary:", extract everything after it
+        raw_response
+            .split("Summary:")
+            .nth(1)
+            .unwrap_or(raw_response)
+            .trim()
+            .to_string()
+    } else {
+        raw_response.trim().to_string()
+    }
+}
+
+/// Builds the Converse API inference configuration from the model settings
+///
+/// # Arguments
+///
+/// * `settings` - Application settings loaded from config.toml
+///
+/// # Returns
+///
+/// The `inferenceConfig` for a Converse request
+///
+/// Maps `model.max_tokens`, `model.temperature` and `model.top_p` onto the
+/// Converse `maxTokens`, `temperature` and `topP` fields. Unset values are left
+/// out so the model defaults apply.
+pub fn converse_inference_config(settings: &Settings) -> InferenceConfiguration {
+    let mut inference_config = InferenceConfiguration::builder();
+
+    if let Some(max_tokens) = settings.model.max_tokens {
+        inference_config = inference_config.max_tokens(max_tokens as i32);
+    }
+    if let Some(temperature) = settings.model.temperature {
+        inference_config = inference_config.temperature(temperature as f32);
+    }
+    if let Some(top_p) = settings.model.top_p {
+        inference_config = inference_config.top_p(top_p as f32);
+    }
+
+    inference_config.build()
+}
+
+/// Builds the model-specific request fields that Converse doesn't model directly
+///
+/// # Arguments
+///
+/// * `settings` - Application settings containing `model.provider` and `model.top_k`
+///
+/// # Returns
+///
+/// The `additionalModelRequestFields` document, if any apply to the model
+///
+/// `top_k` isn't part of the Converse `inferenceConfig` and only some model
+/// families accept it, so it's passed through when `model.provider` is "anthropic".
+/// The provider setting is used rather than the model id, which may be an inference
+/// profile or ARN that doesn't name the model family.
+pub fn converse_additional_fields(settings: &Settings) -> Option<Document> {
+    if settings.model.provider != "anthropic" {
+        return None;
+    }
+
+    let top_k = settings.model.top_k?;
+    Some(Document::Object(HashMap::from([(
+        "top_k".to_string(),
+        Document::Number(Number::PosInt(top_k.max(0) as u64)),
+    )])))
+}
+
+/// Sends a prompt through the Bedrock Converse API and returns the response text
+///
+/// # Arguments
+///
+/// * `client` - Bedrock runtime client
+/// * `settings` - Application settings loaded from config.toml
+/// * `prompt` - The full user prompt
+/// * `max_tokens` - Optional override for `model.max_tokens`
+/// * `on_retry` - Called with the number of the next attempt when a request is throttled
+///
+/// # Returns
+///
+/// A Result containing the concatenated text of the response message or an error
+async fn converse_text(
+    client: &Client,
+    settings: &Settings,
+    prompt: &str,
+    max_tokens: Option<i64>,
+    on_retry: impl FnMut(u32),
+) -> Result<String, Error> {
+    let model_id = &settings.model.model_id;
+
+    let message = Message::builder()
+        .role(ConversationRole::User)
+        .content(ContentBlock::Text(prompt.to_string()))
+        .build()?;
+
+    let mut inference_config = converse_inference_config(settings);
+    if let Some(max_tokens) = max_tokens {
+        inference_config.max_tokens = Some(max_tokens as i32);
+    }
+
+    let mut request = client
+        .converse()
+        .model_id(model_id)
+        .messages(message)
+        .inference_config(inference_config);
+
+    if !settings.anthropic.system.is_empty() {
+        request = request.system(SystemContentBlock::Text(settings.anthropic.system.clone()));
+    }
+    let additional_fields = converse_additional_fields(settings);
+    if let Some(fields) = &additional_fields {
+        request = request.additional_model_request_fields(fields.clone());
+    }
+
+    let system = if settings.anthropic.system.is_empty() {
+        json!([])
+    } else {
+        json!([{ "text": settings.anthropic.system }])
+    };
+    dump_request(json!({
+        "api": "converse",
+        "modelId": model_id,
+        "messages": [{ "role": "user", "content": [{ "text": prompt }] }],
+        "system": system,
+        "inferenceConfig": {
+            "maxTokens": request.get_inference_config().as_ref().and_then(|c| c.max_tokens),
+            "temperature": settings.model.temperature,
+            "topP": settings.model.top_p,
+        },
+        "additionalModelRequestFields": additional_fields.map(|_| json!({ "top_k": settings.model.top_k })),
+    }))?;
+
+    let output = aws_utils::retry_throttled("Converse", settings.model.max_retries, on_retry, || request.clone().send())
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    check_stop_reason(output.stop_reason().as_str())?;
+
+    match output.output() {
+        Some(ConverseOutput::Message(message)) => {
+            let text: Vec<&String> = message
+                .content()
+                .iter()
+                .filter_map(|block| block.as_text().ok())
+                .collect();
+            if text.is_empty() {
+                return Err(anyhow!(
+                    "The model returned no text ({} non-text content blocks)",
+                    message.content().len()
+                ));
+            }
+            Ok(text.into_iter().map(String::as_str).collect())
+        }
+        _ => Err(anyhow!("Converse response did not contain a message")),
     }
 }
\ No newline at end of file
@@ -9,16 +9,35 @@
 //! and provides a simple interface for converting audio files to text. It handles various
 //! audio formats and supports different language options.
 //!
+//! ## Speakers and Channels
+//! By default speakers are told apart with speaker diarization (`spk_0`, `spk_1`, ...),
+//! for up to `transcribe.max_speakers` speakers (10 unless set, or `--speakers <N>`).
+//! For multi-track recordings where each participant has their own audio channel,
+//! channel identification can be used instead, labelling each line by channel
+//! (`ch_0`, `ch_1`, ...).
+//!
+//! ## Transcript Formatting
+//! Turning a Transcribe result into the transcript is kept free of I/O:
+//! `format_transcript()` takes the parsed result JSON and returns the labeled
+//! transcript, so it can be tested against saved results (see `tests/fixtures`).
+//! `format_transcript_as()` breaks the turns further, into paragraphs at long pauses
+//! or into sentences (`--transcript-format`).
+//!
+//! ## Timed Transcripts
+//! The raw Transcribe JSON is kept alongside the plain transcript, so features that need
+//! word timings (such as chapter detection) can render a timed transcript where each
+//! speaker turn starts with an `[HH:MM:SS]` timestamp.
+//!
 //! ## Usage
 //! This module is typically used by the main application to convert audio files to text
 //! before summarization. It requires AWS credentials and an S3 bucket to store the audio file.
 //!
 //! ## Spinner Thread Management
 //!
-//! This module imports the `SPINNER_STOPPED` flag from the output module to ensure
-//! proper spinner thread management. When updating or stopping spinners, it checks
-//! this flag to prevent stopping a spinner thread more than once, which would cause
-//! a panic.
+//! `transcribe_audio()` shows the job progress with `output::update_spinner()`, which
+//! does nothing once the `SPINNER_STOPPED` flag is set, so a late status poll can't
+//! start a new spinner thread. `run_transcription_job()` takes a progress callback
+//! instead of a spinner and never touches one.
 
 use aws_config::SdkConfig;
 use aws_sdk_transcribe::types::{
@@ -28,12 +47,282 @@ use aws_sdk_transcribe::Client;
 
 use anyhow::{anyhow, bail, Context, Error};
 use infer::get_from_path;
+use serde::Serialize;
 use serde_json::Value;
-use spinoff::{spinners, Spinner};
+use spinoff::Spinner;
+use std::collections::HashMap;
 use std::path::Path;
-use tokio::time::{sleep, Duration};
+use tokio::time::sleep;
 use uuid::Uuid;
 
+use crate::{aws_utils, output};
+use crate::settings::TranscribeSettings;
+
+/// File extensions of the media formats Amazon Transcribe accepts
+///
+/// The default for `input.extensions`, the files picked up from a directory input.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["amr", "flac", "m4a", "mp3", "mp4", "ogg", "opus", "wav", "webm"];
+
+/// Language codes supported by Amazon Transcribe batch transcription, with their names
+///
+/// This list drives both `--language-code` validation and the `list-languages` command.
+/// See https://docs.aws.amazon.com/transcribe/latest/dg/supported-languages.html
+pub const SUPPORTED_LANGUAGES: &[(&str, &str)] = &[
+    ("ab-GE", "Abkhaz"),
+    ("af-ZA", "Afrikaans"),
+    ("ar-AE", "Arabic, Gulf"),
+    ("ar-SA", "Arabic, Modern Standard"),
+    ("hy-AM", "Armenian"),
+    ("ast-ES", "Asturian"),
+    ("az-AZ", "Azerbaijani"),
+    ("ba-RU", "Bashkir"),
+    ("eu-ES", "Basque"),
+    ("be-BY", "Belarusian"),
+    ("bn-IN", "Bengali"),
+    ("bs-BA", "Bosnian"),
+    ("bg-BG", "Bulgarian"),
+    ("ca-ES", "Catalan"),
+    ("ckb-IR", "Central Kurdish, Iran"),
+    ("ckb-IQ", "Central Kurdish, Iraq"),
+    ("zh-CN", "Chinese, Simplified"),
+    ("zh-TW", "Chinese, Traditional"),
+    ("hr-HR", "Croatian"),
+    ("cs-CZ", "Czech"),
+    ("da-DK", "Danish"),
+    ("nl-NL", "Dutch"),
+    ("en-AU", "English, Australian"),
+    ("en-GB", "English, British"),
+    ("en-IN", "English, Indian"),
+    ("en-IE", "English, Irish"),
+    ("en-NZ", "English, New Zealand"),
+    ("en-AB", "English, Scottish"),
+    ("en-ZA", "English, South African"),
+    ("en-US", "English, US"),
+    ("en-WL", "English, Welsh"),
+    ("et-ET", "Estonian"),
+    ("fa-IR", "Farsi"),
+    ("fi-FI", "Finnish"),
+    ("fr-FR", "French"),
+    ("fr-CA", "French, Canadian"),
+    ("gl-ES", "Galician"),
+    ("ka-GE", "Georgian"),
+    ("de-DE", "German"),
+    ("de-CH", "German, Swiss"),
+    ("el-GR", "Greek"),
+    ("gu-IN", "Gujarati"),
+    ("ha-NG", "Hausa"),
+    ("he-IL", "Hebrew"),
+    ("hi-IN", "Hindi"),
+    ("hu-HU", "Hungarian"),
+    ("is-IS", "Icelandic"),
+    ("id-ID", "Indonesian"),
+    ("it-IT", "Italian"),
+    ("ja-JP", "Japanese"),
+    ("kab-DZ", "Kabyle"),
+    ("kn-IN", "Kannada"),
+    ("kk-KZ", "Kazakh"),
+    ("rw-RW", "Kinyarwanda"),
+    ("ko-KR", "Korean"),
+    ("ky-KG", "Kyrgyz"),
+    ("lv-LV", "Latvian"),
+    ("lt-LT", "Lithuanian"),
+    ("lg-IN", "Luganda"),
+    ("mk-MK", "Macedonian"),
+    ("ms-MY", "Malay"),
+    ("ml-IN", "Malayalam"),
+    ("mt-MT", "Maltese"),
+    ("mr-IN", "Marathi"),
+    ("mhr-RU", "Meadow Mari"),
+    ("mn-MN", "Mongolian"),
+    ("no-NO", "Norwegian Bokmål"),
+    ("or-IN", "Odia/Oriya"),
+    ("ps-AF", "Pashto"),
+    ("pl-PL", "Polish"),
+    ("pt-PT", "Portuguese"),
+    ("pt-BR", "Portuguese, Brazilian"),
+    ("pa-IN", "Punjabi"),
+    ("ro-RO", "Romanian"),
+    ("ru-RU", "Russian"),
+    ("sr-RS", "Serbian"),
+    ("si-LK", "Sinhala"),
+    ("sk-SK", "Slovak"),
+    ("sl-SI", "Slovenian"),
+    ("so-SO", "Somali"),
+    ("es-ES", "Spanish"),
+    ("es-US", "Spanish, US"),
+    ("su-ID", "Sundanese"),
+    ("sw-KE", "Swahili, Kenya"),
+    ("sw-BI", "Swahili, Burundi"),
+    ("sw-RW", "Swahili, Rwanda"),
+    ("sw-TZ", "Swahili, Tanzania"),
+    ("sw-UG", "Swahili, Uganda"),
+    ("sv-SE", "Swedish"),
+    ("tl-PH", "Tagalog/Filipino"),
+    ("ta-IN", "Tamil"),
+    ("tt-RU", "Tatar"),
+    ("te-IN", "Telugu"),
+    ("th-TH", "Thai"),
+    ("tr-TR", "Turkish"),
+    ("uk-UA", "Ukrainian"),
+    ("ug-CN", "Uyghur"),
+    ("uz-UZ", "Uzbek"),
+    ("vi-VN", "Vietnamese"),
+    ("cy-WL", "Welsh"),
+    ("wo-SN", "Wolof"),
+    ("zu-ZA", "Zulu"),
+];
+
+/// Validates a language code against the supported languages
+///
+/// # Arguments
+///
+/// * `language_code` - Language code for transcription (e.g., "en-US")
+///
+/// # Returns
+///
+/// A Result containing the matching Transcribe `LanguageCode` or an error
+pub fn parse_language_code(language_code: &str) -> Result<LanguageCode, Error> {
+    if !SUPPORTED_LANGUAGES.iter().any(|(code, _)| *code == language_code) {
+        bail!(
+            "\nUnsupported language code: {}. Run `distill-cli list-languages` to see the supported codes.",
+            language_code
+        );
+    }
+
+    Ok(LanguageCode::from(language_code))
+}
+
+/// Loads a mapping of input file names to language codes
+///
+/// # Arguments
+///
+/// * `path` - Path to a CSV (`filename,language_code` per line) or JSON object file
+///
+/// # Returns
+///
+/// A Result containing the file name to language code map or an error
+///
+/// Files ending in `.json` are read as a JSON object; anything else is read as CSV,
+/// where blank lines, `#` comments and a `filename,language_code` header are skipped.
+/// Every language code is validated with `parse_language_code()` up front, so a typo
+/// fails the run before anything is uploaded.
+pub fn load_language_map(path: &Path) -> Result<HashMap<String, String>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read language map {}", path.display()))?;
+
+    let map: HashMap<String, String> = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Language map {} is not a JSON object of strings", path.display()))?
+    } else {
+        let mut map = HashMap::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (file_name, code) = line.split_once(',').ok_or_else(|| {
+                anyhow!("Line {} of {} is not `filename,language_code`", line_number + 1, path.display())
+            })?;
+            let (file_name, code) = (file_name.trim(), code.trim());
+            if line_number == 0 && code == "language_code" {
+                continue;
+            }
+            map.insert(file_name.to_string(), code.to_string());
+        }
+        map
+    };
+
+    for (file_name, code) in &map {
+        parse_language_code(code).with_context(|| format!("Invalid language code for {}", file_name))?;
+    }
+
+    Ok(map)
+}
+
+/// Looks up the language code for an input file in a language map
+///
+/// # Arguments
+///
+/// * `language_map` - Map loaded by `load_language_map()`
+/// * `input_file` - Path of the input audio file
+/// * `default_code` - Language code to use when the file is not in the map
+///
+/// # Returns
+///
+/// The mapped language code, matched on the full path first and then on the file name
+pub fn language_for_file<'a>(
+    language_map: &'a HashMap<String, String>,
+    input_file: &str,
+    default_code: &'a str,
+) -> &'a str {
+    let file_name = Path::new(input_file)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    language_map
+        .get(input_file)
+        .or_else(|| language_map.get(&file_name))
+        .map(String::as_str)
+        .unwrap_or(default_code)
+}
+
+/// Language code that turns on automatic language identification
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Prefix of the names of the transcription jobs started by Distill
+pub const JOB_NAME_PREFIX: &str = "transcription-";
+
+/// Speakers told apart by speaker diarization when `transcribe.max_speakers` is unset
+const DEFAULT_MAX_SPEAKERS: i32 = 10;
+
+/// Range of `MaxSpeakerLabels` accepted by Amazon Transcribe
+const MIN_SPEAKER_LABELS: i32 = 2;
+const MAX_SPEAKER_LABELS: i32 = 30;
+
+/// Language identified by Amazon Transcribe
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdentifiedLanguage {
+    /// The identified language code (e.g., "en-US")
+    pub code: String,
+    /// Transcribe's confidence in the identification (0.0-1.0)
+    pub confidence: f64,
+}
+
+/// Result of a transcription job
+#[derive(Debug, Clone, Default)]
+pub struct Transcription {
+    /// Readable transcript with one speaker (or channel) turn per line
+    pub text: String,
+    /// Raw Amazon Transcribe JSON output, when the job completed
+    pub json: Option<String>,
+    /// The identified language, when the job used automatic language identification
+    pub identified_language: Option<IdentifiedLanguage>,
+}
+
+/// The number of speakers speaker diarization tells apart
+///
+/// # Arguments
+///
+/// * `max_speakers` - `--speakers` or `transcribe.max_speakers`, if set
+///
+/// # Returns
+///
+/// A Result containing the value for `MaxSpeakerLabels` (`DEFAULT_MAX_SPEAKERS` when
+/// unset), or an error when it is outside the 2-30 range Transcribe accepts
+pub fn max_speaker_labels(max_speakers: Option<i32>) -> Result<i32, Error> {
+    let max_speakers = max_speakers.unwrap_or(DEFAULT_MAX_SPEAKERS);
+    if !(MIN_SPEAKER_LABELS..=MAX_SPEAKER_LABELS).contains(&max_speakers) {
+        bail!(
+            "❌ Amazon Transcribe can tell apart {} to {} speakers, not {}",
+            MIN_SPEAKER_LABELS,
+            MAX_SPEAKER_LABELS,
+            max_speakers
+        );
+    }
+    Ok(max_speakers)
+}
+
 /// Transcribes an audio file using Amazon Transcribe
 ///
 /// # Arguments
@@ -42,31 +331,40 @@ use uuid::Uuid;
 /// * `file_path` - Path to the local audio file (used for format detection)
 /// * `s3_uri` - S3 URI where the audio file is stored
 /// * `spinner` - Progress spinner to update during the transcription process
-/// * `language_code` - Language code for transcription (e.g., "en-US")
+/// * `language_code` - Language code for transcription (e.g., "en-US"), or "auto" to let
+///   Transcribe identify the language
+/// * `by_channel` - Transcribe each audio channel separately instead of using speaker diarization
+/// * `polling` - Job status polling intervals (`transcribe.poll_*` settings) and the
+///   most speakers to tell apart (`transcribe.max_speakers`)
 ///
 /// # Returns
 ///
-/// A Result containing the transcribed text or an error
+/// A Result containing the transcription (readable text and raw JSON) or an error
 ///
-/// Detects the audio file format, creates a transcription job with Amazon Transcribe,
-/// polls the job status until completion, and retrieves and processes the transcription results.
+/// Runs `run_transcription_job()`, showing its progress on the spinner.
 pub async fn transcribe_audio(
     config: &SdkConfig,
     file_path: &Path,
     s3_uri: &str,
     spinner: &mut Spinner,
     language_code: &str,
-) -> Result<String, Error> {
-    let client = Client::new(config);
-
-    spinner.update(spinners::Dots7, "Submitting transcription job", None);
-    let job_name = format!("transcription-{}", Uuid::new_v4()); // Generate a unique job name
-    let media = Media::builder().media_file_uri(s3_uri).build();
+    by_channel: bool,
+    polling: &TranscribeSettings,
+) -> Result<Transcription, Error> {
+    // Each step and status poll starts a new line, leaving the previous one visible
+    let mut progress = |message: &str| {
+        output::report("");
+        output::update_spinner(spinner, message.to_string(), output::spinner_color());
+    };
+    run_transcription_job(config, file_path, s3_uri, language_code, by_channel, polling, &mut progress).await
+}
 
+/// Detects the media format of a local audio file from its content
+fn detect_media_format(file_path: &Path) -> Result<MediaFormat, Error> {
     let media_format = match get_from_path(file_path) {
         Ok(Some(kind)) => match kind.mime_type() {
             "audio/amr" => MediaFormat::Amr,
-            "audio/flac" => MediaFormat::Flac,
+            "audio/flac" | "audio/x-flac" => MediaFormat::Flac,
             "audio/m4a" => MediaFormat::M4A,
             "audio/mpeg" => MediaFormat::Mp3,
             "audio/mp4" => MediaFormat::Mp4,
@@ -99,144 +397,117 @@ pub async fn transcribe_audio(
         }
     };
 
-    let settings = Settings::builder()
-        .show_speaker_labels(true)
-        .max_speaker_labels(10)
-        .channel_identification(false)
-        .build();
-
-    let language_code_enum = match language_code {
-        "ab-GE" => LanguageCode::AbGe,
-        "af-ZA" => LanguageCode::AfZa,
-        "ar-AE" => LanguageCode::ArAe,
-        "ar-SA" => LanguageCode::ArSa,
-        "hy-AM" => LanguageCode::HyAm,
-        "ast-ES" => LanguageCode::AstEs,
-        "az-AZ" => LanguageCode::AzAz,
-        "ba-RU" => LanguageCode::BaRu,
-        "eu-ES" => LanguageCode::EuEs,
-        "be-BY" => LanguageCode::BeBy,
-        "bn-IN" => LanguageCode::BnIn,
-        "bs-BA" => LanguageCode::BsBa,
-        "bg-BG" => LanguageCode::BgBg,
-        "ca-ES" => LanguageCode::CaEs,
-        "ckb-IR" => LanguageCode::CkbIr,
-        "ckb-IQ" => LanguageCode::CkbIq,
-        "zh-CN" => LanguageCode::ZhCn,
-        "zh-TW" => LanguageCode::ZhTw,
-        "hr-HR" => LanguageCode::HrHr,
-        "cs-CZ" => LanguageCode::CsCz,
-        "da-DK" => LanguageCode::DaDk,
-        "nl-NL" => LanguageCode::NlNl,
-        "en-AU" => LanguageCode::EnAu,
-        "en-GB" => LanguageCode::EnGb,
-        "en-IN" => LanguageCode::EnIn,
-        "en-IE" => LanguageCode::EnIe,
-        "en-NZ" => LanguageCode::EnNz,
-        "en-AB" => LanguageCode::EnAb,
-        "en-ZA" => LanguageCode::EnZa,
-        "en-US" => LanguageCode::EnUs,
-        "en-WL" => LanguageCode::EnWl,
-        "et-ET" => LanguageCode::EtEt,
-        "fa-IR" => LanguageCode::FaIr,
-        "fi-FI" => LanguageCode::FiFi,
-        "fr-FR" => LanguageCode::FrFr,
-        "fr-CA" => LanguageCode::FrCa,
-        "gl-ES" => LanguageCode::GlEs,
-        "ka-GE" => LanguageCode::KaGe,
-        "de-DE" => LanguageCode::DeDe,
-        "de-CH" => LanguageCode::DeCh,
-        "el-GR" => LanguageCode::ElGr,
-        "gu-IN" => LanguageCode::GuIn,
-        "ha-NG" => LanguageCode::HaNg,
-        "he-IL" => LanguageCode::HeIl,
-        "hi-IN" => LanguageCode::HiIn,
-        "hu-HU" => LanguageCode::HuHu,
-        "is-IS" => LanguageCode::IsIs,
-        "id-ID" => LanguageCode::IdId,
-        "it-IT" => LanguageCode::ItIt,
-        "ja-JP" => LanguageCode::JaJp,
-        "kab-DZ" => LanguageCode::KabDz,
-        "kn-IN" => LanguageCode::KnIn,
-        "kk-KZ" => LanguageCode::KkKz,
-        "rw-RW" => LanguageCode::RwRw,
-        "ko-KR" => LanguageCode::KoKr,
-        "ky-KG" => LanguageCode::KyKg,
-        "lv-LV" => LanguageCode::LvLv,
-        "lt-LT" => LanguageCode::LtLt,
-        "lg-IN" => LanguageCode::LgIn,
-        "mk-MK" => LanguageCode::MkMk,
-        "ms-MY" => LanguageCode::MsMy,
-        "ml-IN" => LanguageCode::MlIn,
-        "mt-MT" => LanguageCode::MtMt,
-        "mr-IN" => LanguageCode::MrIn,
-        "mhr-RU" => LanguageCode::MhrRu,
-        "mn-MN" => LanguageCode::MnMn,
-        "no-NO" => LanguageCode::NoNo,
-        "or-IN" => LanguageCode::OrIn,
-        "ps-AF" => LanguageCode::PsAf,
-        "pl-PL" => LanguageCode::PlPl,
-        "pt-PT" => LanguageCode::PtPt,
-        "pt-BR" => LanguageCode::PtBr,
-        "pa-IN" => LanguageCode::PaIn,
-        "ro-RO" => LanguageCode::RoRo,
-        "ru-RU" => LanguageCode::RuRu,
-        "sr-RS" => LanguageCode::SrRs,
-        "si-LK" => LanguageCode::SiLk,
-        "sk-SK" => LanguageCode::SkSk,
-        "sl-SI" => LanguageCode::SlSi,
-        "so-SO" => LanguageCode::SoSo,
-        "es-ES" => LanguageCode::EsEs,
-        "es-US" => LanguageCode::EsUs,
-        "su-ID" => LanguageCode::SuId,
-        "sw-KE" => LanguageCode::SwKe,
-        "sw-BI" => LanguageCode::SwBi,
-        "sw-RW" => LanguageCode::SwRw,
-        "sw-TZ" => LanguageCode::SwTz,
-        "sw-UG" => LanguageCode::SwUg,
-        "sv-SE" => LanguageCode::SvSe,
-        "tl-PH" => LanguageCode::TlPh,
-        "ta-IN" => LanguageCode::TaIn,
-        "tt-RU" => LanguageCode::TtRu,
-        "te-IN" => LanguageCode::TeIn,
-        "th-TH" => LanguageCode::ThTh,
-        "tr-TR" => LanguageCode::TrTr,
-        "uk-UA" => LanguageCode::UkUa,
-        "ug-CN" => LanguageCode::UgCn,
-        "uz-UZ" => LanguageCode::UzUz,
-        "vi-VN" => LanguageCode::ViVn,
-        "cy-WL" => LanguageCode::CyWl,
-        "wo-SN" => LanguageCode::WoSn,
-        "zu-ZA" => LanguageCode::ZuZa,
-
-        // Add other language codes as needed
-        _ => {
-            bail!("\nUnsupported language code: {}", language_code);
-        }
+    Ok(media_format)
+}
+
+/// Maps a file extension to a Transcribe media format
+fn media_format_from_extension(file_path: &Path) -> Option<MediaFormat> {
+    let extension = file_path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "amr" => Some(MediaFormat::Amr),
+        "flac" => Some(MediaFormat::Flac),
+        "m4a" => Some(MediaFormat::M4A),
+        "mp3" => Some(MediaFormat::Mp3),
+        "mp4" => Some(MediaFormat::Mp4),
+        "ogg" | "opus" => Some(MediaFormat::Ogg),
+        "wav" => Some(MediaFormat::Wav),
+        "webm" => Some(MediaFormat::Webm),
+        _ => None,
+    }
+}
+
+/// Progress message shown while a throttled Transcribe request waits for its next attempt
+fn retry_message(attempt: u32) -> String {
+    format!("Retrying (attempt {})...", attempt)
+}
+
+/// Runs an Amazon Transcribe job for an uploaded audio file and waits for the result
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration
+/// * `file_path` - Path to the local audio file (used for format detection), or the
+///   `s3://` URI of an object that was never local
+/// * `s3_uri` - S3 URI where the audio file is stored
+/// * `language_code` - Language code for transcription (e.g., "en-US"), or "auto" to let
+///   Transcribe identify the language
+/// * `by_channel` - Transcribe each audio channel separately instead of using speaker diarization
+/// * `polling` - Job status polling intervals (`transcribe.poll_*` settings) and the
+///   most speakers to tell apart (`transcribe.max_speakers`)
+/// * `progress` - Called with a status message at each step and after each status poll
+///
+/// # Returns
+///
+/// A Result containing the transcription (readable text and raw JSON) or an error
+///
+/// Detects the audio file format, creates a transcription job with Amazon Transcribe,
+/// polls the job status until completion, and retrieves and processes the transcription results.
+/// Doesn't need a spinner, so several jobs can run concurrently (`--parallel-transcribe`).
+pub async fn run_transcription_job(
+    config: &SdkConfig,
+    file_path: &Path,
+    s3_uri: &str,
+    language_code: &str,
+    by_channel: bool,
+    polling: &TranscribeSettings,
+    progress: &mut (dyn FnMut(&str) + Send),
+) -> Result<Transcription, Error> {
+    let client = Client::new(config);
+
+    progress("Submitting transcription job");
+    let job_name = format!("{}{}", JOB_NAME_PREFIX, Uuid::new_v4()); // Generate a unique job name
+    let media = Media::builder().media_file_uri(s3_uri).build();
+
+    // Objects already in S3 can't be inspected locally; go by the extension, or let
+    // Transcribe detect the format
+    let media_format = if aws_utils::parse_s3_uri(&file_path.to_string_lossy()).is_some() {
+        media_format_from_extension(file_path)
+    } else {
+        Some(detect_media_format(file_path)?)
+    };
+
+    // Speaker diarization and channel identification can't be combined in one job
+    let settings = if by_channel {
+        Settings::builder().channel_identification(true).build()
+    } else {
+        Settings::builder()
+            .show_speaker_labels(true)
+            .max_speaker_labels(max_speaker_labels(polling.max_speakers)?)
+            .channel_identification(false)
+            .build()
     };
 
-    let _job = client
+    let mut request = client
         .start_transcription_job()
         .transcription_job_name(&job_name)
-        .language_code(language_code_enum)
-        .media_format(media_format)
+        .set_media_format(media_format)
         .media(media)
-        .settings(settings)
-        .send()
-        .await?;
-
-    println!();
-    spinner.update(
-        spinners::Dots7,
-        "Waiting for transcription to complete...",
-        None,
-    );
-    let mut poll_interval = Duration::from_secs(5);
-    let mut job_details = client
-        .get_transcription_job()
-        .transcription_job_name(&job_name)
-        .send()
-        .await?;
+        .settings(settings);
+    request = if language_code == AUTO_LANGUAGE {
+        request.identify_language(true)
+    } else {
+        request.language_code(parse_language_code(language_code)?)
+    };
+    // Throttled requests are retried with backoff; the progress message shows the attempt
+    let max_retries = polling.max_retries;
+    aws_utils::retry_throttled(
+        "StartTranscriptionJob",
+        max_retries,
+        |attempt| progress(&retry_message(attempt)),
+        || request.clone().send(),
+    )
+    .await?;
+
+    progress("Waiting for transcription to complete...");
+    let mut polls = 0;
+    let status_request = client.get_transcription_job().transcription_job_name(&job_name);
+    let mut job_details = aws_utils::retry_throttled(
+        "GetTranscriptionJob",
+        max_retries,
+        |attempt| progress(&retry_message(attempt)),
+        || status_request.clone().send(),
+    )
+    .await?;
 
     while let Some(status) = job_details
         .transcription_job
@@ -245,14 +516,16 @@ pub async fn transcribe_audio(
     {
         match status {
             TranscriptionJobStatus::InProgress => {
-                sleep(poll_interval).await;
-                job_details = client
-                    .get_transcription_job()
-                    .transcription_job_name(&job_name)
-                    .send()
-                    .await?;
-                println!();
-                poll_interval *= 2; // Exponential backoff to show progress
+                sleep(polling.poll_interval(polls)).await;
+                job_details = aws_utils::retry_throttled(
+                    "GetTranscriptionJob",
+                    max_retries,
+                    |attempt| progress(&retry_message(attempt)),
+                    || status_request.clone().send(),
+                )
+                .await?;
+                progress("Waiting for transcription to complete...");
+                polls += 1; // Back off up to transcribe.poll_max_secs
             }
             TranscriptionJobStatus::Completed => {
                 break;
@@ -270,33 +543,196 @@ pub async fn transcribe_audio(
         .and_then(|j| j.transcription_job_status.as_ref())
     {
         Some(TranscriptionJobStatus::Completed) => {
+            let identified_language = job_details.transcription_job.as_ref().and_then(|job| {
+                Some(IdentifiedLanguage {
+                    code: job.language_code()?.as_str().to_string(),
+                    confidence: job.identified_language_score()? as f64,
+                })
+            });
             if let Some(transcript_uri) = job_details
                 .transcription_job
                 .and_then(|j| j.transcript)
                 .and_then(|t| t.transcript_file_uri)
             {
-                spinner.update(spinners::Dots7, "Transcription job complete", None);
+                progress("Transcription job complete");
                 let res = reqwest::get(transcript_uri).await?;
                 let body = res.text().await?;
-                let final_transcript = convert_transcribe_json(&body)?;
-                Ok(final_transcript)
+                let final_transcript = if by_channel {
+                    convert_channel_transcribe_json(&body)?
+                } else {
+                    convert_transcribe_json(&body)?
+                };
+                Ok(Transcription {
+                    text: final_transcript,
+                    json: Some(body),
+                    identified_language,
+                })
             } else {
-                println!("Transcript file URI is missing.");
-                Ok("Transcript file URI is missing.".to_string())
+                output::report("Transcript file URI is missing.");
+                Ok(Transcription {
+                    text: "Transcript file URI is missing.".to_string(),
+                    ..Default::default()
+                })
             }
         }
         Some(TranscriptionJobStatus::Failed) => {
             if let Some(reason) = job_details.transcription_job.and_then(|j| j.failure_reason) {
-                println!("Transcription job failed: {}", reason);
+                output::report(format!("Transcription job failed: {}", reason));
+                if by_channel {
+                    output::report("Channel identification requires multi-channel audio. If the file is mono, run again without --by-channel.");
+                }
             } else {
-                println!("Transcription job failed for an unknown reason.");
+                output::report("Transcription job failed for an unknown reason.");
+            }
+            Ok(Transcription {
+                text: "Transcription job failed.".to_string(),
+                ..Default::default()
+            })
+        }
+        _ => Ok(Transcription {
+            text: "Job ended with an unexpected status or status could not be determined.".to_string(),
+            ..Default::default()
+        }),
+    }
+}
+
+/// A completed or failed transcription job, as listed by `finished_jobs()`
+#[derive(Debug, Clone)]
+pub struct FinishedJob {
+    /// Name of the job
+    pub name: String,
+    /// Final status (COMPLETED or FAILED)
+    pub status: String,
+    /// When the job was created
+    pub created: chrono::DateTime<chrono::Utc>,
+}
+
+/// Lists finished transcription jobs whose name starts with a prefix
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration (the jobs of its region are listed)
+/// * `prefix` - Job name prefix, e.g. `JOB_NAME_PREFIX`
+/// * `older_than` - Only list jobs created at least this long ago
+///
+/// # Returns
+///
+/// A Result containing the matching jobs, oldest first, or an error
+///
+/// Jobs that are queued or still running are never listed, so a cleanup can't pull the
+/// result out from under a run in progress.
+pub async fn finished_jobs(
+    config: &SdkConfig,
+    prefix: &str,
+    older_than: std::time::Duration,
+) -> Result<Vec<FinishedJob>, Error> {
+    let client = Client::new(config);
+    let cutoff = chrono::Utc::now() - chrono::Duration::from_std(older_than).context("--older-than is too large")?;
+
+    let mut jobs = Vec::new();
+    let mut next_token = None;
+    loop {
+        let page = client
+            .list_transcription_jobs()
+            .job_name_contains(prefix)
+            .max_results(100)
+            .set_next_token(next_token)
+            .send()
+            .await
+            .context("Failed to list transcription jobs")?;
+
+        for summary in page.transcription_job_summaries() {
+            let (Some(name), Some(status), Some(created)) = (
+                summary.transcription_job_name(),
+                summary.transcription_job_status(),
+                summary.creation_time(),
+            ) else {
+                continue;
+            };
+            let finished = matches!(status, TranscriptionJobStatus::Completed | TranscriptionJobStatus::Failed);
+            let Some(created) = chrono::DateTime::from_timestamp(created.secs(), created.subsec_nanos()) else {
+                continue;
+            };
+            if name.starts_with(prefix) && finished && created <= cutoff {
+                jobs.push(FinishedJob {
+                    name: name.to_string(),
+                    status: status.as_str().to_string(),
+                    created,
+                });
             }
-            Ok("Transcription job failed.".to_string())
         }
-        _ => Ok(
-            "Job ended with an unexpected status or status could not be determined.".to_string(),
-        ),
+
+        next_token = page.next_token().map(str::to_string);
+        if next_token.is_none() {
+            break;
+        }
     }
+
+    jobs.sort_by_key(|job| job.created);
+    Ok(jobs)
+}
+
+/// Deletes a transcription job and its stored result
+///
+/// # Arguments
+///
+/// * `config` - AWS SDK configuration for the job's region
+/// * `name` - Name of the job
+///
+/// # Returns
+///
+/// A Result indicating success or an error
+pub async fn delete_job(config: &SdkConfig, name: &str) -> Result<(), Error> {
+    Client::new(config)
+        .delete_transcription_job()
+        .transcription_job_name(name)
+        .send()
+        .await
+        .with_context(|| format!("Failed to delete transcription job {}", name))?;
+    Ok(())
+}
+
+/// Reads a saved Amazon Transcribe result JSON file
+///
+/// # Arguments
+///
+/// * `path` - Path to the JSON document Transcribe wrote for a completed job
+///
+/// # Returns
+///
+/// A Result containing the transcription, as if the job had just completed, or an error
+///
+/// Results with channel identification are detected from their `channel_labels`, see
+/// `format_transcript()`.
+pub fn load_transcription(path: &Path) -> Result<Transcription, Error> {
+    let body = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read Transcribe result {}", path.display()))?;
+    let json: Value = serde_json::from_str(&body)
+        .with_context(|| format!("{} is not valid JSON", path.display()))?;
+    if json["results"].is_null() {
+        bail!("{} is not an Amazon Transcribe result (no \"results\" field)", path.display());
+    }
+
+    let text = format_transcript(&json)?;
+
+    // Results of jobs with language identification list the candidate languages
+    let identified_language = json["results"]["language_identification"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|candidate| {
+            Some(IdentifiedLanguage {
+                code: candidate["code"].as_str()?.to_string(),
+                confidence: candidate["score"].as_str()?.parse().ok()?,
+            })
+        })
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+
+    Ok(Transcription {
+        text,
+        json: Some(body),
+        identified_language,
+    })
 }
 
 /// Converts Amazon Transcribe JSON output to a readable text format
@@ -309,24 +745,63 @@ pub async fn transcribe_audio(
 ///
 /// A Result containing formatted transcript text with speaker labels or an error
 ///
-/// Parses the JSON response from Amazon Transcribe, extracts words, punctuation,
-/// and speaker labels, and formats the transcript with speaker labels and proper spacing.
+/// Parses the JSON response and formats it with `format_speaker_transcript()`.
 pub fn convert_transcribe_json(json_string: &str) -> Result<String, Error> {
     let v: Value = serde_json::from_str(json_string).with_context(|| "Failed to parse JSON")?;
+    format_speaker_transcript(&v)
+}
+
+/// Formats a parsed Amazon Transcribe result as a readable transcript
+///
+/// # Arguments
+///
+/// * `result` - The parsed JSON document Transcribe wrote for a completed job
+///
+/// # Returns
+///
+/// A Result containing the transcript with one labeled turn per line or an error
+///
+/// Results with channel identification (a `channel_labels` object) are formatted with
+/// `format_channel_transcript()`, everything else with `format_speaker_transcript()`.
+pub fn format_transcript(result: &Value) -> Result<String, Error> {
+    if result["results"]["channel_labels"].is_object() {
+        format_channel_transcript(result)
+    } else {
+        format_speaker_transcript(result)
+    }
+}
+
+/// Formats a parsed Amazon Transcribe result with speaker labels
+///
+/// # Arguments
+///
+/// * `result` - The parsed JSON document Transcribe wrote for a completed job
+///
+/// # Returns
+///
+/// A Result containing formatted transcript text with speaker labels or an error
+///
+/// Extracts words, punctuation and speaker labels, and starts a new "spk_N: ..." line
+/// whenever the speaker changes. Items without a speaker label of their own are looked
+/// up in `results.speaker_labels.segments` by start time; results without speaker
+/// diarization are attributed to `spk_0`.
+pub fn format_speaker_transcript(result: &Value) -> Result<String, Error> {
+    let items = result["results"]["items"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Missing 'results.items' data"))?;
+    let segments = speaker_segments(result);
 
     let mut final_transcript = String::new();
     let mut current_speaker: Option<String> = None;
     let mut current_text = String::new();
 
-    for item in v["results"]["items"].as_array().unwrap() {
-        match item["type"].as_str().unwrap() {
-            "pronunciation" => {
+    for item in items {
+        match item["type"].as_str() {
+            Some("pronunciation") => {
                 let content = item["alternatives"][0]["content"]
                     .as_str()
                     .ok_or_else(|| anyhow!("Missing pronunciation content data"))?;
-                let speaker_label = item["speaker_label"]
-                    .as_str()
-                    .ok_or_else(|| anyhow!("Missing 'speaker_label' data"))?;
+                let speaker_label = speaker_label(item, &segments);
 
                 if let Some(current_speaker_label) = current_speaker.as_ref() {
                     if current_speaker_label != speaker_label {
@@ -348,7 +823,7 @@ pub fn convert_transcribe_json(json_string: &str) -> Result<String, Error> {
                     current_text = content.to_string();
                 }
             }
-            "punctuation" => {
+            Some("punctuation") => {
                 let content = item["alternatives"][0]["content"]
                     .as_str()
                     .ok_or_else(|| anyhow!("Missing punctuation content data"))?;
@@ -365,4 +840,556 @@ pub fn convert_transcribe_json(json_string: &str) -> Result<String, Error> {
     }
 
     Ok(final_transcript)
-}
\ No newline at end of file
+}
+
+/// Start time, end time and label of each `results.speaker_labels.segments` entry
+fn speaker_segments(result: &Value) -> Vec<(f64, f64, &str)> {
+    result["results"]["speaker_labels"]["segments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|segment| {
+            let time = |key: &str| segment[key].as_str().and_then(|time| time.parse::<f64>().ok());
+            Some((time("start_time")?, time("end_time")?, segment["speaker_label"].as_str()?))
+        })
+        .collect()
+}
+
+/// The speaker of a pronunciation item
+///
+/// # Arguments
+///
+/// * `item` - An entry of `results.items`
+/// * `segments` - The speaker segments from `speaker_segments()`
+///
+/// # Returns
+///
+/// The item's own `speaker_label`, otherwise the label of the segment its start time
+/// falls into, otherwise `spk_0`
+fn speaker_label<'a>(item: &'a Value, segments: &[(f64, f64, &'a str)]) -> &'a str {
+    if let Some(label) = item["speaker_label"].as_str() {
+        return label;
+    }
+    item["start_time"]
+        .as_str()
+        .and_then(|time| time.parse::<f64>().ok())
+        .and_then(|start| {
+            segments
+                .iter()
+                .find(|(segment_start, segment_end, _)| (*segment_start..=*segment_end).contains(&start))
+        })
+        .map_or("spk_0", |(_, _, label)| label)
+}
+
+/// Groups the turns of a labeled transcript by speaker
+///
+/// # Arguments
+///
+/// * `transcript` - Transcript with one "label: text" turn per line
+///
+/// # Returns
+///
+/// One (label, text) pair per speaker, in order of first appearance, with all of the
+/// speaker's turns joined by newlines
+///
+/// Works with both speaker labels (`spk_0`) and channel labels (`ch_0`). Lines without a
+/// label are ignored.
+pub fn speaker_turns(transcript: &str) -> Vec<(String, String)> {
+    let mut speakers: Vec<(String, String)> = Vec::new();
+
+    for line in transcript.lines() {
+        let Some((label, text)) = line.split_once(": ") else {
+            continue;
+        };
+        let (label, text) = (label.trim(), text.trim());
+        if label.is_empty() || label.contains(char::is_whitespace) || text.is_empty() {
+            continue;
+        }
+
+        match speakers.iter_mut().find(|(speaker, _)| speaker == label) {
+            Some((_, turns)) => {
+                turns.push('\n');
+                turns.push_str(text);
+            }
+            None => speakers.push((label.to_string(), text.to_string())),
+        }
+    }
+
+    speakers
+}
+
+/// Converts Amazon Transcribe JSON output to a transcript with timestamps
+///
+/// # Arguments
+///
+/// * `json_string` - The JSON string returned by Amazon Transcribe
+///
+/// # Returns
+///
+/// A Result containing the transcript with one timestamped turn per line or an error
+///
+/// Each line starts with the start time of its first word, e.g.
+/// "[00:12:34] spk_1: Let's move on to the budget." Turns are split on speaker labels,
+/// or on channel labels for channel-identified transcripts.
+pub fn timed_transcript(json_string: &str) -> Result<String, Error> {
+    let v: Value = serde_json::from_str(json_string).with_context(|| "Failed to parse JSON")?;
+
+    let mut timed = String::new();
+    let mut current_label: Option<String> = None;
+
+    for item in v["results"]["items"].as_array().into_iter().flatten() {
+        let content = item["alternatives"][0]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing content data"))?;
+
+        match item["type"].as_str() {
+            Some("pronunciation") => {
+                let label = item["speaker_label"]
+                    .as_str()
+                    .or_else(|| item["channel_label"].as_str())
+                    .unwrap_or("spk_0");
+
+                if current_label.as_deref() == Some(label) {
+                    timed.push(' ');
+                    timed.push_str(content);
+                    continue;
+                }
+
+                let start_time = item["start_time"]
+                    .as_str()
+                    .and_then(|t| t.parse::<f64>().ok())
+                    .ok_or_else(|| anyhow!("Missing 'start_time' data"))?;
+                if current_label.is_some() {
+                    timed.push('\n');
+                }
+                timed.push_str(&format!("[{}] {}: {}", format_timestamp(start_time), label, content));
+                current_label = Some(label.to_string());
+            }
+            Some("punctuation") => timed.push_str(content),
+            _ => {}
+        }
+    }
+    if current_label.is_some() {
+        timed.push('\n');
+    }
+
+    Ok(timed)
+}
+
+/// Calculates the average word confidence of an Amazon Transcribe result
+///
+/// # Arguments
+///
+/// * `json_string` - The JSON string returned by Amazon Transcribe
+///
+/// # Returns
+///
+/// The mean confidence (0.0-1.0) over all spoken words, or None if there are none
+pub fn average_confidence(json_string: &str) -> Option<f64> {
+    let v: Value = serde_json::from_str(json_string).ok()?;
+
+    let confidences: Vec<f64> = v["results"]["items"]
+        .as_array()?
+        .iter()
+        .filter(|item| item["type"].as_str() == Some("pronunciation"))
+        .filter_map(|item| item["alternatives"][0]["confidence"].as_str()?.parse::<f64>().ok())
+        .collect();
+
+    if confidences.is_empty() {
+        None
+    } else {
+        Some(confidences.iter().sum::<f64>() / confidences.len() as f64)
+    }
+}
+
+/// Timing of one spoken word, as saved with `--save-word-timings`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordTiming {
+    /// The word, followed by any punctuation Transcribe placed after it
+    pub word: String,
+    /// Start of the word, in seconds from the beginning of the audio
+    pub start: f64,
+    /// End of the word, in seconds from the beginning of the audio
+    pub end: f64,
+    /// Transcribe's confidence in the word (0.0-1.0)
+    pub confidence: f64,
+}
+
+/// Extracts the timing of every spoken word from an Amazon Transcribe result
+///
+/// # Arguments
+///
+/// * `json_string` - The JSON string returned by Amazon Transcribe
+///
+/// # Returns
+///
+/// A Result containing the words in spoken order or an error
+///
+/// Punctuation items have no timing of their own and are appended to the preceding
+/// word. Works for speaker-labeled and channel-identified results alike.
+pub fn word_timings(json_string: &str) -> Result<Vec<WordTiming>, Error> {
+    let v: Value = serde_json::from_str(json_string).with_context(|| "Failed to parse JSON")?;
+    let items = v["results"]["items"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Missing 'results.items' data"))?;
+
+    let mut words: Vec<WordTiming> = Vec::new();
+    for item in items {
+        let alternative = &item["alternatives"][0];
+        let content = alternative["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing content data"))?;
+        let number = |value: &Value| value.as_str().and_then(|n| n.parse::<f64>().ok());
+
+        match item["type"].as_str() {
+            Some("pronunciation") => {
+                let start = number(&item["start_time"]).ok_or_else(|| anyhow!("Missing 'start_time' data"))?;
+                words.push(WordTiming {
+                    word: content.to_string(),
+                    start,
+                    end: number(&item["end_time"]).unwrap_or(start),
+                    confidence: number(&alternative["confidence"]).unwrap_or_default(),
+                });
+            }
+            Some("punctuation") => {
+                if let Some(word) = words.last_mut() {
+                    word.word.push_str(content);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(words)
+}
+
+/// Formats a time offset in seconds as `HH:MM:SS`
+pub fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, (total % 3600) / 60, total % 60)
+}
+
+/// Converts Amazon Transcribe JSON output with channel identification to readable text
+///
+/// # Arguments
+///
+/// * `json_string` - The JSON string returned by Amazon Transcribe
+///
+/// # Returns
+///
+/// A Result containing formatted transcript text with channel labels or an error
+///
+/// Parses the JSON response and formats it with `format_channel_transcript()`. When the
+/// audio turns out to have a single channel, a warning is printed.
+pub fn convert_channel_transcribe_json(json_string: &str) -> Result<String, Error> {
+    let v: Value = serde_json::from_str(json_string).with_context(|| "Failed to parse JSON")?;
+
+    let channels = v["results"]["channel_labels"]["channels"]
+        .as_array()
+        .map_or(0, Vec::len);
+    if channels < 2 {
+        output::report("⚠️ The audio has a single channel, so --by-channel has no effect. Producing a plain transcript.");
+    }
+
+    format_channel_transcript(&v)
+}
+
+/// Formats a parsed Amazon Transcribe result with channel identification
+///
+/// # Arguments
+///
+/// * `result` - The parsed JSON document Transcribe wrote for a completed job
+///
+/// # Returns
+///
+/// A Result containing formatted transcript text with channel labels or an error
+///
+/// Merges the per-channel items in time order and starts a new line whenever the
+/// active channel changes (e.g. "ch_0: ...", "ch_1: ..."). Without per-channel items
+/// the whole transcript is attributed to `ch_0`.
+pub fn format_channel_transcript(result: &Value) -> Result<String, Error> {
+    let words = channel_words(result)?;
+
+    let mut final_transcript = String::new();
+    let mut current_channel: Option<&str> = None;
+    for word in &words {
+        if current_channel != Some(word.label.as_str()) {
+            if current_channel.is_some() {
+                final_transcript.push('\n');
+            }
+            final_transcript.push_str(&format!("{}: {}", word.label, word.text));
+            current_channel = Some(&word.label);
+        } else {
+            final_transcript.push(' ');
+            final_transcript.push_str(&word.text);
+        }
+    }
+    if current_channel.is_some() {
+        final_transcript.push('\n');
+    }
+
+    Ok(final_transcript)
+}
+
+/// Longest pause between two words of a paragraph, in seconds (`TranscriptFormat::Paragraphs`)
+pub const PARAGRAPH_PAUSE_SECS: f64 = 2.0;
+
+/// How the transcript is broken into lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    /// One line per speaker (or channel) turn
+    #[default]
+    Raw,
+    /// A new paragraph at every turn and every pause of `PARAGRAPH_PAUSE_SECS` or more,
+    /// with a blank line between paragraphs
+    Paragraphs,
+    /// One line per sentence, broken after `.`, `?` and `!`
+    Sentences,
+}
+
+/// Formats a parsed Amazon Transcribe result with the chosen line breaks
+///
+/// # Arguments
+///
+/// * `result` - The parsed JSON document Transcribe wrote for a completed job
+/// * `format` - How to break the transcript into lines
+///
+/// # Returns
+///
+/// A Result containing the transcript or an error
+///
+/// `TranscriptFormat::Raw` is `format_transcript()`. The other formats split the turns
+/// further, repeating the speaker (or channel) label on every line, so the transcript
+/// stays one "label: text" line per block for everything that reads it afterwards.
+pub fn format_transcript_as(result: &Value, format: TranscriptFormat) -> Result<String, Error> {
+    let words = match format {
+        TranscriptFormat::Raw => return format_transcript(result),
+        _ if result["results"]["channel_labels"].is_object() => channel_words(result)?,
+        _ => speaker_words(result)?,
+    };
+
+    let mut blocks: Vec<(&str, String)> = Vec::new();
+    let mut previous: Option<&TimedWord> = None;
+    for word in &words {
+        let new_block = match previous {
+            None => true,
+            Some(previous) if previous.label != word.label => true,
+            Some(previous) => match format {
+                TranscriptFormat::Paragraphs => word.start - previous.end >= PARAGRAPH_PAUSE_SECS,
+                _ => previous.text.ends_with(['.', '?', '!']),
+            },
+        };
+        match blocks.last_mut() {
+            Some((_, text)) if !new_block => {
+                text.push(' ');
+                text.push_str(&word.text);
+            }
+            _ => blocks.push((&word.label, word.text.clone())),
+        }
+        previous = Some(word);
+    }
+
+    let separator = if format == TranscriptFormat::Paragraphs { "\n\n" } else { "\n" };
+    let mut transcript = blocks
+        .iter()
+        .map(|(label, text)| format!("{}: {}", label, text))
+        .collect::<Vec<_>>()
+        .join(separator);
+    if !transcript.is_empty() {
+        transcript.push('\n');
+    }
+
+    Ok(transcript)
+}
+
+/// A spoken word with its timing, label and trailing punctuation
+#[derive(Debug, Clone)]
+struct TimedWord {
+    start: f64,
+    end: f64,
+    label: String,
+    text: String,
+}
+
+/// Collects the words of a speaker-labeled result in order
+fn speaker_words(result: &Value) -> Result<Vec<TimedWord>, Error> {
+    let items = result["results"]["items"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Missing 'results.items' data"))?;
+    let segments = speaker_segments(result);
+    let label = |item: &Value| speaker_label(item, &segments).to_string();
+    collect_timed_words(items, label)
+}
+
+/// Collects the words of all channels of a channel-identified result in time order
+///
+/// Without per-channel items the whole transcript is attributed to `ch_0`.
+fn channel_words(result: &Value) -> Result<Vec<TimedWord>, Error> {
+    let channels = result["results"]["channel_labels"]["channels"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    let mut words = Vec::new();
+    if channels.is_empty() {
+        let items = result["results"]["items"].as_array().map_or(&[][..], Vec::as_slice);
+        words.extend(collect_timed_words(items, |_| "ch_0".to_string())?);
+    } else {
+        for channel in &channels {
+            let label = channel["channel_label"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing 'channel_label' data"))?;
+            let items = channel["items"].as_array().map_or(&[][..], Vec::as_slice);
+            words.extend(collect_timed_words(items, |_| label.to_string())?);
+        }
+    }
+    words.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    Ok(words)
+}
+
+/// Collects timed words, attaching punctuation to the preceding word
+fn collect_timed_words(items: &[Value], label: impl Fn(&Value) -> String) -> Result<Vec<TimedWord>, Error> {
+    let mut words: Vec<TimedWord> = Vec::new();
+
+    for item in items {
+        let content = item["alternatives"][0]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing content data"))?;
+
+        match item["type"].as_str() {
+            Some("pronunciation") => {
+                let time = |field: &str| {
+                    item[field]
+                        .as_str()
+                        .and_then(|t| t.parse::<f64>().ok())
+                        .ok_or_else(|| anyhow!("Missing '{}' data", field))
+                };
+                let start = time("start_time")?;
+                words.push(TimedWord {
+                    start,
+                    end: time("end_time").unwrap_or(start),
+                    label: label(item),
+                    text: content.to_string(),
+                });
+            }
+            Some("punctuation") => {
+                if let Some(word) = words.last_mut() {
+                    word.text.push_str(content);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(words)
+}
+
+/// Longest phrase (in words) considered when collapsing immediate repetitions
+const MAX_REPEAT_PHRASE_WORDS: usize = 4;
+
+/// Words that are commonly doubled on purpose ("I know that that works")
+const INTENDED_REPEATS: &[&str] = &["that", "had"];
+
+/// Removes stutters and filler words from a formatted transcript
+///
+/// # Arguments
+///
+/// * `transcript` - Transcript text as produced by `convert_transcribe_json`
+/// * `filler_words` - Filler words or phrases to strip (e.g., "um", "you know")
+///
+/// # Returns
+///
+/// The cleaned transcript text
+///
+/// Works line by line so speaker labels are preserved. Filler words and phrases are
+/// matched case-insensitively, ignoring surrounding punctuation. Immediately repeated
+/// words or phrases (up to four words long) are collapsed into a single occurrence.
+pub fn dedupe_transcript(transcript: &str, filler_words: &[String]) -> String {
+    let fillers: Vec<Vec<String>> = filler_words
+        .iter()
+        .map(|phrase| phrase.split_whitespace().map(normalize_word).collect::<Vec<_>>())
+        .filter(|phrase| !phrase.is_empty())
+        .collect();
+
+    let mut cleaned = String::new();
+    for line in transcript.lines() {
+        let (label, text) = match line.split_once(": ") {
+            Some((label, text)) if !label.contains(' ') => (Some(label), text),
+            _ => (None, line),
+        };
+
+        let mut words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+        strip_filler_words(&mut words, &fillers);
+        collapse_repeats(&mut words);
+
+        if let Some(label) = label {
+            cleaned.push_str(label);
+            cleaned.push_str(": ");
+        }
+        cleaned.push_str(&words.join(" "));
+        cleaned.push('\n');
+    }
+
+    cleaned
+}
+
+/// Lowercases a word and strips surrounding punctuation for comparison purposes
+fn normalize_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+        .to_lowercase()
+}
+
+/// Removes every occurrence of the given filler phrases from a list of words
+fn strip_filler_words(words: &mut Vec<String>, fillers: &[Vec<String>]) {
+    let mut i = 0;
+    while i < words.len() {
+        let matched = fillers.iter().find(|phrase| {
+            i + phrase.len() <= words.len()
+                && phrase
+                    .iter()
+                    .zip(&words[i..i + phrase.len()])
+                    .all(|(filler, word)| *filler == normalize_word(word))
+        });
+
+        match matched {
+            Some(phrase) => {
+                words.drain(i..i + phrase.len());
+            }
+            None => i += 1,
+        }
+    }
+}
+
+/// Collapses immediately repeated words or phrases, keeping the first occurrence
+///
+/// Trailing punctuation from the dropped repetition is carried over so sentence
+/// endings survive (e.g. "ship it it." becomes "ship it."). A repetition after a
+/// comma or sentence end ("no, no", "It was bad. Bad weather.") and the words in
+/// `INTENDED_REPEATS` are kept.
+fn collapse_repeats(words: &mut Vec<String>) {
+    let mut i = 0;
+    while i < words.len() {
+        let repeated = (1..=MAX_REPEAT_PHRASE_WORDS).rev().find(|&n| {
+            i + 2 * n <= words.len()
+                && !words[i + n - 1].ends_with(['.', '?', '!', ','])
+                && !(n == 1 && INTENDED_REPEATS.contains(&normalize_word(&words[i]).as_str()))
+                && (0..n).all(|k| {
+                    let word = normalize_word(&words[i + k]);
+                    !word.is_empty() && word == normalize_word(&words[i + n + k])
+                })
+        });
+
+        match repeated {
+            Some(n) => {
+                let removed: Vec<String> = words.drain(i + n..i + 2 * n).collect();
+                let last_removed = removed.last().map(String::as_str).unwrap_or_default();
+                let trailing = &last_removed[last_removed.trim_end_matches(|c: char| c.is_ascii_punctuation()).len()..];
+                if !trailing.is_empty() && !words[i + n - 1].ends_with(trailing) {
+                    words[i + n - 1].push_str(trailing);
+                }
+            }
+            None => i += 1,
+        }
+    }
+}
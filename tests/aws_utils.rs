@@ -0,0 +1,137 @@
+//! Tests for recognizing audio files that are already in S3, composing object keys,
+//! checking KMS key references, choosing the upload bucket, backing off throttled calls and
+//! reading Secrets Manager references.
+
+use std::time::Duration;
+
+use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::operation::list_buckets::ListBucketsError;
+use distill_cli::aws_utils::{self, BucketChoice, ListBucketsDeniedError};
+
+#[test]
+fn s3_uris_split_into_bucket_and_key() {
+    assert_eq!(
+        aws_utils::parse_s3_uri("s3://team-recordings/2024/05/standup.mp3"),
+        Some(("team-recordings".to_string(), "2024/05/standup.mp3".to_string()))
+    );
+}
+
+#[test]
+fn local_paths_and_incomplete_uris_are_not_s3_inputs() {
+    assert_eq!(aws_utils::parse_s3_uri("recordings/standup.mp3"), None);
+    assert_eq!(aws_utils::parse_s3_uri("s3://team-recordings"), None);
+    assert_eq!(aws_utils::parse_s3_uri("s3://team-recordings/"), None);
+    assert_eq!(aws_utils::parse_s3_uri("s3:///standup.mp3"), None);
+}
+
+#[test]
+fn object_keys_go_under_the_prefix_with_or_without_slashes() {
+    assert_eq!(aws_utils::s3_object_key("meeting.mp3", None, false, None), "meeting.mp3");
+    assert_eq!(aws_utils::s3_object_key("meeting.mp3", Some(""), false, None), "meeting.mp3");
+    for prefix in ["distill/2024-06-01", "distill/2024-06-01/", "/distill/2024-06-01/"] {
+        assert_eq!(
+            aws_utils::s3_object_key("meeting.mp3", Some(prefix), false, None),
+            "distill/2024-06-01/meeting.mp3"
+        );
+    }
+    assert_eq!(
+        aws_utils::s3_object_key("meeting.mp3", Some("distill"), true, Some("-v2")),
+        "distill/meeting-v2"
+    );
+}
+
+#[test]
+fn kms_key_references_accept_ids_aliases_and_arns() {
+    for key in [
+        "1234abcd-12ab-34cd-56ef-1234567890ab",
+        "mrk-1234abcd12ab34cd56ef1234567890ab",
+        "alias/distill-audio",
+        "arn:aws:kms:us-east-1:123456789012:key/1234abcd-12ab-34cd-56ef-1234567890ab",
+        "arn:aws-us-gov:kms:us-gov-west-1:123456789012:alias/distill",
+    ] {
+        assert!(aws_utils::check_kms_key_id(key).is_ok(), "{}", key);
+    }
+    for key in ["distill-audio", "1234abcd", "arn:aws:s3:::bucket", "arn:aws:kms:us-east-1:1234:key/abc"] {
+        assert!(aws_utils::check_kms_key_id(key).is_err(), "{}", key);
+    }
+}
+
+fn listed(names: &[&str]) -> anyhow::Result<Vec<String>> {
+    Ok(names.iter().map(|name| name.to_string()).collect())
+}
+
+#[test]
+fn non_interactive_runs_use_the_configured_bucket() {
+    assert_eq!(
+        aws_utils::choose_bucket("recordings", &listed(&["archive", "recordings"]), false).unwrap(),
+        BucketChoice::Use {
+            name: "recordings".to_string(),
+            verified: true
+        }
+    );
+    assert_eq!(
+        aws_utils::choose_bucket("recordings", &Err(ListBucketsDeniedError.into()), false).unwrap(),
+        BucketChoice::Use {
+            name: "recordings".to_string(),
+            verified: false
+        }
+    );
+}
+
+#[test]
+fn non_interactive_runs_fail_instead_of_asking_for_a_bucket() {
+    let unconfigured = aws_utils::choose_bucket("", &listed(&["archive", "recordings"]), false).unwrap_err();
+    assert!(unconfigured.to_string().contains("aws.s3_bucket_name"));
+
+    let unknown = aws_utils::choose_bucket("missing", &listed(&["archive"]), false).unwrap_err();
+    assert!(unknown.to_string().contains("'missing' was not found"));
+
+    assert_eq!(
+        aws_utils::choose_bucket("missing", &listed(&["archive"]), true).unwrap(),
+        BucketChoice::Ask {
+            buckets: vec!["archive".to_string()],
+            not_found: Some("missing".to_string())
+        }
+    );
+    assert_eq!(
+        aws_utils::choose_bucket("", &listed(&["archive"]), true).unwrap(),
+        BucketChoice::Ask {
+            buckets: vec!["archive".to_string()],
+            not_found: None
+        }
+    );
+}
+
+#[test]
+fn backoff_doubles_up_to_the_limit_with_jitter() {
+    assert_eq!(aws_utils::backoff_delay(1, 1.0), Duration::from_secs(1));
+    assert_eq!(aws_utils::backoff_delay(2, 1.0), Duration::from_secs(2));
+    assert_eq!(aws_utils::backoff_delay(3, 0.0), Duration::from_secs(2));
+    assert_eq!(aws_utils::backoff_delay(4, 0.5), Duration::from_secs(6));
+    assert_eq!(aws_utils::backoff_delay(20, 1.0), Duration::from_secs(30));
+}
+
+#[test]
+fn only_transient_failures_are_retried() {
+    let timeout: SdkError<ListBucketsError> = SdkError::timeout_error("operation timed out");
+    assert!(aws_utils::is_throttled(&timeout));
+
+    let construction: SdkError<ListBucketsError> = SdkError::construction_failure("missing model id");
+    assert!(!aws_utils::is_throttled(&construction));
+}
+
+#[test]
+fn secret_references_keep_the_colons_of_an_arn() {
+    assert_eq!(aws_utils::parse_secret_reference("distill/slack"), ("distill/slack", None));
+    assert_eq!(
+        aws_utils::parse_secret_reference("distill/webhooks:general"),
+        ("distill/webhooks", Some("general"))
+    );
+
+    let arn = "arn:aws:secretsmanager:us-east-1:123456789012:secret:distill/webhooks-AbCdEf";
+    assert_eq!(aws_utils::parse_secret_reference(arn), (arn, None));
+    assert_eq!(
+        aws_utils::parse_secret_reference(&format!("{}:general", arn)),
+        (arn, Some("general"))
+    );
+}
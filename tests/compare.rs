@@ -0,0 +1,37 @@
+//! Tests for comparing a summary with a previous one
+
+use distill_cli::compare::{self, DiffLine};
+
+#[test]
+fn lists_removed_and_added_lines_in_order() {
+    let previous = "## Decisions\n- Ship on Friday\n\n- Hire a designer\n";
+    let current = "## Decisions\n  - Ship on Monday\n- Hire a designer\n- Review the budget\n";
+
+    let comparison = compare::compare(previous, current);
+
+    assert_eq!(
+        comparison.changes,
+        [
+            DiffLine::Removed("- Ship on Friday".to_string()),
+            DiffLine::Added("- Ship on Monday".to_string()),
+            DiffLine::Added("- Review the budget".to_string()),
+        ]
+    );
+    // 9 of the 10 previous and 14 current words are shared
+    assert!((comparison.similarity - 18.0 / 24.0).abs() < 1e-9);
+}
+
+#[test]
+fn identical_summaries_are_fully_similar() {
+    let comparison = compare::compare("Same text.\n", "Same   text.");
+    assert!(comparison.changes.is_empty());
+    assert_eq!(comparison.similarity, 1.0);
+    assert_eq!(compare::compare("", "").similarity, 1.0);
+}
+
+#[test]
+fn markdown_output_header_is_ignored() {
+    let saved = "---\ntitle: \"standup\"\n---\n\n# Summary\n\nAll good.";
+    assert_eq!(compare::strip_output_header(saved), "\nAll good.");
+    assert_eq!(compare::strip_output_header("All good."), "All good.");
+}
@@ -0,0 +1,72 @@
+//! Tests for storing runs in and searching the SQLite index.
+
+use distill_cli::index;
+use distill_cli::output::RunMetadata;
+
+fn metadata(source_file: &str, created: &str) -> RunMetadata {
+    RunMetadata {
+        source_file: source_file.to_string(),
+        language_code: "en-US".to_string(),
+        model_id: "anthropic.claude-3-sonnet-20240229-v1:0".to_string(),
+        created: created.to_string(),
+        ..Default::default()
+    }
+}
+
+fn temp_db(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("distill-index-{}-{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn search_finds_summaries_and_transcripts_newest_first() {
+    let path = temp_db("search");
+    let connection = index::open(&path).unwrap();
+    index::insert_run(
+        &connection,
+        &metadata("standup.mp3", "2024-05-01T09:00:00+00:00"),
+        "spk_0: the budget review moves to Friday",
+        "Standup notes.",
+    )
+    .unwrap();
+    index::insert_run(
+        &connection,
+        &metadata("planning.mp3", "2024-05-02T09:00:00+00:00"),
+        "spk_0: hello",
+        "The Budget was approved.",
+    )
+    .unwrap();
+    index::insert_run(&connection, &metadata("other.mp3", "2024-05-03T09:00:00+00:00"), "nothing", "Nothing.")
+        .unwrap();
+
+    let hits = index::search(&connection, "budget", 10).unwrap();
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].source_file, "planning.mp3");
+    assert!(hits[0].in_summary);
+    assert_eq!(hits[1].source_file, "standup.mp3");
+    assert!(!hits[1].in_summary);
+
+    drop(connection);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn wildcards_in_the_query_are_literal() {
+    let path = temp_db("wildcards");
+    let connection = index::open(&path).unwrap();
+    index::insert_run(&connection, &metadata("a.mp3", "2024-05-01T09:00:00+00:00"), "", "Growth of 10% this quarter.")
+        .unwrap();
+    index::insert_run(&connection, &metadata("b.mp3", "2024-05-02T09:00:00+00:00"), "", "Growth of 100 units.")
+        .unwrap();
+
+    let hits = index::search(&connection, "10%", 10).unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].source_file, "a.mp3");
+    assert_eq!(index::search(&connection, "_", 10).unwrap().len(), 0);
+
+    drop(connection);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,327 @@
+//! Tests for the table of contents built for Markdown and Word output, post-processing
+//! output files, Slack bot messages, Teams cards and webhooks, subtitles, SSML, verifying written
+//! files, saving undelivered summaries and reporting status messages
+
+use distill_cli::output::{self, RunMetadata};
+use distill_cli::settings::{PostProcessSettings, Settings, TeamsIconSettings, TeamsSettings, Webhook, WebhookSettings};
+use distill_cli::transcribe::WordTiming;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn toc_links_each_second_level_heading() {
+    let summary = "## Key Points\n- Budget approved\n\n### Details\ntext\n\n## **Action Items** ##\n\
+                   ```\n## not a heading\n```\n## Q&A: Next Steps\n## Summary\n";
+
+    assert_eq!(
+        output::summary_headings(summary),
+        ["Key Points", "Action Items", "Q&A: Next Steps", "Summary"]
+    );
+    assert_eq!(
+        output::markdown_toc(summary).unwrap(),
+        "## Contents\n\n\
+         1. [Key Points](#key-points)\n\
+         2. [Action Items](#action-items)\n\
+         3. [Q&A: Next Steps](#qa-next-steps)\n\
+         4. [Summary](#summary-1)\n\n"
+    );
+}
+
+#[test]
+fn toc_is_skipped_without_headings() {
+    assert!(output::markdown_toc("Just a paragraph.\n### Only a subheading\n").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn post_command_gets_the_output_file() {
+    let path = std::env::temp_dir().join(format!("distill test {}.md", std::process::id()));
+    std::fs::write(&path, "# Summary\n").unwrap();
+
+    let post_process = |command: &str, fail_on_error| PostProcessSettings {
+        post_command: Some(command.to_string()),
+        fail_on_error,
+    };
+    let result = output::post_process_output(&post_process("test -f {file}", true), &path);
+    let appended = output::post_process_output(&post_process("test -f", true), &path);
+    let failed = output::post_process_output(&post_process("exit 3", true), &path);
+    let ignored = output::post_process_output(&post_process("exit 3", false), &path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(result.is_ok());
+    assert!(appended.is_ok());
+    assert!(failed.is_err());
+    assert!(ignored.is_ok());
+}
+
+fn card_text(card: &serde_json::Value, index: usize) -> String {
+    card["attachments"][0]["content"]["body"][index]["items"][0]["text"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn long_teams_summaries_are_split_across_cards() {
+    let icon = TeamsIconSettings::default();
+    let header = [json!({ "type": "TextBlock", "text": "Date: today" })];
+    let summary: String = (1..=400).map(|i| format!("- Action item {} for \"the team\"\n", i)).collect();
+
+    let single = output::teams_card_payloads("Standup", &header, &icon, "All done.", 28_000);
+    assert_eq!(single.len(), 1);
+    assert_eq!(card_text(&single[0], 2), "All done.");
+
+    let cards = output::teams_card_payloads("Standup", &header, &icon, &summary, 4_000);
+    assert!(cards.len() > 1);
+    assert!(cards.iter().all(|card| card.to_string().len() <= 4_000));
+    let titles: Vec<String> = cards
+        .iter()
+        .map(|card| card["attachments"][0]["content"]["body"][0]["columns"][1]["items"][0]["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(titles[0], format!("Standup (1/{})", cards.len()));
+
+    // Only the first card has the header; no text is lost
+    let texts: Vec<String> = cards
+        .iter()
+        .enumerate()
+        .map(|(index, card)| card_text(card, if index == 0 { 2 } else { 1 }))
+        .collect();
+    assert_eq!(texts.join("\n"), summary.trim());
+}
+
+/// Path and body of each post received by the mock webhook server
+type Posts = Arc<Mutex<Vec<(String, String)>>>;
+
+/// Starts a webhook server that records the path and body of each post
+///
+/// Posts to paths starting with `/fail` get `500 Internal Server Error`, all others `200 OK`.
+fn mock_webhooks() -> (String, Posts) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let posts = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&posts);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let status = if path.starts_with("/fail") { "500 Internal Server Error" } else { "200 OK" };
+            received.lock().unwrap().push((path, String::from_utf8(body).unwrap()));
+
+            write!(stream, "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status).unwrap();
+        }
+    });
+    (url, posts)
+}
+
+#[tokio::test]
+async fn teams_webhooks_sharing_a_name_each_get_every_card() {
+    let (url, posts) = mock_webhooks();
+    let webhook = |path: &str| Webhook { name: Some("Team".to_string()), endpoint: format!("{}{}", url, path) };
+    let settings = Settings {
+        teams: TeamsSettings {
+            webhooks: Some(vec![webhook("/fail"), webhook("/ok")]),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let summary: String = (1..=2000).map(|i| format!("- Action item {} for the team\n", i)).collect();
+    let mut spinner = spinoff::Spinner::new(spinoff::spinners::Dots, "", None);
+
+    let sent = output::send_teams_notification(
+        &settings,
+        &reqwest::Client::new(),
+        &mut spinner,
+        &summary,
+        "Standup",
+        &RunMetadata::default(),
+        "Sent",
+        &[0, 1],
+    )
+    .await
+    .unwrap();
+    assert_eq!(sent, 1);
+
+    // The failing webhook gets no more cards; the other one, with the same name, gets all of them
+    let posts = posts.lock().unwrap();
+    let count = |path: &str| posts.iter().filter(|(posted, _)| posted == path).count();
+    assert_eq!(count("/fail"), 1);
+    assert!(count("/ok") > 1);
+    assert_eq!(count("/ok"), posts.len() - 1);
+}
+
+#[test]
+fn slack_bot_payload_replies_in_thread() {
+    assert_eq!(
+        output::slack_bot_payload("C01", "All done.", Some("Standup"), Some("1718035200.123456")),
+        json!({ "channel": "C01", "text": "*Standup*\n\nAll done.", "thread_ts": "1718035200.123456" })
+    );
+
+    let payload = output::slack_bot_payload("C01", "All done.", None, None);
+    assert!(payload.get("thread_ts").is_none());
+    assert_eq!(payload["text"], "A summarization job just completed:\n\nAll done.");
+}
+
+fn word(word: &str, start: f64, end: f64) -> WordTiming {
+    WordTiming { word: word.to_string(), start, end, confidence: 0.99 }
+}
+
+#[test]
+fn subtitles_group_words_into_cues() {
+    let words = [
+        word("Welcome", 0.5, 0.9),
+        word("everyone.", 0.9, 1.4),
+        word("Today", 4.0, 4.3),
+        word("we", 4.3, 4.4),
+        word("ship", 6.0, 6.8),
+        word("it.", 8.1, 8.5),
+    ];
+
+    let cues = output::caption_cues(&words);
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].text, "Welcome everyone. Today we ship");
+    assert_eq!((cues[1].start, cues[1].end), (8.1, 8.5));
+
+    assert_eq!(
+        output::vtt_contents(&cues),
+        "WEBVTT\n\n\
+         00:00:00.500 --> 00:00:06.800\nWelcome everyone. Today we ship\n\n\
+         00:00:08.100 --> 00:00:08.500\nit.\n"
+    );
+    assert_eq!(
+        output::srt_contents(&cues),
+        "1\n00:00:00,500 --> 00:00:06,800\nWelcome everyone. Today we ship\n\n\
+         2\n00:00:08,100 --> 00:00:08,500\nit.\n"
+    );
+    assert_eq!(output::caption_timestamp(3725.0424, ','), "01:02:05,042");
+}
+
+#[test]
+fn ssml_reads_headings_and_items_with_pauses() {
+    let summary = "## Budget & Risks\n\nThe **Q3** budget was <approved>.\n\n- Hire two engineers\n2. Ship \"v2\"\n---\n";
+
+    assert_eq!(
+        output::summary_to_ssml(summary),
+        "<speak>\n\
+         <p><emphasis level=\"strong\">Budget &amp; Risks</emphasis></p><break time=\"700ms\"/>\n\
+         <p>The <emphasis>Q3</emphasis> budget was &lt;approved&gt;.</p>\n\
+         <s>Hire two engineers</s><break time=\"300ms\"/>\n\
+         <s>Ship &quot;v2&quot;</s><break time=\"300ms\"/>\n\
+         </speak>\n"
+    );
+}
+
+#[test]
+fn written_files_are_verified_by_format() {
+    let dir = std::env::temp_dir().join(format!("distill-test-verify-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = |name: &str, contents: &[u8]| {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+    let docx = |name: &str, part: &str| {
+        let path = dir.join(name);
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        zip.start_file(part, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"<w:document/>").unwrap();
+        zip.finish().unwrap();
+        path
+    };
+
+    let valid = [
+        file("meeting.metadata.json", br#"{"model_id": "claude"}"#),
+        file("meeting.txt", "Résumé".as_bytes()),
+        docx("meeting.docx", "word/document.xml"),
+    ];
+    let invalid = [
+        file("empty.md", b""),
+        file("broken.json", br#"{"model_id": "#),
+        file("latin1.trans", b"R\xe9sum\xe9"),
+        file("plain.docx", b"not a zip"),
+        docx("other.docx", "notes.xml"),
+    ];
+    let valid: Vec<_> = valid.iter().map(|path| output::verify_output_file(path)).collect();
+    let invalid: Vec<_> = invalid.iter().map(|path| output::verify_output_file(path)).collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(valid.iter().all(Result::is_ok), "{:?}", valid);
+    assert!(invalid.iter().all(Result::is_err), "{:?}", invalid);
+}
+
+// Messages received by the reporter set in `status_messages_go_to_the_reporter`
+static REPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[test]
+fn status_messages_go_to_the_reporter() {
+    output::set_reporter(|message| REPORTED.lock().unwrap().push(message.to_string()));
+    output::report(format!("\n💾 Summary written to {}", "notes.md"));
+
+    assert!(REPORTED.lock().unwrap().iter().any(|message| message == "\n💾 Summary written to notes.md"));
+}
+
+// Held by the tests that set the input file artifacts and webhook posts are attributed to
+static ARTIFACT_SOURCE: Mutex<()> = Mutex::new(());
+
+#[test]
+fn repeated_webhook_posts_are_only_skipped_for_the_same_file() {
+    let _source = ARTIFACT_SOURCE.lock().unwrap();
+    let (url, posts) = mock_webhooks();
+    let settings = Settings {
+        slack: WebhookSettings {
+            webhooks: Some(vec![Webhook { name: Some("Team".to_string()), endpoint: format!("{}/ok", url) }]),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let client = reqwest::Client::new();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let send = || runtime.block_on(output::send_status_message(&settings, &client, "slack", &[0], "No action items."));
+
+    output::set_artifact_source("standup.m4a");
+    assert_eq!(send(), 1);
+    assert_eq!(send(), 1);
+    assert_eq!(posts.lock().unwrap().len(), 1);
+
+    // An identical message about another file of the batch is still sent
+    output::set_artifact_source("retro.m4a");
+    assert_eq!(send(), 1);
+    assert_eq!(posts.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn undelivered_summaries_are_appended_to_the_fallback_file() {
+    let _source = ARTIFACT_SOURCE.lock().unwrap();
+    let dir = std::env::temp_dir().join(format!("distill-test-fallback-{}", std::process::id()));
+    let path = dir.join("nested").join("undelivered.md");
+
+    output::set_artifact_source("standup.m4a");
+    output::write_fallback_summary(&path, "Slack", "First summary\n").unwrap();
+    output::set_artifact_source("retro.m4a");
+    output::write_fallback_summary(&path, "Teams", "Second summary").unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let first = contents.find("## standup.m4a (not sent to Slack").unwrap();
+    let second = contents.find("## retro.m4a (not sent to Teams").unwrap();
+    assert!(first < second);
+    assert!(contents.contains("First summary\n\n## retro.m4a"));
+    assert!(contents.trim_end().ends_with("Second summary"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
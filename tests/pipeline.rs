@@ -0,0 +1,243 @@
+//! Tests for selecting the audio files of a directory, glob or stdin input, for carrying
+//! the summaries of a batch into the next prompt and for delivering the result of a run.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use aws_sdk_bedrockruntime::config::{BehaviorVersion, Credentials, Region};
+use distill_cli::output::{OutputType, RunMetadata};
+use distill_cli::pipeline::{self, BedrockFallback, DeliveryOptions, PipelineOptions, PipelineResult, Stages};
+use distill_cli::redact::Redactor;
+use distill_cli::settings::{ModelSettings, Settings};
+use distill_cli::summarize::{self, CarriedContext};
+use serde_json::json;
+
+#[test]
+fn directory_inputs_keep_only_allowed_extensions() {
+    let dir = std::env::temp_dir().join(format!("distill-test-inputs-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested.mp3")).unwrap();
+    for name in ["b.MP3", "a.m4a", "scratch.wav", "notes.txt", "no-extension"] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+
+    let allowed = pipeline::audio_files_in(&dir, &["mp3".to_string(), ".M4A".to_string()]).unwrap();
+    let all = pipeline::audio_files_in(&dir, &distill_cli::settings::InputSettings::default().extensions).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let names = |files: &[std::path::PathBuf]| {
+        files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&allowed), ["a.m4a", "b.MP3"]);
+    assert_eq!(names(&all), ["a.m4a", "b.MP3", "scratch.wav"]);
+}
+
+#[test]
+fn glob_patterns_match_file_names() {
+    let dir = std::env::temp_dir().join(format!("distill-test-glob-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("day-3.m4a")).unwrap();
+    for name in ["day-1.m4a", "day-2.m4a", "day-10.m4a", "day-1.mp3", "notes.txt"] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+
+    let pattern = |pattern: &str| dir.join(pattern).display().to_string();
+    let all = pipeline::audio_files_matching(&pattern("day-*.m4a")).unwrap();
+    let single_digit = pipeline::audio_files_matching(&pattern("day-?.*")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let names = |files: &[std::path::PathBuf]| {
+        files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&all), ["day-1.m4a", "day-10.m4a", "day-2.m4a"]);
+    assert_eq!(names(&single_digit), ["day-1.m4a", "day-1.mp3", "day-2.m4a"]);
+    assert!(pipeline::is_glob("recordings/*.m4a"));
+    assert!(!pipeline::is_glob("recordings/meeting.m4a"));
+    assert!(pipeline::audio_files_matching("rec*/*.m4a").is_err());
+}
+
+#[test]
+fn batch_output_names_append_the_input_stem() {
+    let files: Vec<std::path::PathBuf> = ["in/standup.m4a", "in/a.mp3", "in/a.wav"].iter().map(Into::into).collect();
+
+    assert_eq!(
+        pipeline::batch_output_names("out/notes.md", &files),
+        ["out/notes-standup.md", "out/notes-a-mp3.md", "out/notes-a-wav.md"]
+    );
+    assert_eq!(
+        pipeline::batch_output_names("summarized_output", &files[..1]),
+        ["summarized_output-standup"]
+    );
+}
+
+#[test]
+fn input_lists_skip_blank_lines() {
+    let list = "meeting.m4a\n\n  recordings/standup.mp3  \r\ns3://bucket/retro.wav\n";
+
+    assert_eq!(
+        pipeline::read_input_list(list.as_bytes()).unwrap(),
+        ["meeting.m4a", "recordings/standup.mp3", "s3://bucket/retro.wav"]
+    );
+}
+
+#[test]
+fn inputs_expand_directories_and_keep_plain_paths() {
+    let dir = std::env::temp_dir().join(format!("distill-test-expand-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["standup.m4a", "notes.txt"] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+    let extensions = ["m4a".to_string()];
+    let values = |values: &[&str]| values.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+    let dir_value = dir.display().to_string();
+
+    let single = pipeline::expand_inputs(&values(&["meeting.m4a"]), &extensions).unwrap();
+    let batch = pipeline::expand_inputs(&values(&[&dir_value, "s3://bucket/retro.wav"]), &extensions).unwrap();
+    let empty = pipeline::expand_inputs(&values(&[&format!("{}/*.mp3", dir_value)]), &extensions);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(single, (values(&["meeting.m4a"]), false));
+    assert_eq!(batch, (vec![dir.join("standup.m4a").display().to_string(), "s3://bucket/retro.wav".to_string()], true));
+    assert!(empty.is_err());
+}
+
+/// Starts a Bedrock stand-in that records the request bodies and answers each with
+/// the summary "- Ship on Friday"
+fn mock_bedrock() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&bodies);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            received.lock().unwrap().push(String::from_utf8(body).unwrap());
+
+            let response = json!({"content": [{"type": "text", "text": "- Ship on Friday"}], "stop_reason": "end_turn"});
+            let response = response.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+    (url, bodies)
+}
+
+#[tokio::test]
+async fn carried_summaries_reach_the_prompt_of_the_next_file() {
+    let (endpoint_url, bodies) = mock_bedrock();
+    let settings = Settings {
+        model: ModelSettings {
+            model_id: "anthropic.claude-3-haiku-20240307-v1:0".to_string(),
+            endpoint_url: Some(endpoint_url),
+            max_retries: 0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let config = aws_config::defaults(BehaviorVersion::latest())
+        .region(Region::new("us-east-1"))
+        .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+        .load()
+        .await;
+    let mut spinner = spinoff::Spinner::new(spinoff::spinners::Dots, "", None);
+
+    // What a --carry-context batch does: each summary is added before the next file
+    let mut context = CarriedContext::new(summarize::CARRIED_CONTEXT_CHARS);
+    for file in ["week1.m4a", "week2.m4a"] {
+        let options = PipelineOptions {
+            transcribe_json: Some(PathBuf::from("tests/fixtures/diarized.json")),
+            stages: Stages { upload: false, transcribe: false, summarize: true },
+            carried_context: (!context.is_empty()).then(|| context.render()),
+            ..PipelineOptions::new(file, "")
+        };
+        let result = pipeline::run(&config, &settings, &options, &mut spinner).await.unwrap();
+        assert!(!result.summary_failed);
+        context.push(file, &result.summary);
+    }
+    spinner.clear();
+
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 2, "{:#?}", bodies);
+    assert!(!bodies[0].contains("earlier related recordings"), "{}", bodies[0]);
+    assert!(bodies[1].contains("earlier related recordings"), "{}", bodies[1]);
+    assert!(bodies[1].contains("### week1.m4a\\n- Ship on Friday"), "{}", bodies[1]);
+}
+
+/// A finished run with a summary and a transcript
+fn pipeline_result(summary_failed: bool) -> PipelineResult {
+    PipelineResult {
+        summary: "- Ship on Friday".to_string(),
+        summary_failed,
+        transcript: "spk_0: We ship on Friday.".to_string(),
+        cleaned_transcript: "spk_0: We ship Friday.".to_string(),
+        transcript_json: None,
+        metadata: RunMetadata::default(),
+    }
+}
+
+#[tokio::test]
+async fn deliver_writes_the_output_and_the_requested_extras() {
+    let dir = std::env::temp_dir().join(format!("distill-deliver-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let settings = Settings::default();
+    let redactor = Redactor::new(&settings.redaction).unwrap();
+    let mut spinner = spinoff::Spinner::new(spinoff::spinners::Dots, "", None);
+
+    let options = DeliveryOptions {
+        summary_file_name: dir.join("notes").to_string_lossy().into_owned(),
+        output_type: OutputType::Text,
+        deliver_output: true,
+        save_transcript: true,
+        save_cleaned: true,
+        ..Default::default()
+    };
+    let summary_failed = pipeline::deliver(&settings, &options, pipeline_result(false), &redactor, None, &mut spinner)
+        .await
+        .unwrap();
+    assert!(!summary_failed);
+    assert_eq!(std::fs::read_to_string(dir.join("notes.txt")).unwrap(), "- Ship on Friday");
+    assert_eq!(std::fs::read_to_string(dir.join("notes.trans")).unwrap(), "spk_0: We ship Friday.");
+
+    // With the fallback set to none, a failed summary writes no output
+    let options = DeliveryOptions {
+        summary_file_name: dir.join("failed").to_string_lossy().into_owned(),
+        output_type: OutputType::Text,
+        deliver_output: true,
+        bedrock_fallback: BedrockFallback::None,
+        ..Default::default()
+    };
+    let summary_failed = pipeline::deliver(&settings, &options, pipeline_result(true), &redactor, None, &mut spinner)
+        .await
+        .unwrap();
+    assert!(summary_failed);
+    assert!(!dir.join("failed.txt").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
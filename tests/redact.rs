@@ -0,0 +1,61 @@
+//! Tests for redacting sensitive terms from transcripts.
+
+use distill_cli::redact::Redactor;
+use distill_cli::settings::RedactionSettings;
+
+fn redactor(terms: &[&str], patterns: &[&str]) -> Redactor {
+    Redactor::new(&RedactionSettings {
+        terms: terms.iter().map(|term| term.to_string()).collect(),
+        patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn terms_are_replaced_case_insensitively_as_whole_words() {
+    let redactor = redactor(&["Falcon", "Project Blue"], &[]);
+
+    assert_eq!(
+        redactor.redact("spk_0: falcon ships before PROJECT BLUE. Falconry is unrelated."),
+        "spk_0: [REDACTED] ships before [REDACTED]. Falconry is unrelated."
+    );
+}
+
+#[test]
+fn patterns_are_replaced_as_written() {
+    let redactor = redactor(&[], &[r"ACCT-\d{6}"]);
+
+    assert_eq!(redactor.redact("Charge ACCT-123456, not acct-123456."), "Charge [REDACTED], not acct-123456.");
+}
+
+#[test]
+fn placeholder_is_inserted_literally() {
+    let redactor = Redactor::new(&RedactionSettings {
+        terms: vec!["Falcon".to_string()],
+        placeholder: "$1 <hidden>".to_string(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(redactor.redact("Falcon"), "$1 <hidden>");
+}
+
+#[test]
+fn empty_settings_leave_text_unchanged() {
+    let redactor = redactor(&[], &[]);
+
+    assert!(!redactor.is_active());
+    assert_eq!(redactor.redact("Falcon"), "Falcon");
+}
+
+#[test]
+fn invalid_pattern_is_an_error() {
+    let err = Redactor::new(&RedactionSettings {
+        patterns: vec!["(unclosed".to_string()],
+        ..Default::default()
+    })
+    .unwrap_err();
+
+    assert!(err.to_string().contains("(unclosed"), "unexpected error: {}", err);
+}
@@ -0,0 +1,140 @@
+//! Tests for planning audio segments and stitching their Transcribe results.
+
+use std::path::Path;
+
+use distill_cli::segment::{self, Segment, OVERLAP_SECS};
+use distill_cli::transcribe;
+use serde_json::{json, Value};
+
+fn word(content: &str, start: f64, speaker: &str) -> Value {
+    json!({
+        "type": "pronunciation",
+        "alternatives": [{ "confidence": "0.99", "content": content }],
+        "start_time": format!("{:.3}", start),
+        "end_time": format!("{:.3}", start + 0.4),
+        "speaker_label": speaker,
+    })
+}
+
+fn punctuation(content: &str, speaker: &str) -> Value {
+    json!({
+        "type": "punctuation",
+        "alternatives": [{ "confidence": "0.0", "content": content }],
+        "speaker_label": speaker,
+    })
+}
+
+fn result(items: Vec<Value>) -> Value {
+    json!({ "results": { "items": items } })
+}
+
+#[test]
+fn segments_overlap_but_keep_disjoint_ranges() {
+    let segments = segment::plan_segments(90.0, 3, Path::new("/tmp"));
+
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments[0].offset, 0.0);
+    assert_eq!((segments[0].keep_from, segments[0].keep_until), (0.0, 30.0));
+    assert_eq!(segments[1].offset, 30.0 - OVERLAP_SECS);
+    assert_eq!((segments[1].keep_from, segments[1].keep_until), (30.0, 60.0));
+    assert_eq!(segments[2].keep_until, f64::INFINITY);
+    assert!(segments[2].path.ends_with("segment_002.flac"));
+}
+
+#[test]
+fn stitching_shifts_timestamps_and_drops_the_overlap() {
+    let first = Segment {
+        path: "a.flac".into(),
+        offset: 0.0,
+        keep_from: 0.0,
+        keep_until: 10.0,
+    };
+    let second = Segment {
+        path: "b.flac".into(),
+        offset: 8.0,
+        keep_from: 10.0,
+        keep_until: f64::INFINITY,
+    };
+    let parts = vec![
+        (
+            first,
+            result(vec![
+                word("Hello", 1.0, "spk_0"),
+                word("there", 9.5, "spk_0"),
+                punctuation(".", "spk_0"),
+                // Inside the overlap: transcribed again by the second segment
+                word("How", 10.5, "spk_0"),
+            ]),
+        ),
+        (
+            second,
+            result(vec![
+                // 1.5 + 8.0 = 9.5 belongs to the first segment
+                word("there", 1.5, "spk_0"),
+                punctuation(".", "spk_0"),
+                word("How", 2.5, "spk_0"),
+                word("are", 3.0, "spk_0"),
+                word("you", 3.5, "spk_0"),
+                punctuation("?", "spk_0"),
+            ]),
+        ),
+    ];
+
+    let stitched = segment::stitch_results(&parts).unwrap();
+
+    assert_eq!(
+        transcribe::format_transcript(&stitched).unwrap(),
+        "spk_0: Hello there. How are you?\n"
+    );
+    assert_eq!(stitched["results"]["items"][3]["start_time"], "10.500");
+    assert_eq!(
+        stitched["results"]["transcripts"][0]["transcript"],
+        "Hello there. How are you?"
+    );
+}
+
+#[test]
+fn stitching_merges_channels() {
+    let segments = segment::plan_segments(20.0, 2, Path::new("/tmp"));
+    let channel_result = |items: Vec<(&str, f64, &str)>| {
+        let items: Vec<Value> = items
+            .into_iter()
+            .map(|(content, start, channel)| {
+                let mut item = word(content, start, "spk_0");
+                item.as_object_mut().unwrap().remove("speaker_label");
+                item["channel_label"] = json!(channel);
+                item
+            })
+            .collect();
+        let channel = |label: &str| {
+            items
+                .iter()
+                .filter(|item| item["channel_label"] == label)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        json!({
+            "results": {
+                "items": items,
+                "channel_labels": {
+                    "number_of_channels": 2,
+                    "channels": [
+                        { "channel_label": "ch_0", "items": channel("ch_0") },
+                        { "channel_label": "ch_1", "items": channel("ch_1") }
+                    ]
+                }
+            }
+        })
+    };
+    let parts = vec![
+        (segments[0].clone(), channel_result(vec![("Ready", 1.0, "ch_0"), ("Yes", 2.0, "ch_1")])),
+        (segments[1].clone(), channel_result(vec![("Go", 4.0, "ch_0")])),
+    ];
+
+    let stitched = segment::stitch_results(&parts).unwrap();
+
+    assert_eq!(
+        transcribe::format_transcript(&stitched).unwrap(),
+        "ch_0: Ready\nch_1: Yes\nch_0: Go\n"
+    );
+}
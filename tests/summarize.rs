@@ -0,0 +1,612 @@
+//! Tests for building Bedrock requests, reading Bedrock responses, chunking long
+//! transcripts, structuring summaries into sections, carrying context between summaries,
+//! including reference documents, reading streamed responses and resuming long summaries.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use aws_sdk_bedrockruntime::config::{BehaviorVersion, Credentials, Region};
+use aws_smithy_types::{Document, Number};
+use distill_cli::settings::{AnthropicSettings, ModelSettings, PromptSettings, Settings};
+use distill_cli::summarize::{self, CarriedContext, PromptOptions, StreamChunk, StreamedReprint};
+use serde_json::json;
+
+/// Loads settings from a config.toml written to a temporary file
+fn load_settings(name: &str, contents: &str) -> Settings {
+    let path = std::env::temp_dir().join(format!("distill-test-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    let settings = Settings::load(path.to_str().unwrap(), None, None).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    settings
+}
+
+#[test]
+fn invoke_body_keeps_fractional_sampling_parameters() {
+    let settings = load_settings(
+        "floats",
+        "[model]\nmodel_id = \"anthropic.claude-3-sonnet-20240229-v1:0\"\n\
+         max_tokens = 2000\ntemperature = 0.7\ntop_p = 0.95\ntop_k = 40\n",
+    );
+
+    let body = summarize::invoke_body(&settings, "Summarize this.", None);
+
+    assert_eq!(body["temperature"], json!(0.7));
+    assert_eq!(body["top_p"], json!(0.95));
+    assert_eq!(body["top_k"], json!(40));
+    assert_eq!(body["max_tokens"], json!(2000));
+    let serialized = body.to_string();
+    assert!(serialized.contains("\"temperature\":0.7"), "{}", serialized);
+    assert!(serialized.contains("\"top_p\":0.95"), "{}", serialized);
+}
+
+#[test]
+fn converse_requests_carry_the_sampling_parameters() {
+    let settings = Settings {
+        model: ModelSettings {
+            api: "converse".to_string(),
+            max_tokens: Some(1500),
+            temperature: Some(0.25),
+            top_p: Some(0.8),
+            top_k: Some(40),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let inference_config = summarize::converse_inference_config(&settings);
+    assert_eq!(inference_config.max_tokens(), Some(1500));
+    assert_eq!(inference_config.temperature(), Some(0.25));
+    assert_eq!(inference_config.top_p(), Some(0.8));
+
+    let unset = summarize::converse_inference_config(&Settings::default());
+    assert_eq!(unset.max_tokens(), None);
+    assert_eq!(unset.temperature(), None);
+    assert_eq!(unset.top_p(), None);
+
+    let top_k = Some(Document::Object([("top_k".to_string(), Document::Number(Number::PosInt(40)))].into()));
+    assert_eq!(summarize::converse_additional_fields(&settings), top_k);
+
+    // Inference profile ARNs don't name the model family; the provider setting decides
+    let mut profile = settings.clone();
+    profile.model.model_id =
+        "arn:aws:bedrock:us-east-1:123456789012:application-inference-profile/a1b2c3".to_string();
+    assert_eq!(summarize::converse_additional_fields(&profile), top_k);
+
+    let mut titan = settings.clone();
+    titan.model.model_id = "amazon.titan-text-premier-v1:0".to_string();
+    titan.model.provider = "titan".to_string();
+    assert_eq!(summarize::converse_additional_fields(&titan), None);
+
+    assert_eq!(summarize::converse_additional_fields(&Settings::default()), None);
+}
+
+#[test]
+fn constructed_settings_drive_the_prompt_and_body() {
+    let settings = Settings {
+        prompt: PromptSettings {
+            template: "Summarize this {language} meeting.".to_string(),
+            ..Default::default()
+        },
+        model: ModelSettings {
+            model_id: "anthropic.claude-3-haiku-20240307-v1:0".to_string(),
+            max_tokens: Some(512),
+            ..Default::default()
+        },
+        anthropic: AnthropicSettings {
+            anthropic_version: "bedrock-2023-05-31".to_string(),
+            system: "You are a meeting assistant.".to_string(),
+            beta: None,
+        },
+        ..Default::default()
+    };
+    let prompt_options = PromptOptions {
+        variables: [("language", "German".to_string())].into(),
+        instructions: vec!["Use bullet points.".to_string()],
+        ..Default::default()
+    };
+
+    let prompt = summarize::summary_prompt(&settings, "spk_0: Hallo zusammen.", &prompt_options);
+    assert_eq!(
+        prompt,
+        "Summarize this German meeting.\n\nUse bullet points.\n\nspk_0: Hallo zusammen."
+    );
+
+    let body = summarize::invoke_body(&settings, &prompt, None);
+    assert_eq!(body["anthropic_version"], "bedrock-2023-05-31");
+    assert_eq!(body["system"], "You are a meeting assistant.");
+    assert_eq!(body["max_tokens"], 512);
+    assert_eq!(body["messages"][0]["content"][0]["text"], prompt.as_str());
+}
+
+#[test]
+fn invoke_body_defaults_unset_sampling_parameters() {
+    let settings = load_settings("defaults", "[model]\nmodel_id = \"anthropic.claude-3-sonnet-20240229-v1:0\"\n");
+
+    let body = summarize::invoke_body(&settings, "Summarize this.", Some(100));
+
+    assert_eq!(body["temperature"], json!(summarize::DEFAULT_TEMPERATURE));
+    assert_eq!(body["top_p"], json!(summarize::DEFAULT_TOP_P));
+    assert_eq!(body["max_tokens"], json!(100));
+}
+
+#[test]
+fn text_blocks_are_concatenated_in_order() {
+    let response = json!({
+        "content": [
+            { "type": "text", "text": "## Summary\n" },
+            { "type": "text", "text": "The team agreed to ship on Friday." }
+        ],
+        "stop_reason": "end_turn"
+    });
+
+    assert_eq!(
+        summarize::response_text(&response).unwrap(),
+        "## Summary\nThe team agreed to ship on Friday."
+    );
+}
+
+#[test]
+fn thinking_and_tool_blocks_are_skipped() {
+    let response = json!({
+        "content": [
+            { "type": "thinking", "thinking": "The user wants a summary...", "signature": "abc" },
+            { "type": "redacted_thinking", "data": "..." },
+            { "type": "text", "text": "Summary: " },
+            { "type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {} },
+            { "type": "text", "text": "all good." }
+        ]
+    });
+
+    assert_eq!(summarize::response_text(&response).unwrap(), "Summary: all good.");
+}
+
+#[test]
+fn response_without_text_lists_the_block_types() {
+    let response = json!({
+        "content": [
+            { "type": "thinking", "thinking": "..." },
+            { "type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {} }
+        ]
+    });
+
+    let err = summarize::response_text(&response).unwrap_err().to_string();
+    assert!(err.contains("thinking, tool_use"), "unexpected error: {}", err);
+}
+
+#[test]
+fn missing_content_is_an_error() {
+    assert!(summarize::response_text(&json!({ "stop_reason": "end_turn" })).is_err());
+}
+
+#[test]
+fn malformed_response_bodies_are_errors_that_quote_the_body() {
+    let err = summarize::parse_invoke_response("anthropic", r#"{"stop_reason":"end_turn","message":"Too many requests"}"#)
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Too many requests"), "{:#}", err);
+
+    let err = summarize::parse_invoke_response("anthropic", "<html>Service Unavailable</html>").unwrap_err();
+    assert!(format!("{:#}", err).contains("Service Unavailable"), "{:#}", err);
+
+    let long_body = format!(r#"{{"output":"{}"}}"#, "x".repeat(1000));
+    let err = summarize::parse_invoke_response("anthropic", &long_body).unwrap_err();
+    assert!(format!("{:#}", err).len() < 500, "{:#}", err);
+
+    assert_eq!(
+        summarize::parse_invoke_response("anthropic", r#"{"content":[{"type":"text","text":"Done."}],"stop_reason":"end_turn"}"#)
+            .unwrap(),
+        "Done."
+    );
+}
+
+#[test]
+fn truncated_responses_are_kept_and_reported_once() {
+    let truncated = r#"{"content":[{"type":"text","text":"- Budget"}],"stop_reason":"max_tokens"}"#;
+
+    assert_eq!(summarize::parse_invoke_response("anthropic", truncated).unwrap(), "- Budget");
+    assert!(summarize::take_truncated());
+    assert!(!summarize::take_truncated());
+}
+
+fn provider_settings(provider: &str) -> Settings {
+    Settings {
+        model: ModelSettings {
+            provider: provider.to_string(),
+            model_id: "model".to_string(),
+            max_tokens: Some(300),
+            temperature: Some(0.5),
+            top_p: Some(0.9),
+            top_k: Some(40),
+            ..Default::default()
+        },
+        anthropic: AnthropicSettings {
+            system: "You are a meeting assistant.".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn titan_body_uses_text_generation_config() {
+    let body = summarize::invoke_body(&provider_settings("titan"), "Summarize this.", None);
+
+    assert_eq!(
+        body,
+        json!({
+            "inputText": "You are a meeting assistant.\n\nSummarize this.",
+            "textGenerationConfig": { "maxTokenCount": 300, "temperature": 0.5, "topP": 0.9 }
+        })
+    );
+}
+
+#[test]
+fn llama_body_uses_the_instruct_prompt_format() {
+    let body = summarize::invoke_body(&provider_settings("meta"), "Summarize this.", Some(100));
+
+    assert_eq!(body["max_gen_len"], json!(100));
+    assert_eq!(body["temperature"], json!(0.5));
+    assert_eq!(body["top_p"], json!(0.9));
+    assert!(body.get("top_k").is_none());
+    assert_eq!(
+        body["prompt"],
+        "<|begin_of_text|><|start_header_id|>system<|end_header_id|>\n\nYou are a meeting assistant.<|eot_id|>\
+         <|start_header_id|>user<|end_header_id|>\n\nSummarize this.<|eot_id|>\
+         <|start_header_id|>assistant<|end_header_id|>\n\n"
+    );
+}
+
+#[test]
+fn responses_are_read_per_provider() {
+    let titan = r#"{"inputTextTokenCount":12,"results":[{"tokenCount":3,"outputText":"Titan summary.","completionReason":"FINISH"}]}"#;
+    assert_eq!(summarize::parse_invoke_response("titan", titan).unwrap(), "Titan summary.");
+    let filtered = r#"{"results":[{"outputText":"","completionReason":"CONTENT_FILTERED"}]}"#;
+    assert!(summarize::parse_invoke_response("titan", filtered).is_err());
+
+    let llama = r#"{"generation":"Llama summary.","prompt_token_count":12,"generation_token_count":3,"stop_reason":"stop"}"#;
+    assert_eq!(summarize::parse_invoke_response("meta", llama).unwrap(), "Llama summary.");
+
+    // A body of another provider has no text where it is expected
+    let err = summarize::parse_invoke_response("meta", titan).unwrap_err();
+    assert!(format!("{:#}", err).contains("Titan summary."), "{:#}", err);
+}
+
+fn stream_chunk(provider: &str, event: serde_json::Value) -> StreamChunk {
+    summarize::parse_stream_chunk(provider, event.to_string().as_bytes()).unwrap()
+}
+
+#[test]
+fn streamed_anthropic_events_carry_text_deltas_and_the_stop_reason() {
+    let delta = json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "## Summary" } });
+    assert_eq!(stream_chunk("anthropic", delta).text.as_deref(), Some("## Summary"));
+
+    let message_delta = json!({ "type": "message_delta", "delta": { "stop_reason": "max_tokens" }, "usage": { "output_tokens": 5 } });
+    assert_eq!(stream_chunk("anthropic", message_delta).stop_reason.as_deref(), Some("max_tokens"));
+
+    for event in [
+        json!({ "type": "message_start", "message": { "role": "assistant" } }),
+        json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+        json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "input_json_delta", "partial_json": "{" } }),
+        json!({ "type": "message_stop" }),
+    ] {
+        assert_eq!(stream_chunk("anthropic", event), StreamChunk::default());
+    }
+
+    assert!(summarize::parse_stream_chunk("anthropic", b"not json").is_err());
+}
+
+#[test]
+fn streamed_titan_and_llama_chunks_are_read() {
+    let titan = stream_chunk("titan", json!({ "outputText": "Titan part", "index": 0, "completionReason": null }));
+    assert_eq!(titan.text.as_deref(), Some("Titan part"));
+    assert_eq!(titan.stop_reason, None);
+    let titan_last = stream_chunk("titan", json!({ "outputText": "", "completionReason": "LENGTH" }));
+    assert_eq!(titan_last, StreamChunk { text: None, stop_reason: Some("max_tokens".to_string()) });
+
+    let llama = stream_chunk("meta", json!({ "generation": " Llama part", "stop_reason": null }));
+    assert_eq!(llama.text.as_deref(), Some(" Llama part"));
+    let llama_last = stream_chunk("meta", json!({ "generation": "", "stop_reason": "stop" }));
+    assert_eq!(llama_last.stop_reason.as_deref(), Some("end_turn"));
+}
+
+#[test]
+fn unknown_providers_are_rejected() {
+    for provider in summarize::PROVIDERS {
+        assert!(summarize::check_provider(provider).is_ok());
+    }
+    let err = summarize::check_provider("mistral").unwrap_err().to_string();
+    assert!(err.contains("anthropic, titan, meta"), "{}", err);
+}
+
+#[test]
+fn carried_context_lists_recordings_oldest_first() {
+    let mut context = CarriedContext::new(summarize::CARRIED_CONTEXT_CHARS);
+    assert!(context.is_empty());
+
+    context.push("week1.mp3", "### Decisions\n- Ship on Friday\n");
+    context.push("week2.mp3", "Release slipped to Monday.");
+
+    assert_eq!(
+        context.render(),
+        "### week1.mp3\n### Decisions\n- Ship on Friday\n\n### week2.mp3\nRelease slipped to Monday."
+    );
+}
+
+#[test]
+fn carried_context_drops_the_oldest_recordings_first() {
+    let mut context = CarriedContext::new(120);
+    for week in 1..=5 {
+        context.push(&format!("week{}.mp3", week), &format!("Summary of week {} with some detail.", week));
+    }
+
+    let rendered = context.render();
+    assert!(rendered.chars().count() <= 120);
+    assert!(rendered.contains("week5.mp3"));
+    assert!(!rendered.contains("week1.mp3"));
+}
+
+#[test]
+fn long_summaries_are_condensed_at_a_line_break() {
+    let long_summary = (0..200).map(|line| format!("- Point {}", line)).collect::<Vec<_>>().join("\n");
+    let mut context = CarriedContext::new(summarize::CARRIED_CONTEXT_CHARS);
+    context.push("long.mp3", &long_summary);
+
+    let rendered = context.render();
+    assert!(rendered.len() < long_summary.len());
+    assert!(rendered.ends_with(" …"));
+    assert!(rendered.lines().all(|line| line.starts_with("### ") || line.starts_with("- Point ")));
+}
+
+fn document(name: &str, contents: &str) -> (String, String) {
+    (name.to_string(), contents.to_string())
+}
+
+#[test]
+fn reference_documents_are_delimited_by_name() {
+    let documents = [document("glossary.txt", "SLO: service level objective\n"), document("decisions.md", "Ship on Fridays.")];
+    let (instruction, truncated) = summarize::reference_instruction(&documents, summarize::REFERENCE_MAX_CHARS);
+
+    assert!(truncated.is_empty());
+    assert!(instruction.ends_with(
+        "<reference name=\"glossary.txt\">\nSLO: service level objective\n</reference>\n\n<reference name=\"decisions.md\">\nShip on Fridays.\n</reference>"
+    ));
+}
+
+#[test]
+fn reference_documents_over_the_limit_are_cut() {
+    let documents = [document("a.txt", "aaaaaaaaaa"), document("b.txt", "bbbbbbbbbb"), document("c.txt", "cccccccccc")];
+    let (instruction, truncated) = summarize::reference_instruction(&documents, 15);
+
+    assert_eq!(truncated, ["b.txt", "c.txt"]);
+    assert!(instruction.contains("<reference name=\"a.txt\">\naaaaaaaaaa\n</reference>"));
+    assert!(instruction.contains("<reference name=\"b.txt\">\nbbbbb …\n</reference>"));
+    assert!(!instruction.contains("c.txt"));
+}
+
+fn names(sections: &[&str]) -> Vec<String> {
+    sections.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn sections_are_parsed_in_the_requested_order() {
+    let summary = "Here is the summary:\n\n**next steps:**\n- Ship Friday\n\n## Overview\nRelease review.\n### Details\nAll green.";
+
+    let sections = summarize::parse_sections(summary, &names(&["Overview", "Next Steps"]));
+
+    assert_eq!(sections[0].name, "Overview");
+    assert_eq!(sections[0].content, "Release review.\n### Details\nAll green.");
+    assert_eq!(sections[1].name, "Next Steps");
+    assert_eq!(sections[1].content, "- Ship Friday");
+}
+
+#[test]
+fn missing_and_empty_sections_are_none_noted() {
+    let summary = "## Overview\nRelease review.\n\n## Risks\n\n";
+
+    let sections = summarize::parse_sections(summary, &names(&["Overview", "Risks", "Open Questions"]));
+
+    assert_eq!(sections[1].content, summarize::MISSING_SECTION);
+    assert_eq!(sections[2].content, summarize::MISSING_SECTION);
+    assert_eq!(
+        summarize::render_sections(&sections),
+        "## Overview\n\nRelease review.\n\n## Risks\n\nNone noted\n\n## Open Questions\n\nNone noted"
+    );
+}
+
+#[test]
+fn profile_overrides_top_level_settings() {
+    let path = std::env::temp_dir().join(format!("distill-test-profile-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "[model]\nmodel_id = \"anthropic.claude-3-sonnet-20240229-v1:0\"\nmax_tokens = 2000\ntemperature = 1.0\n\n\
+         [prompt]\ntemplate = \"Summarize:\"\n\n\
+         [profiles.Standup.model]\nmax_tokens = 800\n\n\
+         [profiles.Standup.prompt]\ntemplate = \"List blockers:\"\n",
+    )
+    .unwrap();
+    let path_str = path.to_str().unwrap();
+    let standup = Settings::load(path_str, None, Some("standup")).unwrap();
+    let base = Settings::load(path_str, None, None).unwrap();
+    let unknown = Settings::load(path_str, None, Some("retro"));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(standup.model.max_tokens, Some(800));
+    assert_eq!(standup.model.temperature, Some(1.0));
+    assert_eq!(standup.prompt.template, "List blockers:");
+    assert_eq!(base.model.max_tokens, Some(2000));
+    assert!(unknown.unwrap_err().to_string().contains("available: Standup"));
+}
+
+#[test]
+fn long_transcripts_split_on_paragraphs_then_sentences() {
+    let transcript = "spk_0: First topic.\n\nspk_1: Second topic. It goes on and on.";
+    assert_eq!(
+        summarize::split_transcript(transcript, 30),
+        ["spk_0: First topic.", "spk_1: Second topic.", "It goes on and on."]
+    );
+    assert_eq!(summarize::split_transcript("short", 40), ["short"]);
+    assert_eq!(summarize::split_transcript("abcdefgh", 3), ["abc", "def", "gh"]);
+}
+
+#[test]
+fn text_splits_into_byte_limited_chunks_on_lines_then_words() {
+    assert_eq!(
+        summarize::split_into_chunks("- First item\n- Second item here", 20),
+        ["- First item", "- Second item here"]
+    );
+    assert_eq!(summarize::split_into_chunks("one two three", 9), ["one two", "three"]);
+    // Multi-byte characters are never cut in half
+    assert_eq!(summarize::split_into_chunks("äöü", 3), ["ä", "ö", "ü"]);
+}
+
+#[test]
+fn max_input_chars_defaults_to_a_multiple_of_max_tokens() {
+    let mut settings = Settings::default();
+    assert_eq!(summarize::max_input_chars(&settings), 100_000);
+
+    settings.model.max_tokens = Some(4096);
+    assert_eq!(summarize::max_input_chars(&settings), 204_800);
+
+    settings.model.max_input_chars = Some(20_000);
+    assert_eq!(summarize::max_input_chars(&settings), 20_000);
+}
+
+#[test]
+fn summary_state_key_covers_the_system_prompt_provider_and_sampling() {
+    let settings = Settings::default();
+    let options = PromptOptions::default();
+    let key = |settings: &Settings| summarize::SummaryState::new(settings, &options, "spk_0: Hello.").key;
+    let base = key(&settings);
+    assert_eq!(key(&settings.clone()), base);
+
+    let mut changed = settings.clone();
+    changed.anthropic.system = "You are a terse assistant.".to_string();
+    assert_ne!(key(&changed), base);
+
+    let mut changed = settings.clone();
+    changed.model.provider = "meta".to_string();
+    assert_ne!(key(&changed), base);
+
+    let mut changed = settings.clone();
+    changed.model.temperature = Some(0.1);
+    assert_ne!(key(&changed), base);
+
+    let mut changed = settings.clone();
+    changed.model.top_k = Some(40);
+    assert_ne!(key(&changed), base);
+
+    let mut changed = settings;
+    changed.model.max_tokens = Some(512);
+    assert_ne!(key(&changed), base);
+}
+
+/// Serves every InvokeModel request with the same summary and records the request bodies
+///
+/// # Returns
+///
+/// The endpoint URL and the bodies received so far
+fn mock_bedrock() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&bodies);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            received.lock().unwrap().push(String::from_utf8(body).unwrap());
+
+            let response = json!({"content": [{"type": "text", "text": "Part summary."}], "stop_reason": "end_turn"});
+            let response = response.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+    (url, bodies)
+}
+
+#[tokio::test]
+async fn resumed_long_summaries_only_request_the_missing_chunks() {
+    let (endpoint_url, bodies) = mock_bedrock();
+    let settings = Settings {
+        model: ModelSettings {
+            model_id: "anthropic.claude-3-haiku-20240307-v1:0".to_string(),
+            endpoint_url: Some(endpoint_url),
+            max_input_chars: Some(40),
+            max_retries: 0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let transcript = format!(
+        "spk_0: First topic.\n\nspk_1: Second topic.\n\nspk_0: Third topic, run {}.",
+        std::process::id()
+    );
+    assert_eq!(summarize::split_transcript(&transcript, 40).len(), 3);
+    let options = PromptOptions::default();
+
+    // An earlier run got through the first chunk before it was interrupted
+    let mut state = summarize::SummaryState::new(&settings, &options, &transcript);
+    state.chunk_summaries = vec!["First part summary.".to_string()];
+    state.save().unwrap();
+    let mut state = summarize::SummaryState::new(&settings, &options, &transcript).load();
+    assert_eq!(state.chunk_summaries.len(), 1);
+
+    let config = aws_config::defaults(BehaviorVersion::latest())
+        .region(Region::new("us-east-1"))
+        .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+        .load()
+        .await;
+    let mut spinner = spinoff::Spinner::new(spinoff::spinners::Dots, "", None);
+    let summary = summarize::summarize_text(&config, &settings, &transcript, &options, Some(&mut state), &mut spinner)
+        .await
+        .unwrap();
+    spinner.clear();
+
+    assert_eq!(summary, "Part summary.");
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 3, "{:#?}", bodies);
+    assert!(bodies[0].contains("part 2 of 3"), "{}", bodies[0]);
+    assert!(bodies[1].contains("part 3 of 3"), "{}", bodies[1]);
+    assert!(bodies[2].contains("First part summary."), "{}", bodies[2]);
+    assert!(!bodies.iter().any(|body| body.contains("part 1 of 3")));
+
+    let saved = summarize::SummaryState::new(&settings, &options, &transcript).load();
+    assert_eq!(saved.chunk_summaries, ["First part summary.", "Part summary.", "Part summary."]);
+    saved.clear();
+}
+
+#[test]
+fn streamed_summaries_are_not_printed_twice() {
+    let streamed = "- Ship on Friday";
+    assert_eq!(summarize::streamed_reprint(None, streamed), StreamedReprint::Full);
+    assert_eq!(summarize::streamed_reprint(Some(streamed), streamed), StreamedReprint::Nothing);
+    assert_eq!(
+        summarize::streamed_reprint(Some(streamed), "- Ship on Friday\n\nSentiment: Positive"),
+        StreamedReprint::Appended("Sentiment: Positive".to_string())
+    );
+    assert_eq!(
+        summarize::streamed_reprint(Some(streamed), "## Decisions\n- Ship on Friday"),
+        StreamedReprint::Adjusted
+    );
+}
@@ -0,0 +1,201 @@
+//! Tests for turning saved Amazon Transcribe results into transcripts and cleaning them up.
+//!
+//! The fixtures in `tests/fixtures` are trimmed-down results of real job types:
+//! - `plain.json`: no speaker diarization
+//! - `diarized.json`: speaker labels (`spk_N`)
+//! - `multi_channel.json`: channel identification (`ch_N`)
+
+use std::path::{Path, PathBuf};
+
+use distill_cli::transcribe;
+use serde_json::Value;
+
+fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn fixture(name: &str) -> Value {
+    let contents = std::fs::read_to_string(fixture_path(name)).expect("fixture should be readable");
+    serde_json::from_str(&contents).expect("fixture should be valid JSON")
+}
+
+#[test]
+fn plain_result_is_attributed_to_one_speaker() {
+    let transcript = transcribe::format_transcript(&fixture("plain.json")).unwrap();
+
+    assert_eq!(transcript, "spk_0: Welcome to the weekly sync. Let's start.\n");
+}
+
+#[test]
+fn diarized_result_starts_a_line_per_speaker_turn() {
+    let transcript = transcribe::format_transcript(&fixture("diarized.json")).unwrap();
+
+    assert_eq!(
+        transcript,
+        "spk_0: Is the release ready?\n\
+         spk_1: Almost, one test is failing.\n\
+         spk_0: Okay, thanks.\n"
+    );
+}
+
+#[test]
+fn multi_channel_result_merges_channels_in_time_order() {
+    let transcript = transcribe::format_transcript(&fixture("multi_channel.json")).unwrap();
+
+    assert_eq!(
+        transcript,
+        "ch_0: Can you hear me?\n\
+         ch_1: Yes, loud and clear.\n\
+         ch_0: Great.\n"
+    );
+}
+
+#[test]
+fn string_converters_match_the_parsed_formatters() {
+    let diarized = std::fs::read_to_string(fixture_path("diarized.json")).unwrap();
+    let channels = std::fs::read_to_string(fixture_path("multi_channel.json")).unwrap();
+
+    assert_eq!(
+        transcribe::convert_transcribe_json(&diarized).unwrap(),
+        transcribe::format_speaker_transcript(&fixture("diarized.json")).unwrap()
+    );
+    assert_eq!(
+        transcribe::convert_channel_transcribe_json(&channels).unwrap(),
+        transcribe::format_channel_transcript(&fixture("multi_channel.json")).unwrap()
+    );
+}
+
+#[test]
+fn result_without_items_is_an_error() {
+    let result: Value = serde_json::json!({ "results": { "transcripts": [] } });
+
+    assert!(transcribe::format_transcript(&result).is_err());
+}
+
+#[test]
+fn load_transcription_detects_channel_results() {
+    let transcription = transcribe::load_transcription(&fixture_path("multi_channel.json")).unwrap();
+
+    assert!(transcription.text.starts_with("ch_0: Can you hear me?"));
+    assert!(transcription.json.is_some());
+    assert_eq!(transcription.identified_language, None);
+}
+
+#[test]
+fn timed_transcript_prefixes_each_turn_with_its_start_time() {
+    let json = std::fs::read_to_string(fixture_path("diarized.json")).unwrap();
+
+    assert_eq!(
+        transcribe::timed_transcript(&json).unwrap(),
+        "[00:00:00] spk_0: Is the release ready?\n\
+         [00:00:01] spk_1: Almost, one test is failing.\n\
+         [00:00:03] spk_0: Okay, thanks.\n"
+    );
+}
+
+#[test]
+fn average_confidence_ignores_punctuation() {
+    let json = std::fs::read_to_string(fixture_path("plain.json")).unwrap();
+    let confidence = transcribe::average_confidence(&json).unwrap();
+
+    assert!((confidence - 0.9891).abs() < 1e-3, "unexpected confidence {}", confidence);
+}
+
+#[test]
+fn sentences_format_starts_a_line_per_sentence() {
+    let transcript =
+        transcribe::format_transcript_as(&fixture("plain.json"), transcribe::TranscriptFormat::Sentences).unwrap();
+
+    assert_eq!(transcript, "spk_0: Welcome to the weekly sync.\nspk_0: Let's start.\n");
+}
+
+#[test]
+fn paragraphs_format_breaks_at_turns_and_long_pauses() {
+    let diarized =
+        transcribe::format_transcript_as(&fixture("diarized.json"), transcribe::TranscriptFormat::Paragraphs).unwrap();
+    assert_eq!(
+        diarized,
+        "spk_0: Is the release ready?\n\nspk_1: Almost, one test is failing.\n\nspk_0: Okay, thanks.\n"
+    );
+
+    // Move "Let's start." well past the pause threshold
+    let mut plain = fixture("plain.json");
+    for item in plain["results"]["items"].as_array_mut().unwrap() {
+        for field in ["start_time", "end_time"] {
+            if let Some(time) = item[field].as_str().and_then(|t| t.parse::<f64>().ok()).filter(|t| *t > 1.8) {
+                item[field] = format!("{:.2}", time + transcribe::PARAGRAPH_PAUSE_SECS).into();
+            }
+        }
+    }
+    assert_eq!(
+        transcribe::format_transcript_as(&plain, transcribe::TranscriptFormat::Paragraphs).unwrap(),
+        "spk_0: Welcome to the weekly sync.\n\nspk_0: Let's start.\n"
+    );
+}
+
+#[test]
+fn raw_format_matches_format_transcript() {
+    let result = fixture("multi_channel.json");
+
+    assert_eq!(
+        transcribe::format_transcript_as(&result, transcribe::TranscriptFormat::Raw).unwrap(),
+        transcribe::format_transcript(&result).unwrap()
+    );
+}
+
+#[test]
+fn speakers_are_read_from_label_segments_when_items_have_none() {
+    let mut result = fixture("diarized.json");
+    for item in result["results"]["items"].as_array_mut().unwrap() {
+        item.as_object_mut().unwrap().remove("speaker_label");
+    }
+
+    assert_eq!(
+        transcribe::format_transcript(&result).unwrap(),
+        "spk_0: Is the release ready?\n\
+         spk_1: Almost, one test is failing.\n\
+         spk_0: Okay, thanks.\n"
+    );
+}
+
+#[test]
+fn max_speaker_labels_defaults_to_ten_within_transcribe_limits() {
+    assert_eq!(transcribe::max_speaker_labels(None).unwrap(), 10);
+    assert_eq!(transcribe::max_speaker_labels(Some(4)).unwrap(), 4);
+    assert!(transcribe::max_speaker_labels(Some(1)).is_err());
+    assert!(transcribe::max_speaker_labels(Some(31)).is_err());
+}
+
+#[test]
+fn word_timings_attach_punctuation_to_the_preceding_word() {
+    let json = std::fs::read_to_string(fixture_path("diarized.json")).unwrap();
+    let words = transcribe::word_timings(&json).unwrap();
+
+    assert_eq!(
+        words[..4].iter().map(|timing| timing.word.as_str()).collect::<Vec<_>>(),
+        ["Is", "the", "release", "ready?"]
+    );
+    assert_eq!(
+        words[3],
+        transcribe::WordTiming { word: "ready?".to_string(), start: 0.9, end: 1.3, confidence: 0.993 }
+    );
+    assert_eq!(
+        serde_json::to_value(&words[0]).unwrap(),
+        serde_json::json!({ "word": "Is", "start": 0.1, "end": 0.3, "confidence": 0.998 })
+    );
+}
+
+#[test]
+fn dedupe_collapses_stutters_within_a_sentence_only() {
+    let fillers = ["um".to_string(), "you know".to_string()];
+    let dedupe = |text: &str| transcribe::dedupe_transcript(text, &fillers);
+
+    assert_eq!(dedupe("spk_0: I I think we um ship it it."), "spk_0: I think we ship it.\n");
+    assert_eq!(dedupe("spk_1: Let's you know move on, move on to the budget"), "spk_1: Let's move on, move on to the budget\n");
+    assert_eq!(dedupe("spk_0: the plan the plan is fine"), "spk_0: the plan is fine\n");
+
+    // Repetitions across a comma or sentence end, and intended doubles, are kept
+    assert_eq!(dedupe("spk_0: It was bad. Bad weather."), "spk_0: It was bad. Bad weather.\n");
+    assert_eq!(dedupe("spk_1: No, no, that's fine"), "spk_1: No, no, that's fine\n");
+    assert_eq!(dedupe("spk_0: I know that that works"), "spk_0: I know that that works\n");
+}